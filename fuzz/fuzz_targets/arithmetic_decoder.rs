@@ -0,0 +1,36 @@
+#![no_main]
+
+use heif::cabac::ArithmeticDecoderEngine;
+use heif::hevc::RbspReader;
+use libfuzzer_sys::fuzz_target;
+
+// input is [slice_qp, op count, ops.., bitstream..]. each op is two bytes: the low bits of the
+// first pick a decoding process, and for context-coded bins the rest of it is ctxTable and the
+// second byte is ctxIdx
+fuzz_target!(|data: &[u8]| {
+    let [slice_qp, op_count, rest @ ..] = data else {
+        return;
+    };
+
+    let (ops, bitstream) = rest.split_at((*op_count as usize * 2).min(rest.len()));
+
+    let Ok(mut engine) = ArithmeticDecoderEngine::try_new(RbspReader::new(bitstream)) else {
+        return;
+    };
+    engine.init_all_contexts(*slice_qp as i32 - 64);
+
+    for op in ops.chunks_exact(2) {
+        let [op, ctx_idx] = [op[0], op[1]];
+
+        let result = match op & 0b11 {
+            0 => engine.decode_bin((op >> 2) as usize, ctx_idx as usize, false),
+            1 => engine.decode_bypass(),
+            2 => engine.decode_terminate(),
+            _ => engine.byte_alignment().map(|()| false),
+        };
+
+        if result.is_err() {
+            break;
+        }
+    }
+});
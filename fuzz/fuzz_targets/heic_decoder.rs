@@ -0,0 +1,52 @@
+#![no_main]
+
+use heif::heic::{JpegItemDecoder, MaskItemDecoder, UncompressedItemDecoder};
+use heif::{DecodeBudget, DecodeLimits, HeicDecoder, HeifReader, ItemDecoder, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+// small enough that a crafted header can't run the fuzzer out of memory before it's rejected
+const LIMITS: DecodeLimits = DecodeLimits {
+    max_items: 64,
+    max_properties: 64,
+    max_extents: 8,
+    max_image_pixels: 1 << 20,
+    max_grid_tiles: 16,
+    max_total_allocation: 64 << 20,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let options = ParseOptions {
+        limits: LIMITS,
+        ..Default::default()
+    };
+    let decoder = HeicDecoder::default();
+
+    let _ = decoder.decode_primary(data, options);
+    let _ = decoder.decode_hdr(data, options, 4.0);
+    let _ = decoder.decode_progressive(data, options);
+
+    let mut reader = HeifReader::with_options(data, options);
+    let Ok(heif) = reader.read() else {
+        return;
+    };
+
+    // every item goes through every built-in decoder, whatever its type
+    let decoders: [&dyn ItemDecoder; 3] =
+        [&UncompressedItemDecoder, &JpegItemDecoder, &MaskItemDecoder];
+
+    for meta in heif.meta_boxes() {
+        for reference in &meta.item_location.references {
+            let item_id = reference.item_id;
+            let _ = reader.read_region_item(item_id, meta);
+
+            let Ok(item_data) = reader.get_item_data(item_id, meta) else {
+                continue;
+            };
+            let properties = meta.properties_of(item_id).collect::<Vec<_>>();
+
+            for decoder in decoders {
+                let _ = decoder.decode(&item_data, &properties, &mut DecodeBudget::new(LIMITS));
+            }
+        }
+    }
+});
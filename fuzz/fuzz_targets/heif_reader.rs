@@ -0,0 +1,26 @@
+#![no_main]
+
+use heif::{HeifReader, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = HeifReader::new(data);
+
+    if let Ok(heif) = reader.read() {
+        for meta in heif.meta_boxes() {
+            for reference in &meta.item_location.references {
+                let _ = reader.get_item_data(reference.item_id, meta);
+            }
+        }
+
+        for sample in heif.tracks().iter().flat_map(|track| &track.samples) {
+            let _ = reader.get_sample_data(sample);
+        }
+    }
+
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let _ = HeifReader::with_options(data, strict).read();
+});
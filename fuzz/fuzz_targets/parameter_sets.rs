@@ -0,0 +1,22 @@
+#![no_main]
+
+use heif::hevc::{
+    RbspReader, picture_parameter_set_rbsp, sei_messages, sequence_parameter_set_rbsp,
+    video_parameter_set_rbsp,
+};
+use heif::vvc;
+use libfuzzer_sys::fuzz_target;
+
+// input is a parameter set or SEI NAL unit without its 2 byte header, as stored in hvcC and vvcC
+fuzz_target!(|data: &[u8]| {
+    let rbsp = RbspReader::remove_emulation_prevention(data);
+
+    let _ = video_parameter_set_rbsp(&rbsp);
+    let _ = sequence_parameter_set_rbsp(&rbsp);
+    let _ = picture_parameter_set_rbsp(&rbsp);
+    let _ = sei_messages(&rbsp);
+
+    let _ = vvc::video_parameter_set_rbsp(&rbsp);
+    let _ = vvc::sequence_parameter_set_rbsp(&rbsp);
+    let _ = vvc::picture_parameter_set_rbsp(&rbsp);
+});
@@ -0,0 +1,40 @@
+#![no_main]
+
+use std::sync::LazyLock;
+
+use heif::hevc::{
+    NalUnitHeader, PictureParameterSet, RbspReader, SequenceParameterSet, SliceSegmentReader,
+    picture_parameter_set_rbsp, sequence_parameter_set_rbsp,
+};
+use libfuzzer_sys::fuzz_target;
+
+// slices are parsed against the parameter sets of halfmoonbay.heic
+static PARAMETER_SETS: LazyLock<(SequenceParameterSet, PictureParameterSet)> =
+    LazyLock::new(|| {
+        let sps = RbspReader::remove_emulation_prevention(include_bytes!(
+            "../corpus/parameter_sets/halfmoonbay_sps"
+        ));
+        let pps = RbspReader::remove_emulation_prevention(include_bytes!(
+            "../corpus/parameter_sets/halfmoonbay_pps"
+        ));
+
+        (
+            sequence_parameter_set_rbsp(&sps).expect("valid sps"),
+            picture_parameter_set_rbsp(&pps).expect("valid pps"),
+        )
+    });
+
+// input is a slice segment NAL unit: 2 byte header followed by the payload
+fuzz_target!(|data: &[u8]| {
+    let [header_1, header_2, payload @ ..] = data else {
+        return;
+    };
+
+    let header = NalUnitHeader(u16::from_be_bytes([*header_1, *header_2]));
+    let rbsp = RbspReader::remove_emulation_prevention(payload);
+    let (sps, pps) = &*PARAMETER_SETS;
+
+    if let Ok(mut reader) = SliceSegmentReader::try_new(&rbsp, header, sps, pps) {
+        let _ = reader.read_data();
+    }
+});
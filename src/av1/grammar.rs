@@ -0,0 +1,113 @@
+use crate::hevc::{ColorPrimaries, MatrixCoefficients, TransferCharacteristics};
+
+/// `av1C`, AV1-ISOBMFF 2.3.3
+#[derive(Debug)]
+pub struct AV1CodecConfigurationRecord {
+    pub version: u8,
+    pub seq_profile: u8,
+    pub seq_level_idx_0: u8,
+    pub seq_tier_0: bool,
+    pub high_bitdepth: bool,
+    pub twelve_bit: bool,
+    pub monochrome: bool,
+    pub chroma_subsampling_x: bool,
+    pub chroma_subsampling_y: bool,
+    pub chroma_sample_position: u8,
+    pub initial_presentation_delay_minus_one: Option<u8>,
+    /// the sequence header OBU from `configOBUs`, when the writer included one
+    pub sequence_header: Option<SequenceHeader>,
+}
+
+impl AV1CodecConfigurationRecord {
+    pub const fn bit_depth(&self) -> u8 {
+        match (self.high_bitdepth, self.twelve_bit) {
+            (false, _) => 8,
+            (true, false) => 10,
+            (true, true) => 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObuKind {
+    SequenceHeader,
+    TemporalDelimiter,
+    FrameHeader,
+    TileGroup,
+    Metadata,
+    Frame,
+    RedundantFrameHeader,
+    TileList,
+    Padding,
+    Reserved(u8),
+}
+
+impl From<u8> for ObuKind {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::SequenceHeader,
+            2 => Self::TemporalDelimiter,
+            3 => Self::FrameHeader,
+            4 => Self::TileGroup,
+            5 => Self::Metadata,
+            6 => Self::Frame,
+            7 => Self::RedundantFrameHeader,
+            8 => Self::TileList,
+            15 => Self::Padding,
+            other => Self::Reserved(other),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ObuHeader {
+    pub kind: ObuKind,
+    pub has_size_field: bool,
+    /// (temporal_id, spatial_id), from obu_extension_header
+    pub extension: Option<(u8, u8)>,
+}
+
+/// AV1 5.5.1, without the operating point and decoder model details
+#[derive(Debug)]
+pub struct SequenceHeader {
+    pub seq_profile: u8,
+    pub still_picture: bool,
+    pub reduced_still_picture_header: bool,
+    pub timing_info_present_flag: bool,
+    pub operating_points_cnt_minus_1: u8,
+    /// seq_level_idx of the first operating point
+    pub seq_level_idx_0: u8,
+    pub max_frame_width_minus_1: u32,
+    pub max_frame_height_minus_1: u32,
+    pub use_128x128_superblock: bool,
+    pub enable_superres: bool,
+    pub enable_cdef: bool,
+    pub enable_restoration: bool,
+    pub color_config: ColorConfig,
+    pub film_grain_params_present: bool,
+}
+
+impl SequenceHeader {
+    pub const fn max_frame_width(&self) -> u32 {
+        self.max_frame_width_minus_1 + 1
+    }
+
+    pub const fn max_frame_height(&self) -> u32 {
+        self.max_frame_height_minus_1 + 1
+    }
+}
+
+/// AV1 5.5.2
+#[derive(Debug)]
+pub struct ColorConfig {
+    pub bit_depth: u8,
+    pub mono_chrome: bool,
+    pub color_primaries: ColorPrimaries,
+    pub transfer_characteristics: TransferCharacteristics,
+    pub matrix_coefficients: MatrixCoefficients,
+    pub color_range: bool,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    pub chroma_sample_position: u8,
+    pub separate_uv_delta_q: bool,
+}
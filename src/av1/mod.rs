@@ -0,0 +1,5 @@
+pub mod grammar;
+mod obu_reader;
+
+pub use grammar::*;
+pub use obu_reader::*;
@@ -0,0 +1,339 @@
+use crate::av1::{ColorConfig, ObuHeader, ObuKind, SequenceHeader};
+use crate::hevc::{ColorPrimaries, MatrixCoefficients, RbspReader, TransferCharacteristics};
+use anyhow::{Result, bail, ensure};
+
+/// Splits a sequence of OBUs, as found in `configOBUs` or item data, into headers and payloads.
+pub fn read_obus(mut data: &[u8]) -> Result<Vec<(ObuHeader, &[u8])>> {
+    let mut obus = Vec::new();
+
+    while !data.is_empty() {
+        let mut reader = RbspReader::new(data);
+        let header = obu_header(&mut reader)?;
+
+        let header_size = reader.byte_position();
+        let (payload_size, size_field) = if header.has_size_field {
+            leb128(&data[header_size..])?
+        } else {
+            (data.len() - header_size, 0)
+        };
+
+        let start = header_size + size_field;
+        let end = start
+            .checked_add(payload_size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| anyhow::anyhow!("obu_size {payload_size} runs past the end of data"))?;
+
+        obus.push((header, &data[start..end]));
+        data = &data[end..];
+    }
+
+    Ok(obus)
+}
+
+fn obu_header(reader: &mut RbspReader) -> Result<ObuHeader> {
+    ensure!(!reader.read_flag()?, "obu_forbidden_bit must be 0");
+
+    let kind = ObuKind::from(reader.read_u8(4)?);
+    let obu_extension_flag = reader.read_flag()?;
+    let has_size_field = reader.read_flag()?;
+    let _obu_reserved_1bit = reader.read_flag()?;
+
+    let extension = obu_extension_flag
+        .then(|| -> Result<_> {
+            let temporal_id = reader.read_u8(3)?;
+            let spatial_id = reader.read_u8(2)?;
+            let _extension_header_reserved_3bits = reader.read_u8(3)?;
+
+            Ok((temporal_id, spatial_id))
+        })
+        .transpose()?;
+
+    Ok(ObuHeader {
+        kind,
+        has_size_field,
+        extension,
+    })
+}
+
+// 4.10.5, returns (value, bytes read)
+fn leb128(data: &[u8]) -> Result<(usize, usize)> {
+    let mut value = 0u64;
+
+    for (i, &byte) in data.iter().take(8).enumerate() {
+        value |= ((byte & 0x7F) as u64) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            ensure!(
+                value <= u32::MAX as u64,
+                "leb128 value {value} exceeds 32 bits"
+            );
+            return Ok((value as usize, i + 1));
+        }
+    }
+
+    bail!("unterminated leb128")
+}
+
+// 4.10.3
+fn uvlc(reader: &mut RbspReader) -> Result<u32> {
+    let mut leading_zeros = 0;
+    while !reader.read_flag()? {
+        leading_zeros += 1;
+        ensure!(leading_zeros < 32, "uvlc code exceeds 32 bits");
+    }
+
+    Ok(reader.read_u32(leading_zeros)? + ((1 << leading_zeros) - 1))
+}
+
+pub fn sequence_header_obu(data: &[u8]) -> Result<SequenceHeader> {
+    let mut reader = RbspReader::new(data);
+
+    let seq_profile = reader.read_u8(3)?;
+    ensure!(seq_profile <= 2, "unsupported seq_profile {seq_profile}");
+
+    let still_picture = reader.read_flag()?;
+    let reduced_still_picture_header = reader.read_flag()?;
+
+    let (timing_info_present_flag, operating_points_cnt_minus_1, seq_level_idx_0) =
+        if reduced_still_picture_header {
+            (false, 0, reader.read_u8(5)?)
+        } else {
+            operating_points(&mut reader)?
+        };
+
+    let frame_width_bits_minus_1 = reader.read_u8(4)?;
+    let frame_height_bits_minus_1 = reader.read_u8(4)?;
+    let max_frame_width_minus_1 = reader.read_u32(frame_width_bits_minus_1 as usize + 1)?;
+    let max_frame_height_minus_1 = reader.read_u32(frame_height_bits_minus_1 as usize + 1)?;
+
+    let frame_id_numbers_present_flag = !reduced_still_picture_header && reader.read_flag()?;
+    if frame_id_numbers_present_flag {
+        let _delta_frame_id_length_minus_2 = reader.read_u8(4)?;
+        let _additional_frame_id_length_minus_1 = reader.read_u8(3)?;
+    }
+
+    let use_128x128_superblock = reader.read_flag()?;
+    let _enable_filter_intra = reader.read_flag()?;
+    let _enable_intra_edge_filter = reader.read_flag()?;
+
+    if !reduced_still_picture_header {
+        let _enable_interintra_compound = reader.read_flag()?;
+        let _enable_masked_compound = reader.read_flag()?;
+        let _enable_warped_motion = reader.read_flag()?;
+        let _enable_dual_filter = reader.read_flag()?;
+
+        let enable_order_hint = reader.read_flag()?;
+        if enable_order_hint {
+            let _enable_jnt_comp = reader.read_flag()?;
+            let _enable_ref_frame_mvs = reader.read_flag()?;
+        }
+
+        let seq_choose_screen_content_tools = reader.read_flag()?;
+        let seq_force_screen_content_tools = if seq_choose_screen_content_tools {
+            2 // SELECT_SCREEN_CONTENT_TOOLS
+        } else {
+            reader.read_u8(1)?
+        };
+
+        if seq_force_screen_content_tools > 0 {
+            let seq_choose_integer_mv = reader.read_flag()?;
+            if !seq_choose_integer_mv {
+                let _seq_force_integer_mv = reader.read_flag()?;
+            }
+        }
+
+        if enable_order_hint {
+            let _order_hint_bits_minus_1 = reader.read_u8(3)?;
+        }
+    }
+
+    let enable_superres = reader.read_flag()?;
+    let enable_cdef = reader.read_flag()?;
+    let enable_restoration = reader.read_flag()?;
+    let color_config = color_config(&mut reader, seq_profile)?;
+    let film_grain_params_present = reader.read_flag()?;
+
+    Ok(SequenceHeader {
+        seq_profile,
+        still_picture,
+        reduced_still_picture_header,
+        timing_info_present_flag,
+        operating_points_cnt_minus_1,
+        seq_level_idx_0,
+        max_frame_width_minus_1,
+        max_frame_height_minus_1,
+        use_128x128_superblock,
+        enable_superres,
+        enable_cdef,
+        enable_restoration,
+        color_config,
+        film_grain_params_present,
+    })
+}
+
+// timing_info_present_flag through the operating points loop, returns (timing_info_present_flag,
+// operating_points_cnt_minus_1, seq_level_idx[0])
+fn operating_points(reader: &mut RbspReader) -> Result<(bool, u8, u8)> {
+    let timing_info_present_flag = reader.read_flag()?;
+
+    let mut decoder_model_info_present_flag = false;
+    let mut buffer_delay_length_minus_1 = 0;
+
+    if timing_info_present_flag {
+        // timing_info()
+        let _num_units_in_display_tick = reader.read_u32(32)?;
+        let _time_scale = reader.read_u32(32)?;
+        if reader.read_flag()? {
+            let _num_ticks_per_picture_minus_1 = uvlc(reader)?;
+        }
+
+        decoder_model_info_present_flag = reader.read_flag()?;
+        if decoder_model_info_present_flag {
+            // decoder_model_info()
+            buffer_delay_length_minus_1 = reader.read_u8(5)?;
+            let _num_units_in_decoding_tick = reader.read_u32(32)?;
+            let _buffer_removal_time_length_minus_1 = reader.read_u8(5)?;
+            let _frame_presentation_time_length_minus_1 = reader.read_u8(5)?;
+        }
+    }
+
+    let initial_display_delay_present_flag = reader.read_flag()?;
+    let operating_points_cnt_minus_1 = reader.read_u8(5)?;
+    let mut seq_level_idx_0 = 0;
+
+    for i in 0..=operating_points_cnt_minus_1 {
+        let _operating_point_idc = reader.read_u32(12)?;
+        let seq_level_idx = reader.read_u8(5)?;
+        if seq_level_idx > 7 {
+            let _seq_tier = reader.read_flag()?;
+        }
+
+        if i == 0 {
+            seq_level_idx_0 = seq_level_idx;
+        }
+
+        if decoder_model_info_present_flag && reader.read_flag()? {
+            // operating_parameters_info()
+            let n = buffer_delay_length_minus_1 as usize + 1;
+            let _decoder_buffer_delay = reader.read_u32(n)?;
+            let _encoder_buffer_delay = reader.read_u32(n)?;
+            let _low_delay_mode_flag = reader.read_flag()?;
+        }
+
+        if initial_display_delay_present_flag && reader.read_flag()? {
+            let _initial_display_delay_minus_1 = reader.read_u8(4)?;
+        }
+    }
+
+    Ok((
+        timing_info_present_flag,
+        operating_points_cnt_minus_1,
+        seq_level_idx_0,
+    ))
+}
+
+fn color_config(reader: &mut RbspReader, seq_profile: u8) -> Result<ColorConfig> {
+    let high_bitdepth = reader.read_flag()?;
+    let bit_depth = match (seq_profile, high_bitdepth) {
+        (2, true) => {
+            if reader.read_flag()? {
+                12
+            } else {
+                10
+            }
+        }
+        (_, true) => 10,
+        (_, false) => 8,
+    };
+
+    let mono_chrome = seq_profile != 1 && reader.read_flag()?;
+
+    let color_description_present_flag = reader.read_flag()?;
+    let (color_primaries, transfer_characteristics, matrix_coefficients) =
+        if color_description_present_flag {
+            (reader.read_u8(8)?, reader.read_u8(8)?, reader.read_u8(8)?)
+        } else {
+            // CP_UNSPECIFIED, TC_UNSPECIFIED, MC_UNSPECIFIED
+            (2, 2, 2)
+        };
+
+    let mut config = ColorConfig {
+        bit_depth,
+        mono_chrome,
+        color_primaries: ColorPrimaries::from(color_primaries),
+        transfer_characteristics: TransferCharacteristics::from(transfer_characteristics),
+        matrix_coefficients: MatrixCoefficients::from(matrix_coefficients),
+        color_range: false,
+        subsampling_x: true,
+        subsampling_y: true,
+        // CSP_UNKNOWN
+        chroma_sample_position: 0,
+        separate_uv_delta_q: false,
+    };
+
+    if mono_chrome {
+        config.color_range = reader.read_flag()?;
+        return Ok(config);
+    }
+
+    // sRGB: BT.709 primaries, sRGB transfer and identity matrix is always full range 4:4:4
+    if (
+        color_primaries,
+        transfer_characteristics,
+        matrix_coefficients,
+    ) == (1, 13, 0)
+    {
+        config.color_range = true;
+        config.subsampling_x = false;
+        config.subsampling_y = false;
+    } else {
+        config.color_range = reader.read_flag()?;
+
+        (config.subsampling_x, config.subsampling_y) = match seq_profile {
+            0 => (true, true),
+            1 => (false, false),
+            _ if bit_depth == 12 => {
+                let subsampling_x = reader.read_flag()?;
+                let subsampling_y = subsampling_x && reader.read_flag()?;
+                (subsampling_x, subsampling_y)
+            }
+            _ => (true, false),
+        };
+
+        if config.subsampling_x && config.subsampling_y {
+            config.chroma_sample_position = reader.read_u8(2)?;
+        }
+    }
+
+    config.separate_uv_delta_q = reader.read_flag()?;
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reduced_still_picture_sequence_header() {
+        // a 64x48 8-bit 4:2:0 still: profile 0, level 0, reduced_still_picture_header and
+        // 16-bit frame size fields
+        let obu = [
+            0x0A, 0x09, 0x18, 0x3F, 0xC0, 0x0F, 0xC0, 0x0B, 0xDB, 0x00, 0x80,
+        ];
+
+        let obus = read_obus(&obu).unwrap();
+        let [(header, payload)] = obus.as_slice() else {
+            panic!("expected one obu");
+        };
+        assert_eq!(header.kind, ObuKind::SequenceHeader);
+
+        let seq = sequence_header_obu(payload).unwrap();
+        assert!(seq.still_picture && seq.reduced_still_picture_header);
+        assert_eq!(seq.seq_profile, 0);
+        assert_eq!((seq.max_frame_width(), seq.max_frame_height()), (64, 48));
+        assert_eq!(seq.color_config.bit_depth, 8);
+        assert!(!seq.color_config.mono_chrome);
+        assert!(seq.color_config.subsampling_x && seq.color_config.subsampling_y);
+    }
+}
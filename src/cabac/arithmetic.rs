@@ -95,6 +95,8 @@ impl<'a> ArithmeticDecoderEngine<'a> {
     }
 
     fn decode_decision(&mut self, ctx_table: usize, ctx_idx: usize) -> Result<bool> {
+        self.ensure_not_terminated()?;
+
         let q_range_idx = (self.ivl_curr_range >> 6) & 3;
 
         let ctx_key = (ctx_table, ctx_idx);
@@ -134,6 +136,17 @@ impl<'a> ArithmeticDecoderEngine<'a> {
         Ok(bin_val)
     }
 
+    /// ivlOffset < ivlCurrRange holds between bins until end_of_slice_segment_flag terminates
+    /// decoding; reading further bins after that would underflow the range.
+    fn ensure_not_terminated(&self) -> Result<()> {
+        ensure!(
+            self.ivl_offset < self.ivl_curr_range,
+            "arithmetic decoding past a terminating bin"
+        );
+
+        Ok(())
+    }
+
     fn try_renorm(&mut self) -> Result<()> {
         while self.ivl_curr_range < 256 {
             self.ivl_curr_range <<= 1;
@@ -144,6 +157,8 @@ impl<'a> ArithmeticDecoderEngine<'a> {
     }
 
     pub fn decode_bypass(&mut self) -> Result<bool> {
+        self.ensure_not_terminated()?;
+
         self.ivl_offset <<= 1;
         self.ivl_offset |= self.reader.read_bits(1)? as u16;
 
@@ -157,6 +172,8 @@ impl<'a> ArithmeticDecoderEngine<'a> {
     }
 
     pub fn decode_terminate(&mut self) -> Result<bool> {
+        self.ensure_not_terminated()?;
+
         self.ivl_curr_range -= 2;
 
         if self.ivl_offset >= self.ivl_curr_range {
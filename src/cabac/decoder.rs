@@ -1,5 +1,5 @@
 use crate::{cabac::ArithmeticDecoderEngine, hevc::RbspReader};
-use anyhow::Result;
+use anyhow::{Result, anyhow, ensure};
 
 #[derive(Debug)]
 pub struct CabacDecoder<'a> {
@@ -113,6 +113,11 @@ impl<'a> CabacDecoder<'a> {
         c_idx: u8,
         log2_trafo_size: u8,
     ) -> Result<u16> {
+        ensure!(
+            (2..=5).contains(&log2_trafo_size),
+            "invalid log2TrafoSize {log2_trafo_size}"
+        );
+
         let (ctx_offset, ctx_shift) = if c_idx == 0 {
             let offset = 3 * (log2_trafo_size as usize - 2) + ((log2_trafo_size as usize - 1) >> 2);
             let shift = (log2_trafo_size as usize + 1) >> 2;
@@ -210,6 +215,7 @@ where
     let mut num_ones: u8 = 0;
     while get_bin()? {
         num_ones += 1;
+        ensure!(num_ones + k < 16, "EGk prefix too long for a 16 bit value");
     }
 
     let suffix_len = num_ones + k;
@@ -235,11 +241,12 @@ fn decode_coeff_abs_level_remaining<F>(
 where
     F: FnMut() -> Result<bool>,
 {
-    let threshold = 3 * (1u16 << state.c_last_rice_param);
+    let c_last_rice_param = state.c_last_rice_param.min(4);
+    let threshold = 3 * (1u16 << c_last_rice_param);
     let c_rice_param = if state.c_last_abs_level > threshold {
-        (state.c_last_rice_param + 1).min(4)
+        (c_last_rice_param + 1).min(4)
     } else {
-        state.c_last_rice_param
+        c_last_rice_param
     };
 
     let c_max = 4u16 << c_rice_param;
@@ -248,12 +255,16 @@ where
 
     let coeff_abs_level_remaining = if prefix_val == c_max {
         let suffix_val = decode_egk(c_rice_param + 1, &mut get_bin)?;
-        c_max + suffix_val
+        c_max
+            .checked_add(suffix_val)
+            .ok_or_else(|| anyhow!("coeff_abs_level_remaining overflows"))?
     } else {
         prefix_val
     };
 
-    let c_abs_level = base_level + coeff_abs_level_remaining;
+    let c_abs_level = base_level
+        .checked_add(coeff_abs_level_remaining)
+        .ok_or_else(|| anyhow!("coeff abs level overflows"))?;
     state.c_last_abs_level = c_abs_level;
     state.c_last_rice_param = c_rice_param;
 
@@ -277,7 +288,9 @@ where
 
     if prefix_val == 5 {
         let suffix_val = decode_egk(0, &mut get_bin)?;
-        return Ok(5 + suffix_val);
+        return 5u16
+            .checked_add(suffix_val)
+            .ok_or_else(|| anyhow!("cu_qp_delta_abs overflows"));
     }
 
     Ok(prefix_val)
@@ -0,0 +1,196 @@
+use std::fmt::{self, Display};
+
+use crate::heif::FourCC;
+
+/// Where an error was raised: the chain of boxes being parsed at the time, and the absolute
+/// byte offset into the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    pub box_path: Box<[FourCC]>,
+    pub offset: usize,
+}
+
+impl Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, kind) in self.box_path.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            write!(f, "{kind}")?;
+        }
+
+        write!(f, " @ {:#x}", self.offset)
+    }
+}
+
+#[derive(Debug)]
+pub enum HeifError {
+    /// The input ended before a box or field was complete.
+    Truncated {
+        at: ErrorLocation,
+        needed: usize,
+        available: usize,
+    },
+    /// A specific box was required here but a different one was found.
+    UnexpectedBox {
+        at: ErrorLocation,
+        expected: FourCC,
+        found: FourCC,
+    },
+    UnsupportedBox {
+        at: ErrorLocation,
+        kind: FourCC,
+    },
+    UnsupportedVersion {
+        at: ErrorLocation,
+        kind: FourCC,
+        version: u8,
+    },
+    UnsupportedCodec {
+        at: ErrorLocation,
+        codec: FourCC,
+    },
+    MissingBox {
+        at: ErrorLocation,
+        kind: FourCC,
+    },
+    MissingItem {
+        at: ErrorLocation,
+        item_id: u32,
+    },
+    InvalidBox {
+        at: ErrorLocation,
+        reason: String,
+    },
+    InvalidProperty {
+        at: ErrorLocation,
+        property: FourCC,
+        reason: String,
+    },
+    BitstreamError {
+        at: ErrorLocation,
+        reason: String,
+    },
+    LimitExceeded {
+        at: ErrorLocation,
+        limit: &'static str,
+        value: u64,
+        max: u64,
+    },
+    /// The item's `dref` entry points at data outside of what was read, e.g. another file.
+    UnresolvedDataReference {
+        at: ErrorLocation,
+        item_id: u32,
+        reference: String,
+    },
+    /// The item is protected (`ipro`), with `scheme` from its `schm` when known.
+    ProtectedItem {
+        at: ErrorLocation,
+        item_id: u32,
+        scheme: Option<FourCC>,
+    },
+}
+
+impl HeifError {
+    pub const fn location(&self) -> &ErrorLocation {
+        match self {
+            Self::Truncated { at, .. }
+            | Self::UnexpectedBox { at, .. }
+            | Self::UnsupportedBox { at, .. }
+            | Self::UnsupportedVersion { at, .. }
+            | Self::UnsupportedCodec { at, .. }
+            | Self::MissingBox { at, .. }
+            | Self::MissingItem { at, .. }
+            | Self::InvalidBox { at, .. }
+            | Self::InvalidProperty { at, .. }
+            | Self::BitstreamError { at, .. }
+            | Self::LimitExceeded { at, .. }
+            | Self::UnresolvedDataReference { at, .. }
+            | Self::ProtectedItem { at, .. } => at,
+        }
+    }
+
+    pub fn box_path(&self) -> &[FourCC] {
+        &self.location().box_path
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.location().offset
+    }
+
+    /// Recovers a `HeifError` raised somewhere inside an `anyhow` chain. Anything else (utf-8
+    /// failures, bitstream helpers that don't know about boxes) is reported as an invalid box at
+    /// `at`.
+    pub(crate) fn from_anyhow(err: anyhow::Error, at: impl FnOnce() -> ErrorLocation) -> Self {
+        match err.downcast::<Self>() {
+            Ok(err) => err,
+            Err(err) => Self::InvalidBox {
+                at: at(),
+                reason: format!("{err:#}"),
+            },
+        }
+    }
+}
+
+impl Display for HeifError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated {
+                at,
+                needed,
+                available,
+            } => write!(
+                f,
+                "truncated input at {at}: needed {needed} bytes, {available} available"
+            ),
+            Self::UnexpectedBox {
+                at,
+                expected,
+                found,
+            } => write!(f, "expected {expected} box at {at}, found {found}"),
+            Self::UnsupportedBox { at, kind } => write!(f, "unsupported box {kind} at {at}"),
+            Self::UnsupportedVersion { at, kind, version } => {
+                write!(f, "unsupported {kind} version {version} at {at}")
+            }
+            Self::UnsupportedCodec { at, codec } => write!(f, "unsupported codec {codec} at {at}"),
+            Self::MissingBox { at, kind } => write!(f, "missing required {kind} box in {at}"),
+            Self::MissingItem { at, item_id } => write!(f, "item {item_id} not found ({at})"),
+            Self::InvalidBox { at, reason } => write!(f, "invalid box at {at}: {reason}"),
+            Self::InvalidProperty {
+                at,
+                property,
+                reason,
+            } => write!(f, "invalid {property} property at {at}: {reason}"),
+            Self::BitstreamError { at, reason } => write!(f, "bitstream error at {at}: {reason}"),
+            Self::LimitExceeded {
+                at,
+                limit,
+                value,
+                max,
+            } => write!(f, "{limit} limit exceeded at {at}: {value} > {max}"),
+            Self::UnresolvedDataReference {
+                at,
+                item_id,
+                reference,
+            } => write!(
+                f,
+                "can't resolve the data of item {item_id}, which is in {reference} ({at})"
+            ),
+            Self::ProtectedItem {
+                at,
+                item_id,
+                scheme: Some(scheme),
+            } => write!(
+                f,
+                "item {item_id} is protected with scheme {scheme} and can't be decoded ({at})"
+            ),
+            Self::ProtectedItem {
+                at,
+                item_id,
+                scheme: None,
+            } => write!(f, "item {item_id} is protected and can't be decoded ({at})"),
+        }
+    }
+}
+
+impl std::error::Error for HeifError {}
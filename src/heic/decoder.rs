@@ -1,164 +1,941 @@
-use crate::heif::{HeifReader, ItemInfoEntry, ItemType};
-use crate::hevc::{
-    NalUnitHeader, NalUnitKind, RbspReader, SliceSegmentReader, picture_parameter_set_rbsp,
-    sequence_parameter_set_rbsp, video_parameter_set_rbsp,
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::error::HeifError;
+use crate::heic::{
+    HdrImage, HevcItemDecoder, ItemDecoder, JpegItemDecoder, LayeredHevcItemDecoder,
+    MaskItemDecoder, PlanarImage, UncompressedItemDecoder, apply_gain_map, find_gain_map,
+};
+use crate::heif::{
+    DataResolver, FourCC, Heif, HeifReader, ImageHandle, ItemType, MetaBox, ParseOptions,
 };
 use anyhow::{Result, anyhow, bail, ensure};
 
+/// Decodes the primary image of a HEIF file.
+///
+/// Coded items are handed to the `ItemDecoder` registered for their item type; derived images
+/// such as grids are assembled here from the decoded items they reference. `hvc1`, `lhv1`,
+/// `unci`, `jpeg` and `mski` are registered by default.
+pub struct HeicDecoder {
+    decoders: HashMap<FourCC, Box<dyn ItemDecoder>>,
+}
+
+/// What `HeicDecoder::decode_progressive` could show of a file that is still arriving.
 #[derive(Debug)]
-pub struct HeicDecoder;
+pub struct ProgressiveImage {
+    pub image: PlanarImage,
+    /// The item shown: the primary item, or a coarser rendition of it such as a thumbnail.
+    pub item_id: u32,
+    /// False for a grid decoded with some of its tiles still missing, which are left black.
+    pub complete: bool,
+}
+
+impl Default for HeicDecoder {
+    fn default() -> Self {
+        let mut decoder = Self {
+            decoders: HashMap::new(),
+        };
+        decoder.register(HevcItemDecoder);
+        decoder.register(LayeredHevcItemDecoder);
+        decoder.register(UncompressedItemDecoder);
+        decoder.register(JpegItemDecoder);
+        decoder.register(MaskItemDecoder);
+
+        decoder
+    }
+}
+
+impl Debug for HeicDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeicDecoder")
+            .field("decoders", &self.decoders.keys())
+            .finish()
+    }
+}
 
 impl HeicDecoder {
-    pub fn decode(data: &[u8]) -> Result<()> {
-        let mut reader = HeifReader::new(data);
+    pub fn decode(data: &[u8]) -> Result<PlanarImage, HeifError> {
+        Self::decode_with_options(data, ParseOptions::default())
+    }
+
+    pub fn decode_with_options(
+        data: &[u8],
+        options: ParseOptions,
+    ) -> Result<PlanarImage, HeifError> {
+        Self::default().decode_primary(data, options)
+    }
+
+    /// Registers `decoder` for its item type, replacing any decoder registered before.
+    pub fn register(&mut self, decoder: impl ItemDecoder + 'static) {
+        self.decoders.insert(decoder.item_type(), Box::new(decoder));
+    }
+
+    pub fn can_decode(&self, item_type: &ItemType) -> bool {
+        matches!(item_type, ItemType::Grid)
+            || self
+                .decoders
+                .get(&item_type.four_cc())
+                .is_some_and(|decoder| decoder.is_implemented())
+    }
+
+    pub fn decode_primary(
+        &self,
+        data: &[u8],
+        options: ParseOptions,
+    ) -> Result<PlanarImage, HeifError> {
+        let mut reader = HeifReader::with_options(data, options);
+        let heif = reader.read()?;
+
+        self.decode_primary_item(&mut reader, &heif)
+            .map_err(|err| HeifError::from_anyhow(err, || reader.location()))
+    }
+
+    /// Like `decode_primary`, for image sets whose items may be in other files, e.g. with a
+    /// `FileDataResolver` for the path `data` was read from.
+    pub fn decode_primary_with_resolver(
+        &self,
+        data: &[u8],
+        options: ParseOptions,
+        resolver: &dyn DataResolver,
+    ) -> Result<PlanarImage, HeifError> {
+        let mut reader = HeifReader::with_options(data, options).with_resolver(resolver);
         let heif = reader.read()?;
 
-        let hevc_config = heif
-            .hevc_configuration_record()
-            .ok_or_else(|| anyhow!("missing HEVC decoder configuration"))?;
+        self.decode_primary_item(&mut reader, &heif)
+            .map_err(|err| HeifError::from_anyhow(err, || reader.location()))
+    }
 
-        debug_assert_eq!(hevc_config.arrays.len(), 3, "more than 3 param sets found");
+    /// Decodes what can be shown so far of a file that is still arriving, as soon as its `meta`
+    /// is in: the finest complete rendition of the primary item, or the primary grid with the
+    /// tiles that are in once there are at least half of them. Renditions are taken in the order
+    /// of a `prgr` group containing the primary item, else its thumbnails from the smallest
+    /// up. `None` when there's nothing to show yet.
+    pub fn decode_progressive(
+        &self,
+        data: &[u8],
+        options: ParseOptions,
+    ) -> Result<Option<ProgressiveImage>, HeifError> {
+        let mut reader = HeifReader::with_options(
+            data,
+            ParseOptions {
+                partial: true,
+                ..options
+            },
+        );
+        let heif = reader.read()?;
 
-        // the order should _typically_ be VPS, SPS, PPS
-        // note does heif generally have 1 of each?
-        let vps = {
-            let b = hevc_config
-                .arrays
-                .iter()
-                .find(|a| matches!(a.nal_unit_type(), NalUnitKind::VPS))
-                .ok_or_else(|| anyhow!("no VPS in hvcC"))?
-                .nal_units
-                .first()
-                .ok_or_else(|| anyhow!("vps array is empty"))?;
-
-            let (_header, bitstream) = read_hvcc_nal_unit(&b.data)?;
-            video_parameter_set_rbsp(&bitstream)?
-        };
+        self.decode_progressive_item(&mut reader, &heif)
+            .map_err(|err| HeifError::from_anyhow(err, || reader.location()))
+    }
+
+    fn decode_progressive_item(
+        &self,
+        reader: &mut HeifReader,
+        heif: &Heif,
+    ) -> Result<Option<ProgressiveImage>> {
+        let meta = heif
+            .meta_box
+            .as_ref()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"meta"),
+            })?;
+
+        let primary_item_id = meta
+            .primary_item_id()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"pitm"),
+            })?;
 
-        dbg!(&vps);
+        let renditions = meta.progressive_order(primary_item_id).map_or_else(
+            || {
+                let mut thumbnails = meta
+                    .references_to(primary_item_id, b"thmb")
+                    .collect::<Vec<_>>();
+                thumbnails.sort_by_key(|&item_id| {
+                    meta.image(item_id)
+                        .and_then(|image| image.dimensions())
+                        .map_or(0, |(width, height)| width as u64 * height as u64)
+                });
+                thumbnails.push(primary_item_id);
 
-        let sps = {
-            let b = hevc_config
-                .arrays
+                thumbnails
+            },
+            <[u32]>::to_vec,
+        );
+
+        let complete = renditions
+            .iter()
+            .rposition(|&item_id| self.is_complete(reader, meta, item_id));
+        let primary = renditions
+            .iter()
+            .position(|&item_id| item_id == primary_item_id);
+
+        // a grid with half its tiles in says more than the renditions before it
+        if let Some(image) = meta.image(primary_item_id)
+            && matches!(image.item_type(), ItemType::Grid)
+            && complete < primary
+            && !image.is_protected()
+            && reader.is_item_available(primary_item_id, meta)
+        {
+            let tile_ids = meta.references_from(primary_item_id, b"dimg");
+            let available = tile_ids
                 .iter()
-                .find(|a| matches!(a.nal_unit_type(), NalUnitKind::SPS))
-                .ok_or_else(|| anyhow!("no SPS in hvcC"))?
-                .nal_units
-                .first()
-                .ok_or_else(|| anyhow!("sps array is empty"))?;
-
-            let (_header, bitstream) = read_hvcc_nal_unit(&b.data)?;
-            sequence_parameter_set_rbsp(&bitstream)?
-        };
+                .filter(|&&tile_id| reader.is_item_available(tile_id, meta))
+                .count();
 
-        dbg!(&sps);
+            if available > 0 && (available * 2 >= tile_ids.len() || complete.is_none()) {
+                return Ok(Some(ProgressiveImage {
+                    image: self.decode_grid(reader, meta, image, true)?,
+                    item_id: primary_item_id,
+                    complete: false,
+                }));
+            }
+        }
+
+        complete
+            .map(|i| {
+                Ok(ProgressiveImage {
+                    image: self.decode_item(reader, meta, renditions[i])?,
+                    item_id: renditions[i],
+                    complete: true,
+                })
+            })
+            .transpose()
+    }
 
-        // Parse PPS
-        let pps = {
-            let b = hevc_config
-                .arrays
+    // whether the item and everything it's made of is in and can be decoded
+    fn is_complete(&self, reader: &HeifReader, meta: &MetaBox, item_id: u32) -> bool {
+        let Some(image) = meta.image(item_id) else {
+            return false;
+        };
+        if image.is_protected()
+            || !self.can_decode(image.item_type())
+            || !reader.is_item_available(item_id, meta)
+        {
+            return false;
+        }
+
+        match image.item_type() {
+            ItemType::Grid => meta
+                .references_from(item_id, b"dimg")
                 .iter()
-                .find(|a| matches!(a.nal_unit_type(), NalUnitKind::PPS))
-                .ok_or_else(|| anyhow!("no PPS in hvcC"))?
-                .nal_units
-                .first()
-                .ok_or_else(|| anyhow!("pps array is empty"))?;
-
-            let (_header, bitstream) = read_hvcc_nal_unit(&b.data)?;
-            picture_parameter_set_rbsp(&bitstream)?
+                .all(|&tile_id| {
+                    meta.image(tile_id).is_some_and(|tile| {
+                        !matches!(tile.item_type(), ItemType::Grid)
+                            && self.can_decode(tile.item_type())
+                    }) && reader.is_item_available(tile_id, meta)
+                }),
+            _ => meta
+                .references_to(item_id, b"tbas")
+                .all(|tile_id| reader.is_item_available(tile_id, meta)),
+        }
+    }
+
+    fn decode_primary_item(&self, reader: &mut HeifReader, heif: &Heif) -> Result<PlanarImage> {
+        // image sequences alone have no primary item to decode
+        let meta = heif
+            .meta_box
+            .as_ref()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"meta"),
+            })?;
+
+        // when the primary item uses a coding we can't handle, the writer may have offered
+        // alternatives to it in an altr group, in order of preference
+        let primary_item_id = meta
+            .primary_item_id()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"pitm"),
+            })?;
+        let item_id = std::iter::once(primary_item_id)
+            .chain(meta.alternatives_for(primary_item_id))
+            .find(|&id| {
+                meta.image(id).is_some_and(|image| {
+                    !image.is_protected() && self.can_decode(image.item_type())
+                })
+            })
+            .unwrap_or(primary_item_id);
+
+        self.decode_item(reader, meta, item_id)
+    }
+
+    /// Decodes the primary image to linear light RGB and, when it has a gain map (see
+    /// [`find_gain_map`]), applies it for a display whose peak is `target_headroom` times SDR
+    /// white. Images without a gain map, or a `target_headroom` of 1, give the SDR rendition.
+    pub fn decode_hdr(
+        &self,
+        data: &[u8],
+        options: ParseOptions,
+        target_headroom: f32,
+    ) -> Result<HdrImage, HeifError> {
+        let mut reader = HeifReader::with_options(data, options);
+        let heif = reader.read()?;
+
+        self.decode_hdr_item(&mut reader, &heif, target_headroom)
+            .map_err(|err| HeifError::from_anyhow(err, || reader.location()))
+    }
+
+    fn decode_hdr_item(
+        &self,
+        reader: &mut HeifReader,
+        heif: &Heif,
+        target_headroom: f32,
+    ) -> Result<HdrImage> {
+        let meta = heif
+            .meta_box
+            .as_ref()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"meta"),
+            })?;
+
+        let primary_item_id = meta
+            .primary_item_id()
+            .ok_or_else(|| HeifError::MissingBox {
+                at: reader.location(),
+                kind: FourCC(*b"pitm"),
+            })?;
+        let gain_map = find_gain_map(reader, meta, primary_item_id)?;
+        let base_item_id = gain_map
+            .as_ref()
+            .map_or(primary_item_id, |gain_map| gain_map.base_item_id);
+
+        let base = self.decode_item(reader, meta, base_item_id)?;
+        let nclx = |item_id| {
+            meta.image(item_id)
+                .and_then(|image| image.color_information())
+                .and_then(|colr| colr.nclx.as_ref())
         };
 
-        dbg!(&pps);
-
-        let primary_item_id = heif.primary_item_id();
-        let primary_item_info = heif
-            .item_info_by_item_id(primary_item_id)
-            .ok_or_else(|| anyhow!("primary item {} not found in item_info", primary_item_id))?;
-
-        match primary_item_info {
-            ItemInfoEntry::Fixed { item_type, .. } => {
-                match item_type {
-                    ItemType::Grid => {
-                        let item_references = heif
-                            .meta_box
-                            .item_references
-                            .as_ref()
-                            .ok_or_else(|| anyhow!("missing iref for grid image"))?;
-
-                        let grid_ref = item_references
-                            .references
-                            .iter()
-                            .find(|r| r.from_item_id == primary_item_id)
-                            .ok_or_else(|| {
-                                anyhow!("grid {} has no tile references", primary_item_id)
-                            })?;
-
-                        dbg!("Grid image with {} tiles", grid_ref.to_item_ids.len());
-
-                        let tiles = grid_ref
-                            .to_item_ids
-                            .iter()
-                            .map(|&tile_id| {
-                                match reader.get_item_data(tile_id, &heif.meta_box.item_location) {
-                                    Ok(bitstream) => read_item_nal_unit(bitstream),
-                                    Err(e) => Err(e),
-                                }
-                            })
-                            .collect::<Result<Vec<_>>>()?;
-
-                        ensure!(tiles.iter().all(|(header, _)| matches!(
-                            header.nal_unit_type(),
-                            NalUnitKind::IdrNLp
-                        )));
-
-                        for (header, rbsp) in tiles {
-                            let mut reader =
-                                SliceSegmentReader::try_new(&rbsp, header, &sps, &pps)?;
-
-                            reader.read_data()?;
-                        }
-                    }
-                    // ItemType::Hvc1 => {
-                    //     let tile_data =
-                    //         reader.get_item_data(primary_item_id, &heif.meta_box.item_location)?;
-                    // }
-                    _ => bail!("unsupported primary item type: {:?}", item_type),
-                }
+        // the linear output, and the gain map resampled to it
+        let pixels = base.width as u64 * base.height as u64;
+        reader.charge_allocation(pixels * 3 * 2 * size_of::<f32>() as u64)?;
+
+        let gain_map_image = gain_map
+            .as_ref()
+            .map(|gain_map| self.decode_item(reader, meta, gain_map.gain_map_item_id))
+            .transpose()?;
+        let gain_map = gain_map
+            .as_ref()
+            .zip(gain_map_image.as_ref())
+            .map(|(gain_map, image)| (image, nclx(gain_map.gain_map_item_id), &gain_map.metadata));
+
+        apply_gain_map(&base, nclx(base_item_id), gain_map, target_headroom)
+    }
+
+    fn decode_item(
+        &self,
+        reader: &mut HeifReader,
+        meta: &MetaBox,
+        item_id: u32,
+    ) -> Result<PlanarImage> {
+        let image = meta.image(item_id).ok_or_else(|| HeifError::MissingItem {
+            at: reader.location(),
+            item_id,
+        })?;
+
+        if image.is_protected() {
+            let scheme = meta
+                .protection_scheme_of(item_id)
+                .and_then(|sinf| sinf.scheme_type.as_ref())
+                .map(|schm| schm.scheme_type);
+
+            bail!(HeifError::ProtectedItem {
+                at: reader.location_of(item_offset(meta, item_id)),
+                item_id,
+                scheme,
+            });
+        }
+
+        match image.item_type() {
+            ItemType::Grid => self.decode_grid(reader, meta, image, false),
+            _ => self.decode_coded_item(reader, image),
+        }
+    }
+
+    fn decode_grid(
+        &self,
+        reader: &mut HeifReader,
+        meta: &MetaBox,
+        image: ImageHandle,
+        partial: bool,
+    ) -> Result<PlanarImage> {
+        let grid_id = image.item_id();
+        let grid_offset = item_offset(meta, grid_id);
+
+        let tile_ids = meta.references_from(grid_id, b"dimg");
+        ensure!(
+            !tile_ids.is_empty(),
+            "grid {grid_id} has no tile references"
+        );
+
+        crate::heif::ensure_within(
+            "grid tiles",
+            tile_ids.len() as u64,
+            reader.options().limits.max_grid_tiles as u64,
+            || reader.location_of(grid_offset),
+        )?;
+
+        let grid = ImageGrid::parse(&reader.get_item_data(grid_id, meta)?)
+            .map_err(|err| invalid_item(reader, grid_offset, grid_id, &err))?;
+
+        ensure!(
+            tile_ids.len() == grid.rows as usize * grid.columns as usize,
+            invalid_item(
+                reader,
+                grid_offset,
+                grid_id,
+                &anyhow!(
+                    "{}x{} grid references {} tiles",
+                    grid.columns,
+                    grid.rows,
+                    tile_ids.len()
+                )
+            )
+        );
+
+        charge_image(reader, grid.output_width, grid.output_height, grid_offset)?;
+
+        let mut canvas: Option<PlanarImage> = None;
+
+        for (i, &tile_id) in tile_ids.iter().enumerate() {
+            // tiles still to arrive are left black
+            if partial && !reader.is_item_available(tile_id, meta) {
+                continue;
             }
+
+            let tile_image = meta.image(tile_id).ok_or_else(|| HeifError::MissingItem {
+                at: reader.location_of(grid_offset),
+                item_id: tile_id,
+            })?;
+            let tile = self.decode_coded_item(reader, tile_image)?;
+
+            let canvas = canvas
+                .get_or_insert_with(|| tile.empty_like(grid.output_width, grid.output_height));
+
+            // tiles are all the size of the first, laid out in row-major order
+            let (row, column) = (i as u32 / grid.columns, i as u32 % grid.columns);
+            canvas
+                .paste(&tile, column * tile.width, row * tile.height)
+                .map_err(|err| invalid_item(reader, grid_offset, grid_id, &err))?;
         }
 
-        Ok(())
+        canvas.ok_or_else(|| anyhow!("grid {grid_id} has no tiles"))
+    }
+
+    fn decode_coded_item(
+        &self,
+        reader: &mut HeifReader,
+        image: ImageHandle,
+    ) -> Result<PlanarImage> {
+        let meta = image.meta();
+        let item_id = image.item_id();
+        let item_type = image.item_type().four_cc();
+        let offset = item_offset(meta, item_id);
+
+        let decoder = self
+            .decoders
+            .get(&item_type)
+            .ok_or_else(|| HeifError::UnsupportedCodec {
+                at: reader.location_of(offset),
+                codec: item_type,
+            })?;
+
+        // every image item has an ispe, and it's what the picture is charged by
+        let (width, height) = image.dimensions().ok_or_else(|| HeifError::MissingBox {
+            at: reader.location_of(offset),
+            kind: FourCC(*b"ispe"),
+        })?;
+        charge_image(reader, width, height, offset)?;
+
+        let data = item_data(reader, meta, item_id)?;
+        let properties = image.properties().collect::<Vec<_>>();
+
+        let mut budget = reader.item_budget(offset);
+        let result = decoder.decode(&data, &properties, &mut budget);
+        reader.spend(&budget);
+
+        result.map_err(|err| match err.downcast::<HeifError>() {
+            // over the limits, rather than a broken bitstream
+            Ok(err) => err.into(),
+            Err(err) => HeifError::BitstreamError {
+                at: reader.location_of(offset),
+                reason: format!("{item_type} item {item_id}: {err:#}"),
+            }
+            .into(),
+        })
     }
 }
 
-// no length prefix here
-fn read_hvcc_nal_unit(raw_nal_unit: &[u8]) -> Result<(NalUnitHeader, Vec<u8>)> {
-    match raw_nal_unit {
-        [header_1, header_2, rbsp @ ..] => {
-            let header = NalUnitHeader(u16::from_be_bytes([*header_1, *header_2]));
-            Ok((header, RbspReader::remove_emulation_prevention(rbsp)))
-        }
-        _ => bail!("nal unit is too short"),
+/// The payload of a `grid` item, ISO/IEC 23008-12 6.6.2.3
+#[derive(Debug)]
+struct ImageGrid {
+    rows: u32,
+    columns: u32,
+    output_width: u32,
+    output_height: u32,
+}
+
+impl ImageGrid {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let (version, flags, rows_minus_one, columns_minus_one, rest) = match data {
+            [version, flags, rows, columns, rest @ ..] => (*version, *flags, *rows, *columns, rest),
+            _ => bail!("grid item is too short"),
+        };
+        ensure!(version == 0, "unsupported grid version {version}");
+
+        let (output_width, output_height) = match (flags & 1, rest) {
+            (0, [w0, w1, h0, h1]) => (
+                u16::from_be_bytes([*w0, *w1]) as u32,
+                u16::from_be_bytes([*h0, *h1]) as u32,
+            ),
+            (1, [w0, w1, w2, w3, h0, h1, h2, h3]) => (
+                u32::from_be_bytes([*w0, *w1, *w2, *w3]),
+                u32::from_be_bytes([*h0, *h1, *h2, *h3]),
+            ),
+            _ => bail!("grid item has {} bytes of output size", rest.len()),
+        };
+
+        Ok(Self {
+            rows: rows_minus_one as u32 + 1,
+            columns: columns_minus_one as u32 + 1,
+            output_width,
+            output_height,
+        })
     }
 }
 
-// nal units from item data (tiles) has 4-byte length prefix
-fn read_item_nal_unit(raw_nal_unit: &[u8]) -> Result<(NalUnitHeader, Vec<u8>)> {
-    match raw_nal_unit {
-        [len_0, len_1, len_2, len_3, header_1, header_2, rbsp @ ..] => {
-            let nal_length = u32::from_be_bytes([*len_0, *len_1, *len_2, *len_3]) as usize;
-            let actual_nal_size = 2 + rbsp.len(); // header (2 bytes) + rbsp
-
-            ensure!(
-                nal_length == actual_nal_size,
-                "tile item should contain exactly one NAL unit: length prefix says {} bytes, but item has {} bytes of NAL data",
-                nal_length,
-                actual_nal_size
+// the item's data, or for the base of hvt1 tile items its data followed by that of the tiles, in
+// the order of their tbas references: together they make up the access unit
+fn item_data<'a, 'r: 'a>(
+    reader: &mut HeifReader<'r>,
+    meta: &MetaBox<'a>,
+    item_id: u32,
+) -> Result<Cow<'a, [u8]>> {
+    let data = reader.get_item_data(item_id, meta)?;
+
+    let tile_ids = meta.references_to(item_id, b"tbas").collect::<Vec<_>>();
+    if tile_ids.is_empty() {
+        return Ok(data);
+    }
+
+    let offset = item_offset(meta, item_id);
+    crate::heif::ensure_within(
+        "tile items",
+        tile_ids.len() as u64,
+        reader.options().limits.max_grid_tiles as u64,
+        || reader.location_of(offset),
+    )?;
+
+    let mut tiles = Vec::with_capacity(tile_ids.len());
+    for tile_id in tile_ids {
+        ensure!(
+            meta.image(tile_id)
+                .is_some_and(|tile| matches!(tile.item_type(), ItemType::Hvt1)),
+            invalid_item(
+                reader,
+                offset,
+                item_id,
+                &anyhow!("item {tile_id} refers to it with tbas but isn't an hvt1 tile")
+            )
+        );
+
+        tiles.push(reader.get_item_data(tile_id, meta)?);
+    }
+
+    // the tiles' extents may all cover the same bytes, so the copy is charged before it's made
+    let size = tiles.iter().map(|tile| tile.len()).sum::<usize>() + data.len();
+    reader.charge_allocation(size as u64)?;
+
+    let mut access_unit = Vec::with_capacity(size);
+    access_unit.extend_from_slice(&data);
+    for tile in tiles {
+        access_unit.extend_from_slice(&tile);
+    }
+
+    Ok(Cow::Owned(access_unit))
+}
+
+pub(super) fn item_offset(meta: &MetaBox, item_id: u32) -> usize {
+    meta.item_location
+        .reference(item_id)
+        .map_or(0, |r| r.data_offset() as usize)
+}
+
+// checks the dimensions against the limits before anything is sized from them, and charges a
+// worst case 4:4:4 picture of 16-bit samples to the allocation budget
+fn charge_image(
+    reader: &mut HeifReader,
+    width: u32,
+    height: u32,
+    offset: usize,
+) -> Result<(), HeifError> {
+    let pixels = width as u64 * height as u64;
+
+    crate::heif::ensure_within(
+        "image pixels",
+        pixels,
+        reader.options().limits.max_image_pixels,
+        || reader.location_of(offset),
+    )?;
+
+    reader.charge_allocation(pixels * 3 * size_of::<u16>() as u64)
+}
+
+pub(super) fn invalid_item(
+    reader: &HeifReader,
+    offset: usize,
+    item_id: u32,
+    err: &anyhow::Error,
+) -> HeifError {
+    HeifError::InvalidBox {
+        at: reader.location_of(offset),
+        reason: format!("item {item_id}: {err:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heif::{DecodeBudget, DecodeLimits, ItemProperty};
+    use crate::hevc::ChromaFormat;
+
+    fn make_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn make_full_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        make_box(kind, &[&[0, 0, 0, 0][..], payload].concat())
+    }
+
+    fn infe(item_id: u16, item_type: &[u8; 4]) -> Vec<u8> {
+        let payload = [&item_id.to_be_bytes()[..], &[0, 0], item_type, b"\0"].concat();
+        make_box(b"infe", &[&[2, 0, 0, 0][..], &payload].concat())
+    }
+
+    // a file of the given items, all data in one mdat, with `pitm` and the given `iref` payload
+    fn heif_file(items: &[(u16, &[u8; 4], &[u8])], primary: u16, iref: &[u8]) -> Vec<u8> {
+        heif_file_with_ispe(items, primary, iref, true)
+    }
+
+    fn heif_file_with_ispe(
+        items: &[(u16, &[u8; 4], &[u8])],
+        primary: u16,
+        iref: &[u8],
+        with_ispe: bool,
+    ) -> Vec<u8> {
+        let meta_with_mdat_at = |mdat_payload: u32| {
+            let mut iloc = vec![0x44, 0x00, 0, items.len() as u8];
+            let mut offset = mdat_payload;
+            for (item_id, _, data) in items {
+                iloc.extend(item_id.to_be_bytes());
+                iloc.extend([0, 0, 0, 1]);
+                iloc.extend(offset.to_be_bytes());
+                iloc.extend((data.len() as u32).to_be_bytes());
+                offset += data.len() as u32;
+            }
+
+            let hdlr = make_full_box(b"hdlr", &[&[0; 4][..], b"pict", &[0; 13]].concat());
+            let mut iinf = (items.len() as u16).to_be_bytes().to_vec();
+            for (item_id, item_type, _) in items {
+                iinf.extend(infe(*item_id, item_type));
+            }
+
+            // every item is 2x2, the size FlatDecoder makes
+            let ispe = make_full_box(b"ispe", &[0, 0, 0, 2, 0, 0, 0, 2]);
+            let mut ipma = (items.len() as u32).to_be_bytes().to_vec();
+            for (item_id, _, _) in items {
+                ipma.extend(item_id.to_be_bytes());
+                ipma.extend(if with_ispe { &[1, 1][..] } else { &[0] });
+            }
+            let iprp = make_box(
+                b"iprp",
+                &[make_box(b"ipco", &ispe), make_full_box(b"ipma", &ipma)].concat(),
             );
 
-            let header = NalUnitHeader(u16::from_be_bytes([*header_1, *header_2]));
-            Ok((header, RbspReader::remove_emulation_prevention(rbsp)))
+            make_full_box(
+                b"meta",
+                &[
+                    hdlr,
+                    make_full_box(b"pitm", &primary.to_be_bytes()),
+                    make_full_box(b"iinf", &iinf),
+                    make_full_box(b"iloc", &iloc),
+                    make_full_box(b"iref", iref),
+                    iprp,
+                ]
+                .concat(),
+            )
+        };
+
+        let ftyp = make_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+        let meta_len = meta_with_mdat_at(0).len();
+        let meta = meta_with_mdat_at((ftyp.len() + meta_len + 8) as u32);
+        let mdat = make_box(
+            b"mdat",
+            &items
+                .iter()
+                .map(|(_, _, data)| *data)
+                .collect::<Vec<_>>()
+                .concat(),
+        );
+
+        [ftyp, meta, mdat].concat()
+    }
+
+    // a 3x2 grid (item 3) of two 2x2 tiles (items 1 and 2), so the second tile gets clipped
+    fn grid_file() -> Vec<u8> {
+        heif_file(
+            &[
+                (1, b"hvc1", &[10]),
+                (2, b"hvc1", &[20]),
+                (3, b"grid", &[0, 0, 0, 1, 0, 3, 0, 2]),
+            ],
+            3,
+            &make_box(b"dimg", &[0, 3, 0, 2, 0, 1, 0, 2]),
+        )
+    }
+
+    // an hvc1 base (item 1) with two hvt1 tiles, referenced out of item order
+    fn tile_file(tile_type: &[u8; 4]) -> Vec<u8> {
+        heif_file(
+            &[
+                (1, b"hvc1", &[1]),
+                (2, tile_type, &[2]),
+                (3, tile_type, &[3]),
+            ],
+            1,
+            &[
+                make_box(b"tbas", &[0, 3, 0, 1, 0, 1]),
+                make_box(b"tbas", &[0, 2, 0, 1, 0, 1]),
+            ]
+            .concat(),
+        )
+    }
+
+    // fills a 2x2 monochrome tile with the first byte of the item
+    struct FlatDecoder;
+
+    impl ItemDecoder for FlatDecoder {
+        fn item_type(&self) -> FourCC {
+            FourCC(*b"hvc1")
+        }
+
+        fn decode(
+            &self,
+            data: &[u8],
+            _properties: &[&ItemProperty],
+            _budget: &mut DecodeBudget,
+        ) -> Result<PlanarImage> {
+            let mut tile = PlanarImage::new(2, 2, ChromaFormat::Monochrome, 8);
+            tile.planes[0].samples.fill(data[0] as u16);
+
+            Ok(tile)
+        }
+    }
+
+    #[test]
+    fn test_grid_with_registered_decoder() {
+        let mut decoder = HeicDecoder::default();
+        decoder.register(FlatDecoder);
+
+        let image = decoder
+            .decode_primary(&grid_file(), ParseOptions::default())
+            .unwrap();
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(*image.planes[0].samples, [10, 10, 20, 10, 10, 20]);
+    }
+
+    // a one row image of the access unit's bytes
+    struct AccessUnitDecoder;
+
+    impl ItemDecoder for AccessUnitDecoder {
+        fn item_type(&self) -> FourCC {
+            FourCC(*b"hvc1")
+        }
+
+        fn decode(
+            &self,
+            data: &[u8],
+            _properties: &[&ItemProperty],
+            _budget: &mut DecodeBudget,
+        ) -> Result<PlanarImage> {
+            let mut image = PlanarImage::new(data.len() as u32, 1, ChromaFormat::Monochrome, 8);
+            for (sample, &byte) in image.planes[0].samples.iter_mut().zip(data) {
+                *sample = byte as u16;
+            }
+
+            Ok(image)
+        }
+    }
+
+    #[test]
+    fn test_hvt1_tiles_complete_the_access_unit() {
+        let mut decoder = HeicDecoder::default();
+        decoder.register(AccessUnitDecoder);
+
+        let image = decoder
+            .decode_primary(&tile_file(b"hvt1"), ParseOptions::default())
+            .unwrap();
+        assert_eq!(*image.planes[0].samples, [1, 3, 2]);
+
+        let err = decoder
+            .decode_primary(&tile_file(b"hvc1"), ParseOptions::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't an hvt1 tile"), "{err}");
+    }
+
+    #[test]
+    fn test_access_unit_is_charged() {
+        let tile = [2; 16_384];
+        let data = heif_file(
+            &[(1, b"hvc1", &[1]), (2, b"hvt1", &tile), (3, b"hvt1", &tile)],
+            1,
+            &[
+                make_box(b"tbas", &[0, 2, 0, 1, 0, 1]),
+                make_box(b"tbas", &[0, 3, 0, 1, 0, 1]),
+            ]
+            .concat(),
+        );
+        let options = ParseOptions {
+            limits: DecodeLimits {
+                max_total_allocation: 24_576,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut decoder = HeicDecoder::default();
+        decoder.register(AccessUnitDecoder);
+
+        let err = decoder.decode_primary(&data, options).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                HeifError::LimitExceeded {
+                    limit: "total allocation",
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_decode_hdr_with_tmap() {
+        // full gain everywhere, up to 1 stop over the base image's headroom of 0
+        let mut tmap = vec![0, 0, 0, 0, 0, 0];
+        for (numerator, denominator) in [(0, 1), (1, 1), (0, 1), (1, 1), (1, 1), (0, 1), (0, 1)] {
+            tmap.extend((numerator as u32).to_be_bytes());
+            tmap.extend((denominator as u32).to_be_bytes());
         }
-        _ => bail!("nal unit is too short (need at least 6 bytes: 4 length + 2 header)"),
+        let data = heif_file(
+            &[
+                (1, b"hvc1", &[255]),
+                (2, b"hvc1", &[255]),
+                (3, b"tmap", &tmap),
+            ],
+            1,
+            &make_box(b"dimg", &[0, 3, 0, 2, 0, 1, 0, 2]),
+        );
+
+        let mut decoder = HeicDecoder::default();
+        decoder.register(FlatDecoder);
+
+        let hdr = decoder
+            .decode_hdr(&data, ParseOptions::default(), 2.0)
+            .unwrap();
+        assert_eq!((hdr.width, hdr.height), (2, 2));
+        assert!(hdr.samples.iter().all(|&sample| sample == 2.0));
+
+        let sdr = decoder
+            .decode_hdr(&data, ParseOptions::default(), 1.0)
+            .unwrap();
+        assert!(sdr.samples.iter().all(|&sample| sample == 1.0));
+    }
+
+    #[test]
+    fn test_progressive_decoding() {
+        // the grid and its thumbnail (item 4) come first in the mdat, then the tiles
+        let data = heif_file(
+            &[
+                (3, b"grid", &[0, 0, 0, 1, 0, 3, 0, 2]),
+                (4, b"hvc1", &[40]),
+                (1, b"hvc1", &[10]),
+                (2, b"hvc1", &[20]),
+            ],
+            3,
+            &[
+                make_box(b"dimg", &[0, 3, 0, 2, 0, 1, 0, 2]),
+                make_box(b"thmb", &[0, 4, 0, 1, 0, 3]),
+            ]
+            .concat(),
+        );
+
+        let mut decoder = HeicDecoder::default();
+        decoder.register(FlatDecoder);
+        let decode = |missing: usize| {
+            decoder
+                .decode_progressive(&data[..data.len() - missing], ParseOptions::default())
+                .unwrap()
+                .map(|shown| {
+                    (
+                        shown.item_id,
+                        shown.complete,
+                        shown.image.planes[0].samples.to_vec(),
+                    )
+                })
+        };
+
+        assert_eq!(decode(0), Some((3, true, vec![10, 10, 20, 10, 10, 20])));
+        assert_eq!(decode(1), Some((3, false, vec![10, 10, 0, 10, 10, 0])));
+        assert_eq!(decode(2), Some((4, true, vec![40, 40, 40, 40])));
+        assert_eq!(decode(3), None);
+
+        // the file isn't complete, so isn't decoded without partial
+        assert!(
+            decoder
+                .decode_primary(&data[..data.len() - 1], ParseOptions::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_coded_item_needs_ispe() {
+        let mut decoder = HeicDecoder::default();
+        decoder.register(FlatDecoder);
+
+        let data = heif_file_with_ispe(&[(1, b"hvc1", &[10])], 1, &[], false);
+        let err = decoder
+            .decode_primary(&data, ParseOptions::default())
+            .unwrap_err();
+
+        assert!(
+            matches!(err, HeifError::MissingBox { kind, .. } if kind == FourCC(*b"ispe")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_unregistered_item_type() {
+        let mut decoder = HeicDecoder::default();
+        decoder.decoders.clear();
+
+        let err = decoder
+            .decode_primary(&grid_file(), ParseOptions::default())
+            .unwrap_err();
+
+        assert!(
+            matches!(err, HeifError::UnsupportedCodec { codec, .. } if codec == FourCC(*b"hvc1")),
+            "{err}"
+        );
     }
 }
@@ -0,0 +1,684 @@
+use anyhow::{Result, anyhow, bail, ensure};
+
+use crate::error::HeifError;
+use crate::heic::{PlanarImage, invalid_item, item_offset};
+use crate::heif::{
+    ComponentType, HeifReader, ItemInfoEntry, ItemType, MetaBox, NclxColourInformation,
+};
+use crate::impl_read_for_datatype;
+
+/// The `auxC` type of Apple's HDR gain map auxiliary images.
+pub const APPLE_HDR_GAIN_MAP: &str = "urn:com:apple:photo:2020:aux:hdrgainmap";
+
+/// An SDR base image together with the gain map taking it to an HDR rendition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainMap {
+    pub base_item_id: u32,
+    pub gain_map_item_id: u32,
+    pub metadata: GainMapMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GainMapMetadata {
+    /// the payload of a `tmap` item
+    Iso21496(ToneMapMetadata),
+    /// an auxiliary image of type [`APPLE_HDR_GAIN_MAP`]; `headroom` is the linear ratio of the
+    /// HDR rendition's peak to SDR white, from `HDRGainMap:HDRGainMapHeadroom` in its XMP
+    Apple { headroom: f64 },
+}
+
+/// Gain map metadata, ISO 21496-1 clause 6. Headrooms and gains are log2 values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToneMapMetadata {
+    pub writer_version: u16,
+    /// whether the map applies in the base image's colour space rather than the alternate's
+    pub use_base_colour_space: bool,
+    /// set when the base image is the HDR rendition and the map leads to the SDR one
+    pub backward_direction: bool,
+    pub base_hdr_headroom: f64,
+    pub alternate_hdr_headroom: f64,
+    /// one channel, or red, green and blue for a multichannel map
+    pub channels: Box<[GainMapChannel]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GainMapChannel {
+    pub gain_map_min: f64,
+    pub gain_map_max: f64,
+    pub gamma: f64,
+    pub base_offset: f64,
+    pub alternate_offset: f64,
+}
+
+impl ToneMapMetadata {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = MetadataReader { cursor: 0, data };
+
+        let version = reader.read_u8()?;
+        ensure!(version == 0, "unsupported tmap version {version}");
+        let minimum_version = reader.read_u16()?;
+        ensure!(
+            minimum_version == 0,
+            "tmap needs a reader of version {minimum_version}"
+        );
+        let writer_version = reader.read_u16()?;
+
+        let flags = reader.read_u8()?;
+        let is_multichannel = (flags & 0x80) != 0;
+        let use_common_denominator = (flags & 0x08) != 0;
+
+        let common_denominator = if use_common_denominator {
+            Some(reader.read_u32()?)
+        } else {
+            None
+        };
+        // each value is a numerator and, unless they share one, its denominator
+        let mut fraction = |signed: bool| -> Result<f64> {
+            let raw = reader.read_u32()?;
+            let numerator = if signed {
+                raw as i32 as f64
+            } else {
+                raw as f64
+            };
+            let denominator = match common_denominator {
+                Some(denominator) => denominator,
+                None => reader.read_u32()?,
+            };
+            ensure!(denominator != 0, "zero denominator");
+
+            Ok(numerator / denominator as f64)
+        };
+
+        let base_hdr_headroom = fraction(false)?;
+        let alternate_hdr_headroom = fraction(false)?;
+
+        let channels = (0..if is_multichannel { 3 } else { 1 })
+            .map(|_| {
+                Ok(GainMapChannel {
+                    gain_map_min: fraction(true)?,
+                    gain_map_max: fraction(true)?,
+                    gamma: fraction(false)?,
+                    base_offset: fraction(true)?,
+                    alternate_offset: fraction(true)?,
+                })
+            })
+            .collect::<Result<Box<[_]>>>()?;
+        ensure!(
+            channels.iter().all(|channel| channel.gamma > 0.0),
+            "gamma must be positive"
+        );
+
+        Ok(Self {
+            writer_version,
+            use_base_colour_space: (flags & 0x40) != 0,
+            backward_direction: (flags & 0x04) != 0,
+            base_hdr_headroom,
+            alternate_hdr_headroom,
+            channels,
+        })
+    }
+
+    /// How much of the map to apply for a display with log2 headroom `target`, clause 5.2.5.
+    pub fn weight(&self, target: f64) -> f64 {
+        let (base, alternate) = (self.base_hdr_headroom, self.alternate_hdr_headroom);
+
+        if base == alternate {
+            return 0.0;
+        }
+
+        ((target - base) / (alternate - base)).clamp(0.0, 1.0)
+    }
+}
+
+struct MetadataReader<'a> {
+    cursor: usize,
+    data: &'a [u8],
+}
+
+impl MetadataReader<'_> {
+    fn read_slice(&mut self, len: usize) -> Result<&[u8]> {
+        let Some(slice) = self.data.get(self.cursor..self.cursor + len) else {
+            bail!("tmap metadata is truncated");
+        };
+        self.cursor += len;
+
+        Ok(slice)
+    }
+
+    impl_read_for_datatype!(read_u8, u8);
+    impl_read_for_datatype!(read_u16, u16);
+    impl_read_for_datatype!(read_u32, u32);
+}
+
+/// The value of `HDRGainMap:HDRGainMapHeadroom` in an XMP packet, written either as an attribute
+/// or as an element.
+pub fn apple_headroom_from_xmp(xmp: &str) -> Option<f64> {
+    const NAME: &str = "HDRGainMap:HDRGainMapHeadroom";
+
+    let (_, rest) = xmp.split_once(NAME)?;
+    let value = match rest.trim_start().strip_prefix('=') {
+        Some(attribute) => {
+            let attribute = attribute.trim_start();
+            let quote = attribute
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '"' | '\''))?;
+            attribute[1..].split(quote).next()?
+        }
+        None => rest.strip_prefix('>')?.split('<').next()?,
+    };
+
+    value.trim().parse().ok()
+}
+
+/// The gain map of `item_id`: a `tmap` item with it as its base image (or `item_id` being that
+/// `tmap` item), else an Apple HDR gain map auxiliary image of it.
+pub fn find_gain_map<'a>(
+    reader: &HeifReader<'a>,
+    meta: &MetaBox<'a>,
+    item_id: u32,
+) -> Result<Option<GainMap>, HeifError> {
+    let tone_map = meta.item_info.item_info_entries.iter().find_map(
+        |ItemInfoEntry::Fixed {
+             item_id: id,
+             item_type,
+             ..
+         }| {
+            let inputs = meta.references_from(*id, b"dimg");
+            (matches!(item_type, ItemType::Tmap)
+                && (*id == item_id || inputs.first() == Some(&item_id)))
+            .then_some((*id, inputs))
+        },
+    );
+
+    if let Some((tmap_id, inputs)) = tone_map {
+        let invalid =
+            |err: anyhow::Error| invalid_item(reader, item_offset(meta, tmap_id), tmap_id, &err);
+
+        let &[base_item_id, gain_map_item_id] = inputs else {
+            return Err(invalid(anyhow!(
+                "tmap has {} dimg inputs rather than a base image and a gain map",
+                inputs.len()
+            )));
+        };
+        let metadata =
+            ToneMapMetadata::parse(&reader.get_item_data(tmap_id, meta)?).map_err(invalid)?;
+
+        return Ok(Some(GainMap {
+            base_item_id,
+            gain_map_item_id,
+            metadata: GainMapMetadata::Iso21496(metadata),
+        }));
+    }
+
+    let apple_gain_map = meta.references_to(item_id, b"auxl").find(|&aux_id| {
+        meta.image(aux_id)
+            .and_then(|image| image.auxiliary_type())
+            .is_some_and(|auxc| &*auxc.aux_type == APPLE_HDR_GAIN_MAP)
+    });
+
+    let Some(gain_map_item_id) = apple_gain_map else {
+        return Ok(None);
+    };
+
+    // the headroom is in the XMP describing the gain map
+    let mut headroom = None;
+    for xmp_id in meta.references_to(gain_map_item_id, b"cdsc") {
+        let is_xmp = meta.image(xmp_id).is_some_and(|item| {
+            matches!(
+                item.item_type(),
+                ItemType::Mime {
+                    content_type: "application/rdf+xml",
+                    ..
+                }
+            )
+        });
+        if !is_xmp {
+            continue;
+        }
+
+        let data = reader.get_item_data(xmp_id, meta)?;
+        let xmp = String::from_utf8_lossy(&data);
+        headroom = headroom.or_else(|| apple_headroom_from_xmp(&xmp));
+    }
+
+    let headroom = headroom.ok_or_else(|| {
+        invalid_item(
+            reader,
+            item_offset(meta, gain_map_item_id),
+            gain_map_item_id,
+            &anyhow!("HDR gain map without an HDRGainMapHeadroom in its XMP"),
+        )
+    })?;
+
+    Ok(Some(GainMap {
+        base_item_id: item_id,
+        gain_map_item_id,
+        metadata: GainMapMetadata::Apple { headroom },
+    }))
+}
+
+/// A linear light RGB image in the colour space of the base image, where 1.0 is SDR white.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    /// RGBRGB..., row-major
+    pub samples: Box<[f32]>,
+}
+
+impl HdrImage {
+    /// The samples encoded with the SMPTE ST 2084 (PQ) curve, placing SDR white at
+    /// `sdr_white_nits`; 203 is the reference white of ITU-R BT.2408.
+    pub fn to_pq(&self, sdr_white_nits: f32) -> Box<[f32]> {
+        const M1: f32 = 2610.0 / 16384.0;
+        const M2: f32 = 2523.0 / 4096.0 * 128.0;
+        const C1: f32 = 3424.0 / 4096.0;
+        const C2: f32 = 2413.0 / 4096.0 * 32.0;
+        const C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+        self.samples
+            .iter()
+            .map(|&sample| {
+                let y = (sample * sdr_white_nits / 10000.0).clamp(0.0, 1.0).powf(M1);
+                (C2.mul_add(y, C1) / C3.mul_add(y, 1.0)).powf(M2)
+            })
+            .collect()
+    }
+}
+
+/// `base` as linear light RGB, with `gain_map` applied for a display whose peak is
+/// `target_headroom` times SDR white.
+///
+/// The base image's `nclx` picks the YCbCr matrix, range and transfer function; without one it's
+/// taken to be full range BT.601 with the sRGB curve, and transfer functions other than linear and
+/// PQ are decoded with the sRGB curve too.
+pub fn apply_gain_map(
+    base: &PlanarImage,
+    base_nclx: Option<&NclxColourInformation>,
+    gain_map: Option<(
+        &PlanarImage,
+        Option<&NclxColourInformation>,
+        &GainMapMetadata,
+    )>,
+    target_headroom: f32,
+) -> Result<HdrImage> {
+    let transfer = base_nclx.map_or(13, |nclx| nclx.transfer_characteristics);
+    let mut samples = rgb(base, base_nclx)?;
+    for sample in samples.iter_mut() {
+        *sample = match transfer {
+            8 => *sample,
+            16 => pq_eotf(*sample) * 10000.0 / 203.0,
+            _ => srgb_eotf(*sample),
+        };
+    }
+
+    if let Some((image, nclx, metadata)) = gain_map {
+        let target = (target_headroom.max(1.0) as f64).log2();
+
+        match metadata {
+            GainMapMetadata::Iso21496(metadata) => {
+                let weight = metadata.weight(target) as f32;
+                let map = GainMapSampler::new(image, nclx, metadata.channels.len())?;
+
+                for (i, pixel) in samples.chunks_exact_mut(3).enumerate() {
+                    let (x, y) = (i as u32 % base.width, i as u32 / base.width);
+
+                    for (c, sample) in pixel.iter_mut().enumerate() {
+                        let channel = &metadata.channels[c % metadata.channels.len()];
+                        let g = map
+                            .sample(c % metadata.channels.len(), x, y, base)
+                            .powf(1.0 / channel.gamma as f32);
+                        let log_gain = ((channel.gain_map_max - channel.gain_map_min) as f32)
+                            .mul_add(g, channel.gain_map_min as f32);
+
+                        *sample = (*sample + channel.base_offset as f32)
+                            .mul_add((log_gain * weight).exp2(), -channel.alternate_offset as f32);
+                    }
+                }
+            }
+            GainMapMetadata::Apple { headroom } => {
+                let headroom = *headroom as f32;
+                let weight = if headroom > 1.0 {
+                    (target as f32 / headroom.log2()).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let map = GainMapSampler::new(image, nclx, 1)?;
+
+                for (i, pixel) in samples.chunks_exact_mut(3).enumerate() {
+                    let (x, y) = (i as u32 % base.width, i as u32 / base.width);
+                    let gain = (headroom - 1.0).mul_add(srgb_eotf(map.sample(0, x, y, base)), 1.0);
+                    let gain = gain.powf(weight);
+
+                    for sample in pixel {
+                        *sample *= gain;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(HdrImage {
+        width: base.width,
+        height: base.height,
+        samples: samples.into_boxed_slice(),
+    })
+}
+
+// the normalized gain map, sampled bilinearly at the pixels of the (usually larger) base image
+struct GainMapSampler {
+    width: u32,
+    height: u32,
+    channels: usize,
+    samples: Vec<f32>,
+}
+
+impl GainMapSampler {
+    fn new(
+        image: &PlanarImage,
+        nclx: Option<&NclxColourInformation>,
+        channels: usize,
+    ) -> Result<Self> {
+        // sampling reads the corner pixels, so there have to be some
+        ensure!(
+            image.width > 0 && image.height > 0,
+            "empty {}x{} gain map",
+            image.width,
+            image.height
+        );
+
+        let samples = if channels == 1 {
+            // single channel maps are stored in the luma plane, which isn't always listed first
+            let plane = image
+                .planes
+                .iter()
+                .find(|plane| {
+                    matches!(
+                        plane.component,
+                        ComponentType::Y | ComponentType::Monochrome
+                    )
+                })
+                .ok_or_else(|| anyhow!("gain map has no luma plane"))?;
+            ensure!(
+                (plane.width, plane.height) == (image.width, image.height),
+                "{}x{} luma plane in a {}x{} gain map",
+                plane.width,
+                plane.height,
+                image.width,
+                image.height
+            );
+            let full_range = nclx.is_none_or(|nclx| nclx.full_range_flag);
+            let depth = image.bit_depth;
+
+            plane
+                .samples
+                .iter()
+                .map(|&sample| luma(sample, depth, full_range).clamp(0.0, 1.0))
+                .collect()
+        } else {
+            rgb(image, nclx)?
+        };
+
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            channels,
+            samples,
+        })
+    }
+
+    fn sample(&self, channel: usize, x: u32, y: u32, base: &PlanarImage) -> f32 {
+        let position = |i: u32, from: u32, to: u32| {
+            ((i as f32 + 0.5) * to as f32 / from as f32 - 0.5).clamp(0.0, (to - 1) as f32)
+        };
+        let (fx, fy) = (
+            position(x, base.width, self.width),
+            position(y, base.height, self.height),
+        );
+        let (x0, y0) = (fx as u32, fy as u32);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+        let at =
+            |x: u32, y: u32| self.samples[(y * self.width + x) as usize * self.channels + channel];
+        let top = (at(x1, y0) - at(x0, y0)).mul_add(tx, at(x0, y0));
+        let bottom = (at(x1, y1) - at(x0, y1)).mul_add(tx, at(x0, y1));
+
+        (bottom - top).mul_add(ty, top)
+    }
+}
+
+// the non-linear RGB of each pixel, interleaved and normalized to [0, 1]
+fn rgb(image: &PlanarImage, nclx: Option<&NclxColourInformation>) -> Result<Vec<f32>> {
+    let plane = |component| {
+        image
+            .planes
+            .iter()
+            .find(|plane| plane.component == component)
+    };
+    let full_range = nclx.is_none_or(|nclx| nclx.full_range_flag);
+    let depth = image.bit_depth;
+    let max = ((1u32 << depth) - 1) as f32;
+    let pixels = (0..image.height).flat_map(|y| (0..image.width).map(move |x| (x, y)));
+
+    let at = |plane: &crate::heic::Plane, x: u32, y: u32| {
+        let (x, y) = (
+            x as u64 * plane.width as u64 / image.width as u64,
+            y as u64 * plane.height as u64 / image.height as u64,
+        );
+        plane.samples[(y * plane.width as u64 + x) as usize]
+    };
+
+    let samples = match (
+        plane(ComponentType::Y).or_else(|| plane(ComponentType::Monochrome)),
+        plane(ComponentType::Cb),
+        plane(ComponentType::Cr),
+        [
+            ComponentType::Red,
+            ComponentType::Green,
+            ComponentType::Blue,
+        ]
+        .map(plane),
+    ) {
+        (Some(y_plane), Some(cb_plane), Some(cr_plane), _) => {
+            let (kr, kb): (f32, f32) = match nclx.map_or(6, |nclx| nclx.matrix_coefficients) {
+                0 => (0.0, 0.0),
+                1 => (0.2126, 0.0722),
+                9 | 10 => (0.2627, 0.0593),
+                _ => (0.299, 0.114),
+            };
+            let scale = (1u32 << depth.saturating_sub(8)) as f32;
+
+            pixels
+                .flat_map(|(x, y)| {
+                    let (luma, cb, cr) = (
+                        at(y_plane, x, y) as f32,
+                        at(cb_plane, x, y) as f32,
+                        at(cr_plane, x, y) as f32,
+                    );
+                    let (luma, cb, cr) = if full_range {
+                        let mid = (1u32 << (depth - 1)) as f32;
+                        (luma / max, (cb - mid) / max, (cr - mid) / max)
+                    } else {
+                        let (black, mid) = (16.0 * scale, 128.0 * scale);
+                        (
+                            (luma - black) / (219.0 * scale),
+                            (cb - mid) / (224.0 * scale),
+                            (cr - mid) / (224.0 * scale),
+                        )
+                    };
+
+                    // matrix_coefficients 0 is GBR carried in the Y, Cb and Cr planes
+                    if kr == 0.0 {
+                        let offset = if full_range { 0.5 } else { 0.5 * 224.0 / 219.0 };
+                        return [cr + offset, luma, cb + offset];
+                    }
+
+                    let red = (2.0 * (1.0 - kr)).mul_add(cr, luma);
+                    let blue = (2.0 * (1.0 - kb)).mul_add(cb, luma);
+                    let green = kb.mul_add(-blue, kr.mul_add(-red, luma)) / (1.0 - kr - kb);
+
+                    [red, green, blue]
+                })
+                .map(|sample| sample.clamp(0.0, 1.0))
+                .collect()
+        }
+        (Some(luma_plane), None, None, _) => pixels
+            .flat_map(|(x, y)| [luma(at(luma_plane, x, y), depth, full_range).clamp(0.0, 1.0); 3])
+            .collect(),
+        (_, _, _, [Some(red), Some(green), Some(blue)]) => pixels
+            .flat_map(|(x, y)| [red, green, blue].map(|plane| at(plane, x, y) as f32 / max))
+            .collect(),
+        _ => bail!(
+            "can't convert {:?} to RGB",
+            image
+                .planes
+                .iter()
+                .map(|plane| plane.component)
+                .collect::<Vec<_>>()
+        ),
+    };
+
+    Ok(samples)
+}
+
+fn luma(sample: u16, depth: u8, full_range: bool) -> f32 {
+    if full_range {
+        sample as f32 / ((1u32 << depth) - 1) as f32
+    } else {
+        let scale = (1u32 << depth.saturating_sub(8)) as f32;
+        let black = 16.0 * scale;
+        (sample as f32 - black) / (219.0 * scale)
+    }
+}
+
+fn srgb_eotf(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// the PQ EOTF, normalized to 10000 nits
+fn pq_eotf(value: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+    let e = value.powf(1.0 / M2);
+    ((e - C1).max(0.0) / C3.mul_add(-e, C2)).powf(1.0 / M1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hevc::ChromaFormat;
+
+    // a single channel map from gain 0 to 1 stop, for headrooms 0 and 1 stop
+    fn tmap_payload(common_denominator: bool) -> Vec<u8> {
+        let mut data = vec![0, 0, 0, 0, 0];
+        if common_denominator {
+            data.push(0x08);
+            data.extend(2u32.to_be_bytes());
+            for numerator in [0, 2, 0, 2, 2, 0, 0] {
+                data.extend((numerator as u32).to_be_bytes());
+            }
+        } else {
+            data.push(0x00);
+            for (numerator, denominator) in [(0, 1), (1, 1), (0, 1), (1, 1), (1, 1), (0, 1), (0, 1)]
+            {
+                data.extend((numerator as u32).to_be_bytes());
+                data.extend((denominator as u32).to_be_bytes());
+            }
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_tmap_metadata() {
+        for common_denominator in [false, true] {
+            let metadata = ToneMapMetadata::parse(&tmap_payload(common_denominator)).unwrap();
+
+            assert_eq!(
+                (metadata.base_hdr_headroom, metadata.alternate_hdr_headroom),
+                (0.0, 1.0)
+            );
+            assert_eq!(
+                *metadata.channels,
+                [GainMapChannel {
+                    gain_map_min: 0.0,
+                    gain_map_max: 1.0,
+                    gamma: 1.0,
+                    base_offset: 0.0,
+                    alternate_offset: 0.0,
+                }]
+            );
+            assert_eq!(metadata.weight(0.5), 0.5);
+            assert_eq!(metadata.weight(3.0), 1.0);
+        }
+
+        assert!(ToneMapMetadata::parse(&tmap_payload(false)[..20]).is_err());
+    }
+
+    #[test]
+    fn test_apple_headroom_from_xmp() {
+        assert_eq!(
+            apple_headroom_from_xmp(r#"<rdf:Description HDRGainMap:HDRGainMapHeadroom="3.5"/>"#),
+            Some(3.5)
+        );
+        assert_eq!(
+            apple_headroom_from_xmp(
+                "<HDRGainMap:HDRGainMapHeadroom>2.25</HDRGainMap:HDRGainMapHeadroom>"
+            ),
+            Some(2.25)
+        );
+        assert_eq!(apple_headroom_from_xmp("<x:xmpmeta/>"), None);
+    }
+
+    #[test]
+    fn test_apply_gain_map() {
+        let mut base = PlanarImage::new(2, 2, ChromaFormat::Monochrome, 8);
+        base.planes[0].samples.fill(255);
+        // full gain on the left, none on the right
+        let mut map = PlanarImage::new(2, 1, ChromaFormat::Monochrome, 8);
+        map.planes[0].samples.copy_from_slice(&[255, 0]);
+        let metadata =
+            GainMapMetadata::Iso21496(ToneMapMetadata::parse(&tmap_payload(false)).unwrap());
+
+        let hdr = apply_gain_map(&base, None, Some((&map, None, &metadata)), 2.0).unwrap();
+        assert_eq!(&hdr.samples[..6], [2.0, 2.0, 2.0, 1.0, 1.0, 1.0]);
+
+        // an SDR display gets the base image
+        let sdr = apply_gain_map(&base, None, Some((&map, None, &metadata)), 1.0).unwrap();
+        assert!(sdr.samples.iter().all(|&sample| sample == 1.0));
+
+        let apple = GainMapMetadata::Apple { headroom: 4.0 };
+        let hdr = apply_gain_map(&base, None, Some((&map, None, &apple)), 2.0).unwrap();
+        assert_eq!(&hdr.samples[..6], [2.0, 2.0, 2.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_gain_map_planes() {
+        let mut base = PlanarImage::new(2, 2, ChromaFormat::Monochrome, 8);
+        base.planes[0].samples.fill(255);
+        let metadata =
+            GainMapMetadata::Iso21496(ToneMapMetadata::parse(&tmap_payload(false)).unwrap());
+
+        // a 4:2:0 unci map may list its chroma before its luma, which is the one applied
+        let mut map = PlanarImage::new(4, 4, ChromaFormat::YUV420, 8);
+        map.planes.rotate_left(1);
+        map.planes[0].samples.fill(255);
+        map.planes[1].samples.fill(255);
+        let hdr = apply_gain_map(&base, None, Some((&map, None, &metadata)), 2.0).unwrap();
+        assert!(hdr.samples.iter().all(|&sample| sample == 1.0));
+
+        let empty = PlanarImage::new(0, 0, ChromaFormat::Monochrome, 8);
+        let err = apply_gain_map(&base, None, Some((&empty, None, &metadata)), 2.0).unwrap_err();
+        assert!(err.to_string().contains("empty 0x0 gain map"), "{err}");
+    }
+}
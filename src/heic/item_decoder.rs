@@ -0,0 +1,198 @@
+use anyhow::{Result, ensure};
+
+use crate::heif::{ComponentType, DecodeBudget, FourCC, ItemProperty};
+use crate::hevc::ChromaFormat;
+
+/// Decodes the coded data of one image item.
+///
+/// Decoders are looked up by the item's type, so a decoder for `av01` handles every AV1 item
+/// while grids, and anything else built out of coded items, are assembled by `HeicDecoder`.
+pub trait ItemDecoder {
+    /// The `infe` item type this decoder handles, e.g. `hvc1`.
+    fn item_type(&self) -> FourCC;
+
+    /// `data` is the item's payload as located by `iloc`; `properties` are the item's associated
+    /// properties, in `ipma` order, e.g. its decoder configuration and `ispe`. Buffers sized from
+    /// the bitstream rather than the `ispe` are charged to `budget` before they're allocated.
+    fn decode(
+        &self,
+        data: &[u8],
+        properties: &[&ItemProperty],
+        budget: &mut DecodeBudget,
+    ) -> Result<PlanarImage>;
+
+    /// False while `decode` can't produce pictures yet, so that `HeicDecoder` prefers
+    /// alternatives to such items and doesn't report them as decodable.
+    fn is_implemented(&self) -> bool {
+        true
+    }
+}
+
+/// A decoded image, one plane per component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanarImage {
+    pub width: u32,
+    pub height: u32,
+    /// the subsampling of the Cb and Cr planes; `YUV444` for images without chroma planes,
+    /// such as RGB
+    pub chroma_format: ChromaFormat,
+    /// the largest bit depth of any plane
+    pub bit_depth: u8,
+    /// Y, Cb, Cr for coded images; uncompressed images keep the order of their `uncC`
+    pub planes: Box<[Plane]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plane {
+    pub component: ComponentType,
+    pub width: u32,
+    pub height: u32,
+    /// row-major, `width` samples per row
+    pub samples: Box<[u16]>,
+}
+
+impl Plane {
+    pub fn new(component: ComponentType, width: u32, height: u32) -> Self {
+        Self {
+            component,
+            width,
+            height,
+            samples: vec![0; width as usize * height as usize].into_boxed_slice(),
+        }
+    }
+
+    pub fn row(&self, y: u32) -> &[u16] {
+        let start = (y * self.width) as usize;
+        &self.samples[start..start + self.width as usize]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u16] {
+        let start = (y * self.width) as usize;
+        &mut self.samples[start..start + self.width as usize]
+    }
+}
+
+impl PlanarImage {
+    /// A black (all zero) image with planes sized for `chroma_format`.
+    pub fn new(width: u32, height: u32, chroma_format: ChromaFormat, bit_depth: u8) -> Self {
+        let planes = match chroma_format {
+            ChromaFormat::Monochrome => vec![Plane::new(ComponentType::Monochrome, width, height)],
+            _ => {
+                let (sub_x, sub_y) = subsampling(chroma_format);
+                let (chroma_width, chroma_height) = (width.div_ceil(sub_x), height.div_ceil(sub_y));
+
+                vec![
+                    Plane::new(ComponentType::Y, width, height),
+                    Plane::new(ComponentType::Cb, chroma_width, chroma_height),
+                    Plane::new(ComponentType::Cr, chroma_width, chroma_height),
+                ]
+            }
+        };
+
+        Self {
+            width,
+            height,
+            chroma_format,
+            bit_depth,
+            planes: planes.into_boxed_slice(),
+        }
+    }
+
+    /// Copies `tile` into this image with its top-left corner at (`x`, `y`) in luma samples,
+    /// clipping whatever falls outside.
+    pub fn paste(&mut self, tile: &Self, x: u32, y: u32) -> Result<()> {
+        ensure!(
+            tile.chroma_format == self.chroma_format && tile.bit_depth == self.bit_depth,
+            "tile is {:?} {}-bit, but the image is {:?} {}-bit",
+            tile.chroma_format,
+            tile.bit_depth,
+            self.chroma_format,
+            self.bit_depth
+        );
+
+        ensure!(
+            self.planes
+                .iter()
+                .map(|plane| plane.component)
+                .eq(tile.planes.iter().map(|plane| plane.component)),
+            "tile components don't match the image"
+        );
+
+        for (dst, src) in self.planes.iter_mut().zip(&tile.planes) {
+            let (sub_x, sub_y) = if dst.component.is_chroma() {
+                subsampling(self.chroma_format)
+            } else {
+                (1, 1)
+            };
+            let (x, y) = (x / sub_x, y / sub_y);
+
+            let columns = src.width.min(dst.width.saturating_sub(x));
+            let rows = src.height.min(dst.height.saturating_sub(y));
+
+            for row in 0..rows {
+                dst.row_mut(y + row)[x as usize..(x + columns) as usize]
+                    .copy_from_slice(&src.row(row)[..columns as usize]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A black image of the given size with the same planes as `self`.
+    ///
+    /// Luma and RGB samples are zero, chroma samples are at their midpoint.
+    pub fn empty_like(&self, width: u32, height: u32) -> Self {
+        let (sub_x, sub_y) = subsampling(self.chroma_format);
+        let neutral = 1u16 << self.bit_depth.saturating_sub(1);
+
+        let planes = self.planes.iter().map(|plane| {
+            if plane.component.is_chroma() {
+                let mut plane = Plane::new(
+                    plane.component,
+                    width.div_ceil(sub_x),
+                    height.div_ceil(sub_y),
+                );
+                plane.samples.fill(neutral);
+                plane
+            } else {
+                Plane::new(plane.component, width, height)
+            }
+        });
+
+        Self {
+            width,
+            height,
+            planes: planes.collect(),
+            ..*self
+        }
+    }
+
+    /// The samples of all planes interleaved pixel by pixel, e.g. RGBRGB..., or `None` when
+    /// the chroma planes are subsampled.
+    pub fn interleaved(&self) -> Option<Box<[u16]>> {
+        if self
+            .planes
+            .iter()
+            .any(|plane| (plane.width, plane.height) != (self.width, self.height))
+        {
+            return None;
+        }
+
+        let pixels = self.width as usize * self.height as usize;
+
+        Some(
+            (0..pixels)
+                .flat_map(|i| self.planes.iter().map(move |plane| plane.samples[i]))
+                .collect(),
+        )
+    }
+}
+
+// (SubWidthC, SubHeightC)
+const fn subsampling(chroma_format: ChromaFormat) -> (u32, u32) {
+    match chroma_format {
+        ChromaFormat::Monochrome | ChromaFormat::YUV444 => (1, 1),
+        ChromaFormat::YUV420 => (2, 2),
+        ChromaFormat::YUV422 => (2, 1),
+    }
+}
@@ -0,0 +1,217 @@
+use std::borrow::Cow;
+
+use anyhow::{Result, bail, ensure};
+
+use crate::heic::{ItemDecoder, PlanarImage, Plane};
+use crate::heif::{ComponentType, DecodeBudget, FourCC, ItemProperty};
+use crate::hevc::ChromaFormat;
+use crate::jpeg::{FrameHeader, decode_jpeg, frame_buffer_size, read_frame_header};
+
+/// The built-in decoder for `jpeg` items: baseline and extended sequential Huffman coded JPEG,
+/// grayscale or YCbCr.
+#[derive(Debug, Default)]
+pub struct JpegItemDecoder;
+
+impl ItemDecoder for JpegItemDecoder {
+    fn item_type(&self) -> FourCC {
+        FourCC(*b"jpeg")
+    }
+
+    fn decode(
+        &self,
+        data: &[u8],
+        properties: &[&ItemProperty],
+        budget: &mut DecodeBudget,
+    ) -> Result<PlanarImage> {
+        let mut extents = None;
+        let mut data = Cow::Borrowed(data);
+
+        for property in properties {
+            match property {
+                ItemProperty::JpegConfiguration(jpgc) => {
+                    data = Cow::Owned([&jpgc.prefix, data.as_ref()].concat());
+                }
+                ItemProperty::ImageSpatialExtentsProperty(ispe) => extents = Some(ispe),
+                _ => {}
+            }
+        }
+
+        // the frame is sized by the JPEG itself, so check it against the ispe that was
+        // accounted for before allocating anything
+        let frame = read_frame_header(&data)?;
+        if let Some(ispe) = extents {
+            ensure!(
+                (frame.width as u32, frame.height as u32) == (ispe.image_width, ispe.image_height),
+                "{}x{} JPEG in a {}x{} item",
+                frame.width,
+                frame.height,
+                ispe.image_width,
+                ispe.image_height
+            );
+        }
+        let chroma_format = chroma_format(&frame)?;
+
+        // the component buffers, then the planes cropped out of them
+        budget.charge_image(
+            frame.width as u32,
+            frame.height as u32,
+            2 * frame_buffer_size(&frame),
+        )?;
+        let image = decode_jpeg(&data)?;
+
+        let components: &[ComponentType] = match chroma_format {
+            ChromaFormat::Monochrome => &[ComponentType::Monochrome],
+            _ => &[ComponentType::Y, ComponentType::Cb, ComponentType::Cr],
+        };
+
+        let planes = image
+            .planes
+            .into_iter()
+            .zip(components)
+            .map(|(plane, &component)| Plane {
+                component,
+                width: plane.width,
+                height: plane.height,
+                samples: plane.samples,
+            })
+            .collect();
+
+        Ok(PlanarImage {
+            width: frame.width as u32,
+            height: frame.height as u32,
+            chroma_format,
+            bit_depth: frame.precision,
+            planes,
+        })
+    }
+}
+
+// the chroma format of a grayscale or YCbCr frame from its sampling factors
+fn chroma_format(frame: &FrameHeader) -> Result<ChromaFormat> {
+    let sampling = |i: usize| {
+        let c = &frame.components[i];
+        (c.horizontal_sampling, c.vertical_sampling)
+    };
+
+    match frame.components.len() {
+        1 => Ok(ChromaFormat::Monochrome),
+        3 => {
+            let ((luma_h, luma_v), cb, cr) = (sampling(0), sampling(1), sampling(2));
+            ensure!(cb == cr, "Cb and Cr are sampled differently");
+
+            match (luma_h / cb.0, luma_v / cb.1, luma_h % cb.0, luma_v % cb.1) {
+                (1, 1, 0, 0) => Ok(ChromaFormat::YUV444),
+                (2, 1, 0, 0) => Ok(ChromaFormat::YUV422),
+                (2, 2, 0, 0) => Ok(ChromaFormat::YUV420),
+                _ => bail!("unsupported sampling factors {:?}", [sampling(0), cb, cr]),
+            }
+        }
+        n => bail!("unsupported JPEG with {n} components"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HeifError;
+    use crate::heif::{ImageSpatialExtentsPropertyBox, JpegConfigurationBox};
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        [
+            &[0xFF, marker][..],
+            &(payload.len() as u16 + 2).to_be_bytes(),
+            payload,
+        ]
+        .concat()
+    }
+
+    // SOI and the tables of an 8x8 grayscale frame: DC categories as 4-bit codes, AC only EOB
+    fn headers() -> Vec<u8> {
+        let mut dht = vec![0x00, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        dht.extend(0..12);
+        dht.extend([0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00]);
+
+        [
+            vec![0xFF, 0xD8],
+            segment(0xDB, &[&[0][..], &[1; 64]].concat()),
+            segment(0xC4, &dht),
+            segment(0xC0, &[8, 0, 8, 0, 8, 1, 1, 0x11, 0]),
+        ]
+        .concat()
+    }
+
+    // a scan of one mid-grey block: DC category 0 and EOB, padded with 1s
+    fn scan() -> Vec<u8> {
+        [
+            segment(0xDA, &[1, 1, 0x00, 0, 63, 0]),
+            vec![0x07, 0xFF, 0xD9],
+        ]
+        .concat()
+    }
+
+    fn ispe(image_width: u32, image_height: u32) -> ItemProperty {
+        ItemProperty::ImageSpatialExtentsProperty(ImageSpatialExtentsPropertyBox {
+            image_width,
+            image_height,
+        })
+    }
+
+    #[test]
+    fn test_jpgc_prefix() {
+        let jpgc = ItemProperty::JpegConfiguration(JpegConfigurationBox {
+            prefix: headers().into(),
+        });
+
+        let image = JpegItemDecoder
+            .decode(&scan(), &[&jpgc, &ispe(8, 8)], &mut DecodeBudget::default())
+            .unwrap();
+
+        assert_eq!(image.chroma_format, ChromaFormat::Monochrome);
+        assert_eq!((image.width, image.height, image.bit_depth), (8, 8, 8));
+        assert!(image.planes[0].samples.iter().all(|&s| s == 128));
+
+        // without jpgC the item data alone has no frame
+        assert!(
+            JpegItemDecoder
+                .decode(&scan(), &[&ispe(8, 8)], &mut DecodeBudget::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_frame_size_is_charged() {
+        let huge = [
+            vec![0xFF, 0xD8],
+            segment(0xC0, &[8, 0xFF, 0xFF, 0xFF, 0xFF, 1, 1, 0x11, 0]),
+        ]
+        .concat();
+
+        // no ispe to check the frame against, so only the budget stands in the way
+        let err = JpegItemDecoder
+            .decode(&huge, &[], &mut DecodeBudget::default())
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<HeifError>(),
+                Some(HeifError::LimitExceeded {
+                    limit: "image pixels",
+                    ..
+                })
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_size_must_match_ispe() {
+        let data = [headers(), scan()].concat();
+
+        let err = JpegItemDecoder
+            .decode(&data, &[&ispe(4096, 4096)], &mut DecodeBudget::default())
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("8x8 JPEG in a 4096x4096 item"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Result, anyhow, ensure};
+
+use crate::heic::{ItemDecoder, PlanarImage};
+use crate::heif::{DecodeBudget, FourCC, ItemProperty};
+use crate::hevc::ChromaFormat;
+
+/// The built-in decoder for `mski` mask items: `mskC` bits per pixel, packed most significant
+/// bit first with no padding between rows.
+#[derive(Debug, Default)]
+pub struct MaskItemDecoder;
+
+impl ItemDecoder for MaskItemDecoder {
+    fn item_type(&self) -> FourCC {
+        FourCC(*b"mski")
+    }
+
+    fn decode(
+        &self,
+        data: &[u8],
+        properties: &[&ItemProperty],
+        _budget: &mut DecodeBudget,
+    ) -> Result<PlanarImage> {
+        let (mut config, mut extents) = (None, None);
+
+        for property in properties {
+            match property {
+                ItemProperty::MaskConfiguration(mskc) => config = Some(mskc),
+                ItemProperty::ImageSpatialExtentsProperty(ispe) => extents = Some(ispe),
+                _ => {}
+            }
+        }
+
+        let bits_per_pixel = config
+            .ok_or_else(|| anyhow!("missing mskC property"))?
+            .bits_per_pixel;
+        let extents = extents.ok_or_else(|| anyhow!("missing ispe property"))?;
+        ensure!(
+            (1..=16).contains(&bits_per_pixel),
+            "unsupported mask of {bits_per_pixel} bits per pixel"
+        );
+
+        let (width, height) = (extents.image_width, extents.image_height);
+        let needed = (width as u64 * height as u64 * bits_per_pixel as u64).div_ceil(8);
+        ensure!(
+            data.len() as u64 >= needed,
+            "{width}x{height} mask needs {needed} bytes, the item has {}",
+            data.len()
+        );
+
+        let mut image = PlanarImage::new(width, height, ChromaFormat::Monochrome, bits_per_pixel);
+        let mut bit = 0;
+
+        for sample in image.planes[0].samples.iter_mut() {
+            for _ in 0..bits_per_pixel {
+                let set = (data[bit / 8] >> (7 - bit % 8)) & 1;
+                *sample = (*sample << 1) | set as u16;
+                bit += 1;
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heif::{ImageSpatialExtentsPropertyBox, MaskConfigurationBox};
+
+    #[test]
+    fn test_packed_mask() {
+        let mskc = ItemProperty::MaskConfiguration(MaskConfigurationBox { bits_per_pixel: 1 });
+        let ispe = ItemProperty::ImageSpatialExtentsProperty(ImageSpatialExtentsPropertyBox {
+            image_width: 3,
+            image_height: 3,
+        });
+
+        // a plus sign, rows running across byte boundaries
+        let image = MaskItemDecoder
+            .decode(
+                &[0b0101_1101, 0b0000_0000],
+                &[&mskc, &ispe],
+                &mut DecodeBudget::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            (image.bit_depth, image.chroma_format),
+            (1, ChromaFormat::Monochrome)
+        );
+        assert_eq!(*image.planes[0].samples, [0, 1, 0, 1, 1, 1, 0, 1, 0]);
+
+        let err = MaskItemDecoder
+            .decode(&[0xFF], &[&mskc, &ispe], &mut DecodeBudget::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("needs 2 bytes"), "{err}");
+    }
+}
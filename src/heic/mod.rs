@@ -1,3 +1,15 @@
 mod decoder;
+mod gain_map;
+mod hevc_decoder;
+mod item_decoder;
+mod jpeg_decoder;
+mod mask_decoder;
+mod unci_decoder;
 
 pub use decoder::*;
+pub use gain_map::*;
+pub use hevc_decoder::*;
+pub use item_decoder::*;
+pub use jpeg_decoder::*;
+pub use mask_decoder::*;
+pub use unci_decoder::*;
@@ -0,0 +1,785 @@
+use anyhow::{Result, anyhow, bail, ensure};
+
+use crate::heic::{ItemDecoder, PlanarImage, Plane};
+use crate::heif::{
+    ComponentDefinitionBox, ComponentFormat, ComponentType, DecodeBudget, FourCC,
+    ImageSpatialExtentsPropertyBox, InterleaveType, ItemProperty, SamplingType,
+    UncompressedComponent, UncompressedFrameConfigBox,
+};
+use crate::hevc::ChromaFormat;
+
+/// The built-in decoder for uncompressed `unci` items, ISO/IEC 23001-17.
+///
+/// Handles unsigned integer components of up to 16 bits in every interleave mode but multi-Y,
+/// bit-packed or in blocks. `Padded` components are read and dropped.
+#[derive(Debug, Default)]
+pub struct UncompressedItemDecoder;
+
+impl ItemDecoder for UncompressedItemDecoder {
+    fn item_type(&self) -> FourCC {
+        FourCC(*b"unci")
+    }
+
+    fn decode(
+        &self,
+        data: &[u8],
+        properties: &[&ItemProperty],
+        budget: &mut DecodeBudget,
+    ) -> Result<PlanarImage> {
+        let (mut config, mut definitions, mut extents) = (None, None, None);
+
+        for property in properties {
+            match property {
+                ItemProperty::UncompressedFrameConfig(uncc) => config = Some(uncc),
+                ItemProperty::ComponentDefinition(cmpd) => definitions = Some(cmpd),
+                ItemProperty::ImageSpatialExtentsProperty(ispe) => extents = Some(ispe),
+                _ => {}
+            }
+        }
+
+        let config = config.ok_or_else(|| anyhow!("missing uncC property"))?;
+        let extents = extents.ok_or_else(|| anyhow!("missing ispe property"))?;
+
+        Frame::new(config, definitions, extents, budget)?.decode(data)
+    }
+}
+
+/// The item being decoded, one plane per `uncC` component.
+struct Frame<'c> {
+    config: &'c UncompressedFrameConfigBox,
+    planes: Vec<Plane>,
+    tile_width: u32,
+    tile_height: u32,
+}
+
+impl<'c> Frame<'c> {
+    fn new(
+        config: &'c UncompressedFrameConfigBox,
+        definitions: Option<&ComponentDefinitionBox>,
+        extents: &ImageSpatialExtentsPropertyBox,
+        budget: &mut DecodeBudget,
+    ) -> Result<Self> {
+        let (width, height) = (extents.image_width, extents.image_height);
+
+        for component in &config.components {
+            ensure!(
+                component.format == ComponentFormat::Unsigned && component.bit_depth <= 16,
+                "unsupported {:?} component of {} bits",
+                component.format,
+                component.bit_depth
+            );
+            ensure!(
+                component.align_size == 0
+                    || (component.align_size <= 8
+                        && component.bit_depth <= component.align_size as u16 * 8),
+                "{}-bit component can't be aligned to {} bytes",
+                component.bit_depth,
+                component.align_size
+            );
+        }
+
+        ensure!(
+            !matches!(config.sampling_type, SamplingType::YCbCr411),
+            "4:1:1 sampling is not supported"
+        );
+        ensure!(
+            config.interleave_type != InterleaveType::MultiY,
+            "multi-Y interleave is not supported"
+        );
+        ensure!(
+            config.interleave_type != InterleaveType::Pixel
+                || config.sampling_type == SamplingType::NoSubsampling,
+            "pixel interleave can't be subsampled"
+        );
+        ensure!(
+            config.block_size <= 8,
+            "unsupported block_size {}",
+            config.block_size
+        );
+
+        ensure!(
+            width.is_multiple_of(config.num_tile_cols)
+                && height.is_multiple_of(config.num_tile_rows),
+            "{width}x{height} image can't be split into {}x{} tiles",
+            config.num_tile_cols,
+            config.num_tile_rows
+        );
+        let (tile_width, tile_height) =
+            (width / config.num_tile_cols, height / config.num_tile_rows);
+
+        let (sub_x, sub_y) = config.sampling_type.factors();
+        ensure!(
+            tile_width.is_multiple_of(sub_x) && tile_height.is_multiple_of(sub_y),
+            "{tile_width}x{tile_height} tiles can't be subsampled {:?}",
+            config.sampling_type
+        );
+
+        // one plane per component, however many the uncC lists, charged before any is allocated
+        let components = component_types(config, definitions)?;
+        let samples = components
+            .iter()
+            .map(|component| {
+                let pixels = width as u64 * height as u64;
+                if component.is_chroma() {
+                    pixels / (sub_x * sub_y) as u64
+                } else {
+                    pixels
+                }
+            })
+            .sum::<u64>();
+        budget.charge_image(width, height, samples * size_of::<u16>() as u64)?;
+
+        let planes = components
+            .into_iter()
+            .map(|component| {
+                if component.is_chroma() {
+                    Plane::new(component, width / sub_x, height / sub_y)
+                } else {
+                    Plane::new(component, width, height)
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            config,
+            planes,
+            tile_width,
+            tile_height,
+        })
+    }
+
+    fn decode(mut self, data: &[u8]) -> Result<PlanarImage> {
+        let config = self.config;
+        let mut cursor = BitCursor::new(data);
+
+        let tiles = (0..config.num_tile_rows)
+            .flat_map(|row| (0..config.num_tile_cols).map(move |column| (column, row)));
+        let all = (0..self.planes.len()).collect::<Vec<_>>();
+
+        match config.interleave_type {
+            InterleaveType::Component => {
+                for tile in tiles {
+                    let start = cursor.byte_position();
+                    for p in 0..self.planes.len() {
+                        self.read_plane(&mut cursor, p, tile)?;
+                    }
+                    cursor.pad(start, config.tile_align_size)?;
+                }
+            }
+            InterleaveType::TileComponent => {
+                for p in 0..self.planes.len() {
+                    for tile in tiles.clone() {
+                        let start = cursor.byte_position();
+                        self.read_plane(&mut cursor, p, tile)?;
+                        cursor.pad(start, config.tile_align_size)?;
+                    }
+                }
+            }
+            InterleaveType::Row => {
+                for tile in tiles {
+                    let start = cursor.byte_position();
+                    for y in 0..self.tile_height {
+                        for p in 0..self.planes.len() {
+                            let (_, sub_y) = self.factors(p);
+                            if y.is_multiple_of(sub_y) {
+                                self.read_row(&mut cursor, p, tile, y / sub_y)?;
+                            }
+                        }
+                    }
+                    cursor.pad(start, config.tile_align_size)?;
+                }
+            }
+            InterleaveType::Pixel => {
+                for tile in tiles {
+                    let start = cursor.byte_position();
+                    self.read_pixels(&mut cursor, &all, tile)?;
+                    cursor.pad(start, config.tile_align_size)?;
+                }
+            }
+            InterleaveType::Mixed => {
+                // luma (and anything else at full resolution) planar, then Cb and Cr together
+                let (chroma, other): (Vec<_>, Vec<_>) = all
+                    .iter()
+                    .partition(|&&p| self.planes[p].component.is_chroma());
+
+                for tile in tiles {
+                    let start = cursor.byte_position();
+                    for &p in &other {
+                        self.read_plane(&mut cursor, p, tile)?;
+                    }
+                    if !chroma.is_empty() {
+                        self.read_pixels(&mut cursor, &chroma, tile)?;
+                    }
+                    cursor.pad(start, config.tile_align_size)?;
+                }
+            }
+            InterleaveType::MultiY => unreachable!("rejected in Frame::new"),
+        }
+
+        let bit_depth = config
+            .components
+            .iter()
+            .zip(&self.planes)
+            .filter(|(_, plane)| plane.component != ComponentType::Padded)
+            .map(|(component, _)| component.bit_depth as u8)
+            .max()
+            .unwrap_or(8);
+
+        let planes = self
+            .planes
+            .into_iter()
+            .filter(|plane| plane.component != ComponentType::Padded)
+            .collect::<Box<[_]>>();
+
+        let chroma_format = match config.sampling_type {
+            SamplingType::YCbCr422 => ChromaFormat::YUV422,
+            SamplingType::YCbCr420 => ChromaFormat::YUV420,
+            _ if planes.len() == 1 => ChromaFormat::Monochrome,
+            _ => ChromaFormat::YUV444,
+        };
+
+        let (width, height) = (
+            self.tile_width * config.num_tile_cols,
+            self.tile_height * config.num_tile_rows,
+        );
+
+        Ok(PlanarImage {
+            width,
+            height,
+            chroma_format,
+            bit_depth,
+            planes,
+        })
+    }
+
+    // (horizontal, vertical) subsampling of plane `p`
+    fn factors(&self, p: usize) -> (u32, u32) {
+        if self.planes[p].component.is_chroma() {
+            self.config.sampling_type.factors()
+        } else {
+            (1, 1)
+        }
+    }
+
+    // the size of plane `p` within one tile
+    fn tile_size(&self, p: usize) -> (u32, u32) {
+        let (sub_x, sub_y) = self.factors(p);
+        (self.tile_width / sub_x, self.tile_height / sub_y)
+    }
+
+    fn read_plane(&mut self, cursor: &mut BitCursor, p: usize, tile: (u32, u32)) -> Result<()> {
+        for y in 0..self.tile_size(p).1 {
+            self.read_row(cursor, p, tile, y)?;
+        }
+        cursor.align();
+
+        Ok(())
+    }
+
+    // one row of plane `p` within `tile`, `y` in plane samples from the top of the tile
+    fn read_row(
+        &mut self,
+        cursor: &mut BitCursor,
+        p: usize,
+        (column, row): (u32, u32),
+        y: u32,
+    ) -> Result<()> {
+        let config = self.config;
+        let component = config.components[p];
+        let (tile_width, tile_height) = self.tile_size(p);
+
+        let start = cursor.byte_position();
+        let x0 = (column * tile_width) as usize;
+        let samples =
+            &mut self.planes[p].row_mut(row * tile_height + y)[x0..x0 + tile_width as usize];
+
+        if config.block_size == 0 {
+            for sample in samples {
+                *sample = read_value(cursor, config, &component)?;
+            }
+        } else {
+            // as many values as fit in each block
+            let per_block = (config.block_size as u16 * 8 / component.bit_depth) as usize;
+            ensure!(
+                per_block > 0,
+                "{}-bit component doesn't fit a {}-byte block",
+                component.bit_depth,
+                config.block_size
+            );
+
+            let depths = vec![component.bit_depth; per_block];
+            for chunk in samples.chunks_mut(per_block) {
+                read_block(cursor, config, &depths, chunk)?;
+            }
+        }
+
+        cursor.pad(start, config.row_align_size)
+    }
+
+    // all samples of the planes `ps` within `tile`, interleaved pixel by pixel; they all have
+    // the same subsampling
+    fn read_pixels(
+        &mut self,
+        cursor: &mut BitCursor,
+        ps: &[usize],
+        (column, row): (u32, u32),
+    ) -> Result<()> {
+        let config = self.config;
+        let (tile_width, tile_height) = self.tile_size(ps[0]);
+        let (x0, y0) = (column * tile_width, row * tile_height);
+
+        let depths = ps
+            .iter()
+            .map(|&p| config.components[p].bit_depth)
+            .collect::<Vec<_>>();
+        let mut pixel = vec![0; ps.len()];
+
+        for y in y0..y0 + tile_height {
+            let row_start = cursor.byte_position();
+
+            for x in x0..x0 + tile_width {
+                let pixel_start = cursor.byte_position();
+
+                if config.block_size == 0 {
+                    for (sample, &p) in pixel.iter_mut().zip(ps) {
+                        *sample = read_value(cursor, config, &config.components[p])?;
+                    }
+                } else {
+                    read_block(cursor, config, &depths, &mut pixel)?;
+                }
+
+                if config.pixel_size > 0 {
+                    cursor.align();
+                    let end = pixel_start + config.pixel_size as usize;
+                    ensure!(
+                        cursor.byte_position() <= end,
+                        "pixel is larger than pixel_size {}",
+                        config.pixel_size
+                    );
+                    cursor.seek(end);
+                }
+
+                for (&sample, &p) in pixel.iter().zip(ps) {
+                    self.planes[p].row_mut(y)[x as usize] = sample;
+                }
+            }
+
+            cursor.pad(row_start, config.row_align_size)?;
+        }
+
+        Ok(())
+    }
+}
+
+// the type of each uncC component, from cmpd or, without one, the profile
+fn component_types(
+    config: &UncompressedFrameConfigBox,
+    definitions: Option<&ComponentDefinitionBox>,
+) -> Result<Vec<ComponentType>> {
+    let types = match definitions {
+        Some(cmpd) => cmpd
+            .components
+            .iter()
+            .map(|definition| definition.component_type)
+            .collect(),
+        None => UncompressedFrameConfigBox::profile_components(config.profile)
+            .ok_or_else(|| anyhow!("missing cmpd property"))?
+            .to_vec(),
+    };
+
+    config
+        .components
+        .iter()
+        .map(|component| {
+            types
+                .get(component.component_index as usize)
+                .copied()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "component_index {} is out of range of {} components",
+                        component.component_index,
+                        types.len()
+                    )
+                })
+        })
+        .collect()
+}
+
+fn read_value(
+    cursor: &mut BitCursor,
+    config: &UncompressedFrameConfigBox,
+    component: &UncompressedComponent,
+) -> Result<u16> {
+    let little_endian = config.components_little_endian;
+
+    let value = if component.align_size > 0 {
+        // the value sits in the low bits of its own bytes
+        cursor.align();
+        cursor.read_bytes(component.align_size as usize, little_endian)?
+    } else if little_endian && component.bit_depth.is_multiple_of(8) && cursor.is_aligned() {
+        cursor.read_bytes(component.bit_depth as usize / 8, true)?
+    } else {
+        cursor.read_bits(component.bit_depth as u32)?
+    };
+
+    Ok((value & mask(component.bit_depth)) as u16)
+}
+
+// reads one block of `depths.len()` values and keeps the first `out.len()` of them
+//
+// the values are packed together from the most significant bit down, or from the least
+// significant bit up when block_reversed, at whichever end of the block isn't padding
+fn read_block(
+    cursor: &mut BitCursor,
+    config: &UncompressedFrameConfigBox,
+    depths: &[u16],
+    out: &mut [u16],
+) -> Result<()> {
+    let bits = config.block_size as u32 * 8;
+    let occupied = depths.iter().map(|&d| d as u32).sum::<u32>();
+    ensure!(
+        occupied <= bits,
+        "{occupied} bits of components don't fit a {}-byte block",
+        config.block_size
+    );
+
+    cursor.align();
+    let block = cursor.read_bytes(config.block_size as usize, config.block_little_endian)?;
+
+    let mut shift = if config.block_pad_lsb { bits } else { occupied };
+    let mut order = (0..depths.len()).collect::<Vec<_>>();
+    if config.block_reversed {
+        order.reverse();
+    }
+
+    for i in order {
+        shift -= depths[i] as u32;
+        if let Some(sample) = out.get_mut(i) {
+            *sample = ((block >> shift) & mask(depths[i])) as u16;
+        }
+    }
+
+    Ok(())
+}
+
+const fn mask(bit_depth: u16) -> u64 {
+    (1 << bit_depth) - 1
+}
+
+/// Reads big-endian bit fields and whole bytes from item data.
+struct BitCursor<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> BitCursor<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, bit: 0 }
+    }
+
+    const fn is_aligned(&self) -> bool {
+        self.bit.is_multiple_of(8)
+    }
+
+    const fn byte_position(&self) -> usize {
+        self.bit.div_ceil(8)
+    }
+
+    const fn align(&mut self) {
+        self.bit = self.byte_position() * 8;
+    }
+
+    const fn seek(&mut self, byte: usize) {
+        self.bit = byte * 8;
+    }
+
+    // aligns to a byte, then skips padding until a multiple of `alignment` bytes were read since
+    // `start`; 0 means no alignment, not even to a byte
+    fn pad(&mut self, start: usize, alignment: u32) -> Result<()> {
+        if alignment == 0 {
+            return Ok(());
+        }
+
+        self.align();
+        let read = self.byte_position() - start;
+        self.seek(start + read.next_multiple_of(alignment as usize));
+
+        ensure!(
+            self.byte_position() <= self.data.len(),
+            "item data is truncated"
+        );
+        Ok(())
+    }
+
+    fn read_bits(&mut self, n: u32) -> Result<u64> {
+        let mut value = 0u64;
+        let mut left = n;
+
+        while left > 0 {
+            let Some(&byte) = self.data.get(self.bit / 8) else {
+                bail!("item data is truncated");
+            };
+
+            let offset = (self.bit % 8) as u32;
+            let take = left.min(8 - offset);
+            let bits = (byte as u64 >> (8 - offset - take)) & ((1 << take) - 1);
+
+            value = (value << take) | bits;
+            self.bit += take as usize;
+            left -= take;
+        }
+
+        Ok(value)
+    }
+
+    // expects to be byte aligned
+    fn read_bytes(&mut self, n: usize, little_endian: bool) -> Result<u64> {
+        let start = self.bit / 8;
+        let bytes = self
+            .data
+            .get(start..start + n)
+            .ok_or_else(|| anyhow!("item data is truncated"))?;
+        self.bit += n * 8;
+
+        let fold = |value: u64, &byte: &u8| (value << 8) | byte as u64;
+
+        Ok(if little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HeifError;
+    use crate::heif::{ComponentDefinition, DecodeLimits};
+
+    fn config(
+        bit_depths: &[u16],
+        sampling_type: SamplingType,
+        interleave_type: InterleaveType,
+    ) -> UncompressedFrameConfigBox {
+        UncompressedFrameConfigBox {
+            profile: FourCC([0; 4]),
+            components: bit_depths
+                .iter()
+                .enumerate()
+                .map(|(i, &bit_depth)| UncompressedComponent {
+                    component_index: i as u16,
+                    bit_depth,
+                    format: ComponentFormat::Unsigned,
+                    align_size: 0,
+                })
+                .collect(),
+            sampling_type,
+            interleave_type,
+            block_size: 0,
+            components_little_endian: false,
+            block_pad_lsb: false,
+            block_little_endian: false,
+            block_reversed: false,
+            pad_unknown: false,
+            pixel_size: 0,
+            row_align_size: 0,
+            tile_align_size: 0,
+            num_tile_cols: 1,
+            num_tile_rows: 1,
+        }
+    }
+
+    fn cmpd(types: &[ComponentType]) -> ItemProperty {
+        ItemProperty::ComponentDefinition(ComponentDefinitionBox {
+            components: types
+                .iter()
+                .map(|&component_type| ComponentDefinition {
+                    component_type,
+                    uri: None,
+                })
+                .collect(),
+        })
+    }
+
+    fn ispe(image_width: u32, image_height: u32) -> ItemProperty {
+        ItemProperty::ImageSpatialExtentsProperty(ImageSpatialExtentsPropertyBox {
+            image_width,
+            image_height,
+        })
+    }
+
+    fn decode(data: &[u8], properties: &[ItemProperty]) -> Result<PlanarImage> {
+        UncompressedItemDecoder.decode(
+            data,
+            &properties.iter().collect::<Vec<_>>(),
+            &mut DecodeBudget::default(),
+        )
+    }
+
+    #[test]
+    fn test_rgb3_profile() {
+        let mut uncc = config(
+            &[8, 8, 8],
+            SamplingType::NoSubsampling,
+            InterleaveType::Pixel,
+        );
+        uncc.profile = FourCC(*b"rgb3");
+
+        let data = [1, 2, 3, 4, 5, 6];
+        let image = decode(
+            &data,
+            &[ItemProperty::UncompressedFrameConfig(uncc), ispe(2, 1)],
+        )
+        .unwrap();
+
+        let components = image.planes.iter().map(|plane| plane.component);
+        assert!(components.eq([
+            ComponentType::Red,
+            ComponentType::Green,
+            ComponentType::Blue
+        ]));
+        assert_eq!(*image.planes[0].samples, [1, 4]);
+        assert_eq!(*image.interleaved().unwrap(), data.map(u16::from));
+    }
+
+    #[test]
+    fn test_420_component_interleave_in_tiles() {
+        use ComponentType::{Cb, Cr, Y};
+
+        let mut uncc = config(
+            &[8, 8, 8],
+            SamplingType::YCbCr420,
+            InterleaveType::Component,
+        );
+        uncc.num_tile_cols = 2;
+        uncc.tile_align_size = 8;
+
+        // each 2x2 tile: 4 luma samples, one Cb, one Cr, padded to 8 bytes
+        let data = [
+            1, 2, 3, 4, 10, 20, 0, 0, //
+            5, 6, 7, 8, 11, 21, 0, 0,
+        ];
+        let image = decode(
+            &data,
+            &[cmpd(&[Y, Cb, Cr]), uncc_property(uncc), ispe(4, 2)],
+        )
+        .unwrap();
+
+        assert_eq!(image.chroma_format, ChromaFormat::YUV420);
+        assert_eq!(*image.planes[0].samples, [1, 2, 5, 6, 3, 4, 7, 8]);
+        assert_eq!(*image.planes[1].samples, [10, 11]);
+        assert_eq!(*image.planes[2].samples, [20, 21]);
+        assert!(image.interleaved().is_none());
+    }
+
+    #[test]
+    fn test_bit_packed_rows_and_blocks() {
+        // 12-bit monochrome, 3 samples bit-packed into 4.5 bytes, rows aligned to 2 bytes
+        let mut uncc = config(&[12], SamplingType::NoSubsampling, InterleaveType::Row);
+        uncc.row_align_size = 2;
+
+        let data = [0xAB, 0xC1, 0x23, 0x45, 0x60, 0x00];
+        let image = decode(
+            &data,
+            &[
+                cmpd(&[ComponentType::Monochrome]),
+                uncc_property(uncc),
+                ispe(3, 1),
+            ],
+        )
+        .unwrap();
+        assert_eq!(image.chroma_format, ChromaFormat::Monochrome);
+        assert_eq!(
+            (image.bit_depth, &*image.planes[0].samples),
+            (12, &[0xABC, 0x123, 0x456][..])
+        );
+
+        // 10-bit RGB plus 2 bits of padding in little-endian 4-byte blocks
+        let mut uncc = config(
+            &[10, 10, 10, 2],
+            SamplingType::NoSubsampling,
+            InterleaveType::Pixel,
+        );
+        uncc.block_size = 4;
+        uncc.block_little_endian = true;
+
+        let block: u32 = (0x3FF << 22) | (0x155 << 12) | (0x001 << 2);
+        let image = decode(
+            &block.to_le_bytes(),
+            &[
+                cmpd(&[
+                    ComponentType::Red,
+                    ComponentType::Green,
+                    ComponentType::Blue,
+                    ComponentType::Padded,
+                ]),
+                uncc_property(uncc),
+                ispe(1, 1),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(image.planes.len(), 3);
+        assert_eq!(*image.interleaved().unwrap(), [0x3FF, 0x155, 0x001]);
+    }
+
+    #[test]
+    fn test_truncated_data() {
+        let uncc = config(&[8], SamplingType::NoSubsampling, InterleaveType::Component);
+
+        let err = decode(
+            &[1, 2, 3],
+            &[
+                cmpd(&[ComponentType::Monochrome]),
+                uncc_property(uncc),
+                ispe(2, 2),
+            ],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{err}");
+    }
+
+    #[test]
+    fn test_planes_are_charged() {
+        // a few bytes of uncC asking for a thousand full-size planes
+        let mut uncc = config(
+            &[8; 1000],
+            SamplingType::NoSubsampling,
+            InterleaveType::Component,
+        );
+        for component in &mut uncc.components {
+            component.component_index = 0;
+        }
+        let mut budget = DecodeBudget::new(DecodeLimits {
+            max_total_allocation: 1 << 20,
+            ..DecodeLimits::default()
+        });
+
+        let err = UncompressedItemDecoder
+            .decode(
+                &[],
+                &[
+                    &cmpd(&[ComponentType::Monochrome]),
+                    &uncc_property(uncc),
+                    &ispe(1024, 1024),
+                ],
+                &mut budget,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<HeifError>(),
+                Some(HeifError::LimitExceeded {
+                    limit: "total allocation",
+                    ..
+                })
+            ),
+            "{err}"
+        );
+    }
+
+    const fn uncc_property(uncc: UncompressedFrameConfigBox) -> ItemProperty {
+        ItemProperty::UncompressedFrameConfig(uncc)
+    }
+}
@@ -1,6 +1,11 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::hevc::HEVCDecoderConfigurationRecord;
+use crate::av1::AV1CodecConfigurationRecord;
+use crate::error::HeifError;
+use crate::heif::ImageHandle;
+use crate::hevc::{HEVCDecoderConfigurationRecord, LHEVCDecoderConfigurationRecord};
+use crate::vvc::VvcDecoderConfigurationRecord;
 
 macro_rules! impl_box {
     ($box_struct:ident<$lifetime:lifetime>, $box_kind:expr) => {
@@ -19,16 +24,45 @@ macro_rules! impl_box {
 #[derive(Debug)]
 pub struct Heif<'a> {
     pub file_type_box: FileTypeBox<'a>,
-    pub meta_box: MetaBox<'a>,
+    /// the top-level `pict` meta, absent only in files that hold nothing but image sequences
+    pub meta_box: Option<MetaBox<'a>>,
+    /// the other top-level `meta` boxes, e.g. `mdir` iTunes metadata, in file order
+    pub other_meta_boxes: Box<[MetaBox<'a>]>,
+    pub movie_box: Option<MovieBox<'a>>,
+
+    /// spec violations tolerated while parsing in lenient mode
+    pub warnings: Box<[HeifError]>,
 }
 
 impl<'a> Heif<'a> {
-    pub const fn primary_item_id(&self) -> u32 {
-        self.meta_box.primary_item.item_id
+    pub fn primary_item_id(&self) -> Option<u32> {
+        self.meta_box.as_ref()?.primary_item_id()
+    }
+
+    /// Every `meta` box in the file: the top-level ones, then those of `moov`, then those of
+    /// each track.
+    pub fn meta_boxes(&self) -> impl Iterator<Item = &MetaBox<'a>> {
+        let movie = self.movie_box.as_ref();
+
+        self.meta_box
+            .iter()
+            .chain(self.other_meta_boxes.iter())
+            .chain(movie.map_or(&[][..], |moov| &moov.meta_boxes))
+            .chain(
+                self.tracks()
+                    .iter()
+                    .flat_map(|track| track.meta_boxes.iter()),
+            )
+    }
+
+    /// The first `meta` box anywhere in the file with the given handler, e.g. `mdir`.
+    pub fn meta_box_with_handler(&self, handler: &str) -> Option<&MetaBox<'a>> {
+        self.meta_boxes().find(|meta| meta.handler.kind == handler)
     }
 
     pub fn item_info_by_item_id(&self, target_item_id: u32) -> Option<&ItemInfoEntry<'a>> {
         self.meta_box
+            .as_ref()?
             .item_info
             .item_info_entries
             .iter()
@@ -36,16 +70,30 @@ impl<'a> Heif<'a> {
     }
 
     pub fn hevc_configuration_record(&self) -> Option<&HEVCDecoderConfigurationRecord> {
-        self.meta_box.item_properties.as_ref().and_then(|props| {
-            props
-                .container
-                .properties
-                .iter()
-                .find_map(|prop| match prop {
-                    ItemProperty::HevcDecoderConfiguration(config) => Some(config),
-                    _ => None,
-                })
-        })
+        self.meta_box
+            .as_ref()?
+            .item_properties
+            .as_ref()
+            .and_then(|props| {
+                props
+                    .container
+                    .properties
+                    .iter()
+                    .find_map(|prop| match prop {
+                        ItemProperty::HevcDecoderConfiguration(config) => Some(config),
+                        _ => None,
+                    })
+            })
+    }
+
+    pub fn primary_image(&self) -> Option<ImageHandle<'_, 'a>> {
+        self.meta_box.as_ref()?.primary_image()
+    }
+
+    pub fn tracks(&self) -> &[TrackBox<'a>] {
+        self.movie_box
+            .as_ref()
+            .map_or(&[], |movie| movie.tracks.as_ref())
     }
 }
 
@@ -73,7 +121,36 @@ impl<'a> TryFrom<&'a [u8]> for BoxKind<'a> {
 
 impl<'a> Debug for BoxKind<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(str::from_utf8(self.0).unwrap())
+        write!(f, "{}", self.0.escape_ascii())
+    }
+}
+
+/// An owned four-character code, for when a borrowed `BoxKind` can't outlive the reader (errors,
+/// lookups keyed by type).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FourCC(pub [u8; 4]);
+
+impl<'a> From<&BoxKind<'a>> for FourCC {
+    fn from(value: &BoxKind<'a>) -> Self {
+        Self(*value.0)
+    }
+}
+
+impl From<&[u8; 4]> for FourCC {
+    fn from(value: &[u8; 4]) -> Self {
+        Self(*value)
+    }
+}
+
+impl Display for FourCC {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.escape_ascii())
+    }
+}
+
+impl Debug for FourCC {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0.escape_ascii().to_string())
     }
 }
 
@@ -126,6 +203,8 @@ pub enum DataEntryBaseBox<'a> {
 
 #[derive(Debug)]
 pub struct DataEntryUrlBox<'a> {
+    /// the data is in this file, and `location` is empty
+    pub self_contained: bool,
     pub location: &'a str,
 }
 
@@ -133,6 +212,7 @@ impl_box!(DataEntryUrlBox<'a>, b"url ");
 
 #[derive(Debug)]
 pub struct DataEntryUrnBox<'a> {
+    pub self_contained: bool,
     pub name: &'a str,
     pub location: &'a str,
 }
@@ -159,7 +239,7 @@ pub struct PrimaryItemBox {
 
 impl_box!(PrimaryItemBox, b"pitm");
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ItemInfoBox<'a> {
     pub item_info_entries: Box<[ItemInfoEntry<'a>]>,
 }
@@ -176,8 +256,62 @@ pub enum ItemType<'a> {
         item_uri_type: &'a str,
     },
     Hvc1,
+    /// an HEVC tile item, decoded as part of the `hvc1` item it references with `tbas`
+    Hvt1,
+    Lhv1,
+    Vvc1,
+    Av01,
+    Unci,
+    Jpeg,
     Grid,
+    /// a tone mapped derived image: the base image and an ISO 21496-1 gain map, through `dimg`
+    Tmap,
+    /// regions of the images it describes through `cdsc`, see `RegionItem`
+    Rgan,
+    /// a mask, decoded to a monochrome image of `mskC` bits per pixel
+    Mski,
     Exif,
+    /// any other item type, e.g. `iovl`, `iden` or a vendor's own
+    Other(FourCC),
+}
+
+impl ItemType<'_> {
+    pub const fn four_cc(&self) -> FourCC {
+        match self {
+            Self::Mime { .. } => FourCC(*b"mime"),
+            Self::Uri { .. } => FourCC(*b"uri "),
+            Self::Hvc1 => FourCC(*b"hvc1"),
+            Self::Hvt1 => FourCC(*b"hvt1"),
+            Self::Lhv1 => FourCC(*b"lhv1"),
+            Self::Vvc1 => FourCC(*b"vvc1"),
+            Self::Av01 => FourCC(*b"av01"),
+            Self::Unci => FourCC(*b"unci"),
+            Self::Jpeg => FourCC(*b"jpeg"),
+            Self::Grid => FourCC(*b"grid"),
+            Self::Tmap => FourCC(*b"tmap"),
+            Self::Rgan => FourCC(*b"rgan"),
+            Self::Mski => FourCC(*b"mski"),
+            Self::Exif => FourCC(*b"Exif"),
+            Self::Other(four_cc) => *four_cc,
+        }
+    }
+
+    /// Whether items of this type are images, as opposed to metadata. `hvt1` tiles are only
+    /// part of one.
+    pub const fn is_image(&self) -> bool {
+        matches!(
+            self,
+            Self::Hvc1
+                | Self::Lhv1
+                | Self::Vvc1
+                | Self::Av01
+                | Self::Unci
+                | Self::Jpeg
+                | Self::Grid
+                | Self::Tmap
+                | Self::Mski
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -185,13 +319,69 @@ pub enum ItemInfoEntry<'a> {
     Fixed {
         item_id: u32,
         item_name: &'a str,
+        /// 1-based index into `ipro`, 0 when the item isn't protected
         item_protection_index: u16,
         item_type: ItemType<'a>,
+        /// not meant to be displayed on its own, from the `infe` flags
+        hidden: bool,
+        /// only in version 1 entries
+        extension: Option<ItemInfoExtension<'a>>,
     },
 }
 
 impl_box!(ItemInfoEntry<'a>, b"infe");
 
+/// The `extension_type` payload of a version 1 `infe`.
+#[derive(Debug)]
+pub enum ItemInfoExtension<'a> {
+    /// `fdel`: how the item was delivered over FLUTE, ISO/IEC 14496-12 8.11.6.2
+    FileDelivery {
+        content_location: &'a str,
+        content_md5: &'a str,
+        content_length: u64,
+        transfer_length: u64,
+        group_ids: Box<[u32]>,
+    },
+    Other {
+        extension_type: FourCC,
+        data: &'a [u8],
+    },
+}
+
+/// `ipro`, the protection schemes of protected items
+#[derive(Debug)]
+pub struct ItemProtectionBox<'a> {
+    pub protection_schemes: Box<[ProtectionSchemeInfoBox<'a>]>,
+}
+
+impl_box!(ItemProtectionBox<'a>, b"ipro");
+
+/// `sinf`: how an item was protected and what it was before.
+#[derive(Debug)]
+pub struct ProtectionSchemeInfoBox<'a> {
+    /// the item type of the unprotected item, from `frma`
+    pub original_format: FourCC,
+    pub scheme_type: Option<SchemeTypeBox<'a>>,
+    pub scheme_information: Option<SchemeInformationBox<'a>>,
+}
+
+impl_box!(ProtectionSchemeInfoBox<'a>, b"sinf");
+
+#[derive(Debug)]
+pub struct SchemeTypeBox<'a> {
+    pub scheme_type: FourCC,
+    pub scheme_version: u32,
+    pub scheme_uri: Option<&'a str>,
+}
+
+impl_box!(SchemeTypeBox<'a>, b"schm");
+
+/// `schi`, left unparsed: its boxes are defined by the scheme
+#[derive(Debug)]
+pub struct SchemeInformationBox<'a>(pub &'a [u8]);
+
+impl_box!(SchemeInformationBox<'a>, b"schi");
+
 #[derive(Debug)]
 pub struct ItemReferenceBox<'a> {
     pub references: Box<[SingleItemReferenceBox<'a>]>,
@@ -225,9 +415,29 @@ impl_box!(ItemPropertyContainerBox, b"ipco");
 pub enum ItemProperty {
     ColorInformation(ColorInformationBox),
     HevcDecoderConfiguration(HEVCDecoderConfigurationRecord),
+    LHevcDecoderConfiguration(LHEVCDecoderConfigurationRecord),
+    OperatingPointsInformation(OperatingPointsInformationBox),
+    LayerSelector(LayerSelectorBox),
+    VvcDecoderConfiguration(VvcDecoderConfigurationRecord),
+    Av1CodecConfiguration(AV1CodecConfigurationRecord),
     ImageSpatialExtentsProperty(ImageSpatialExtentsPropertyBox),
     ImageRotation(ImageRotationBox),
     PixelInformationProperty(PixelInformationPropertyBox),
+    ComponentDefinition(ComponentDefinitionBox),
+    UncompressedFrameConfig(UncompressedFrameConfigBox),
+    JpegConfiguration(JpegConfigurationBox),
+    AuxiliaryType(AuxiliaryTypePropertyBox),
+    MaskConfiguration(MaskConfigurationBox),
+    ContentLightLevel(ContentLightLevelBox),
+    MasteringDisplayColourVolume(MasteringDisplayColourVolumeBox),
+    ContentColourVolume(ContentColourVolumeBox),
+    AmbientViewingEnvironment(AmbientViewingEnvironmentBox),
+    UserDescription(UserDescriptionBox),
+    AccessibilityText(AccessibilityTextBox),
+    CreationTime(CreationTimeBox),
+    ModificationTime(ModificationTimeBox),
+    /// a property we don't parse, kept so `ipma` indices still line up
+    Other(FourCC),
 }
 
 #[derive(Debug)]
@@ -238,7 +448,21 @@ pub struct ItemPropertyAssociationBox {
 impl_box!(ItemPropertyAssociationBox, b"ipma");
 
 #[derive(Debug)]
-pub struct ColorInformationBox {}
+pub struct ColorInformationBox {
+    /// `nclx`, `rICC` or `prof`
+    pub colour_type: FourCC,
+    /// only for `nclx`
+    pub nclx: Option<NclxColourInformation>,
+}
+
+/// The code points of an `nclx` `colr` box, as in ISO/IEC 23091-2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NclxColourInformation {
+    pub colour_primaries: u16,
+    pub transfer_characteristics: u16,
+    pub matrix_coefficients: u16,
+    pub full_range_flag: bool,
+}
 
 impl_box!(ColorInformationBox, b"colr");
 
@@ -250,6 +474,216 @@ pub struct ImageSpatialExtentsPropertyBox {
 
 impl_box!(ImageSpatialExtentsPropertyBox, b"ispe");
 
+/// `auxC`, ISO/IEC 23008-12 6.5.8: what an auxiliary image (one with an `auxl` reference)
+/// holds, e.g. `urn:mpeg:mpegB:cicp:systems:auxiliary:alpha`
+#[derive(Debug)]
+pub struct AuxiliaryTypePropertyBox {
+    pub aux_type: Box<str>,
+    pub aux_subtype: Box<[u8]>,
+}
+
+impl_box!(AuxiliaryTypePropertyBox, b"auxC");
+
+/// `mskC`, ISO/IEC 23008-12 6.10.2.2
+#[derive(Debug)]
+pub struct MaskConfigurationBox {
+    pub bits_per_pixel: u8,
+}
+
+impl_box!(MaskConfigurationBox, b"mskC");
+
+/// `clli`, with the fields of the content light level information SEI message, H.265 D.3.35.
+/// Luminances are in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLightLevelBox {
+    pub max_content_light_level: u16,
+    pub max_pic_average_light_level: u16,
+}
+
+impl_box!(ContentLightLevelBox, b"clli");
+
+/// `mdcv`, with the fields of the mastering display colour volume SEI message, H.265 D.3.28.
+/// Chromaticities are in units of 0.00002, luminances in units of 0.0001 cd/m².
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasteringDisplayColourVolumeBox {
+    /// (x, y) of the green, blue and red primaries, in that order
+    pub display_primaries: [(u16, u16); 3],
+    pub white_point: (u16, u16),
+    pub max_display_mastering_luminance: u32,
+    pub min_display_mastering_luminance: u32,
+}
+
+impl_box!(MasteringDisplayColourVolumeBox, b"mdcv");
+
+/// `cclv`, with the fields of the content colour volume SEI message, H.265 D.3.41. Each field
+/// is only present when its flag is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentColourVolumeBox {
+    /// (x, y) of the primaries, in units of 0.00002
+    pub primaries: Option<[(i32, i32); 3]>,
+    /// in units of 0.0000001 cd/m²
+    pub min_luminance_value: Option<u32>,
+    pub max_luminance_value: Option<u32>,
+    pub avg_luminance_value: Option<u32>,
+}
+
+impl_box!(ContentColourVolumeBox, b"cclv");
+
+/// `amve`, with the fields of the ambient viewing environment SEI message, H.265 D.3.39.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmbientViewingEnvironmentBox {
+    /// in units of 0.0001 lux
+    pub ambient_illuminance: u32,
+    /// chromaticity in units of 0.00002
+    pub ambient_light_x: u16,
+    pub ambient_light_y: u16,
+}
+
+impl_box!(AmbientViewingEnvironmentBox, b"amve");
+
+/// `udes`, ISO/IEC 23008-12 6.5.20: a name, description and tags in one language. An item may
+/// have one per language.
+#[derive(Debug)]
+pub struct UserDescriptionBox {
+    /// an RFC 5646 language tag, or empty when unknown
+    pub lang: Box<str>,
+    pub name: Box<str>,
+    pub description: Box<str>,
+    /// comma separated
+    pub tags: Box<str>,
+}
+
+impl_box!(UserDescriptionBox, b"udes");
+
+/// `altt`, ISO/IEC 23008-12 6.5.21: alternative text for when the image can't be seen.
+#[derive(Debug)]
+pub struct AccessibilityTextBox {
+    pub alt_text: Box<str>,
+    /// an RFC 5646 language tag, or empty when unknown
+    pub alt_lang: Box<str>,
+}
+
+impl_box!(AccessibilityTextBox, b"altt");
+
+/// `crtt`, ISO/IEC 23008-12 6.5.22
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreationTimeBox {
+    /// microseconds since 1904-01-01 00:00:00 UTC
+    pub creation_time: u64,
+}
+
+impl_box!(CreationTimeBox, b"crtt");
+
+/// `mdft`, ISO/IEC 23008-12 6.5.23
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModificationTimeBox {
+    /// microseconds since 1904-01-01 00:00:00 UTC
+    pub modification_time: u64,
+}
+
+impl_box!(ModificationTimeBox, b"mdft");
+
+// seconds from 1904-01-01, the ISOBMFF epoch, to 1970-01-01
+const ISOBMFF_EPOCH_TO_UNIX_EPOCH: u64 = 2_082_844_800;
+
+// microseconds since 1904 as a SystemTime, `None` when it can't be represented
+fn system_time(micros: u64) -> Option<SystemTime> {
+    let since_1904 = Duration::from_micros(micros);
+    let epoch_offset = Duration::from_secs(ISOBMFF_EPOCH_TO_UNIX_EPOCH);
+
+    if since_1904 >= epoch_offset {
+        UNIX_EPOCH.checked_add(since_1904 - epoch_offset)
+    } else {
+        UNIX_EPOCH.checked_sub(epoch_offset - since_1904)
+    }
+}
+
+impl CreationTimeBox {
+    pub fn system_time(&self) -> Option<SystemTime> {
+        system_time(self.creation_time)
+    }
+}
+
+impl ModificationTimeBox {
+    pub fn system_time(&self) -> Option<SystemTime> {
+        system_time(self.modification_time)
+    }
+}
+
+/// `oinf`, ISO/IEC 14496-15 9.6.2: the operating points of a layered HEVC item
+#[derive(Debug)]
+pub struct OperatingPointsInformationBox {
+    /// scalability_mask_flag[i] of the VPS extension in bit i
+    pub scalability_mask: u16,
+    pub profile_tier_levels: Box<[OperatingPointProfileTierLevel]>,
+    pub operating_points: Box<[OperatingPoint]>,
+    pub layers: Box<[OperatingPointLayerDependency]>,
+}
+
+impl_box!(OperatingPointsInformationBox, b"oinf");
+
+#[derive(Debug)]
+pub struct OperatingPointProfileTierLevel {
+    pub profile_space: u8,
+    pub tier_flag: bool,
+    pub profile_idc: u8,
+    pub profile_compatibility_flags: u32,
+    /// the low 48 bits
+    pub constraint_indicator_flags: u64,
+    pub level_idc: u8,
+}
+
+#[derive(Debug)]
+pub struct OperatingPoint {
+    pub output_layer_set_idx: u16,
+    pub max_temporal_id: u8,
+    pub layers: Box<[OperatingPointLayer]>,
+    pub min_picture_width: u16,
+    pub min_picture_height: u16,
+    pub max_picture_width: u16,
+    pub max_picture_height: u16,
+    pub max_chroma_format: u8,
+    pub max_bit_depth_minus8: u8,
+    /// (avgFrameRate, constantFrameRate)
+    pub frame_rate: Option<(u16, u8)>,
+    /// (maxBitRate, avgBitRate)
+    pub bit_rate: Option<(u32, u32)>,
+}
+
+impl OperatingPoint {
+    pub fn output_layer_ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.layers
+            .iter()
+            .filter(|layer| layer.is_output_layer)
+            .map(|layer| layer.layer_id)
+    }
+}
+
+#[derive(Debug)]
+pub struct OperatingPointLayer {
+    /// index into `profile_tier_levels`
+    pub ptl_idx: u8,
+    pub layer_id: u8,
+    pub is_output_layer: bool,
+    pub is_alternate_output_layer: bool,
+}
+
+#[derive(Debug)]
+pub struct OperatingPointLayerDependency {
+    pub layer_id: u8,
+    pub direct_ref_layer_ids: Box<[u8]>,
+    /// one per bit set in `scalability_mask`, lowest bit first
+    pub dimension_identifiers: Box<[u8]>,
+}
+
+/// `lsel`, ISO/IEC 23008-12 6.5.11: the layer of a multi-layer item to output
+#[derive(Debug)]
+pub struct LayerSelectorBox {
+    pub layer_id: u16,
+}
+
+impl_box!(LayerSelectorBox, b"lsel");
+
 #[derive(Debug)]
 pub struct ImageRotationBox {
     pub angle: u8,
@@ -264,6 +698,218 @@ pub struct PixelInformationPropertyBox {
 
 impl_box!(PixelInformationPropertyBox, b"pixi");
 
+/// `jpgC`, ISO/IEC 23008-12 Annex H: JPEG headers shared by `jpeg` items, which are prepended to
+/// their data before decoding
+#[derive(Debug)]
+pub struct JpegConfigurationBox {
+    pub prefix: Box<[u8]>,
+}
+
+impl_box!(JpegConfigurationBox, b"jpgC");
+
+/// `cmpd`, ISO/IEC 23001-17 5.2.1
+#[derive(Debug)]
+pub struct ComponentDefinitionBox {
+    pub components: Box<[ComponentDefinition]>,
+}
+
+impl_box!(ComponentDefinitionBox, b"cmpd");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDefinition {
+    pub component_type: ComponentType,
+    /// only for user defined types (0x8000 and up)
+    pub uri: Option<Box<str>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    Monochrome,
+    Y,
+    Cb,
+    Cr,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Depth,
+    Disparity,
+    Palette,
+    FilterArray,
+    Padded,
+    Cyan,
+    Magenta,
+    Yellow,
+    Key,
+    Other(u16),
+}
+
+impl ComponentType {
+    /// Cb and Cr are the only components affected by `uncC` chroma subsampling.
+    pub const fn is_chroma(&self) -> bool {
+        matches!(self, Self::Cb | Self::Cr)
+    }
+}
+
+impl From<u16> for ComponentType {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Self::Monochrome,
+            1 => Self::Y,
+            2 => Self::Cb,
+            3 => Self::Cr,
+            4 => Self::Red,
+            5 => Self::Green,
+            6 => Self::Blue,
+            7 => Self::Alpha,
+            8 => Self::Depth,
+            9 => Self::Disparity,
+            10 => Self::Palette,
+            11 => Self::FilterArray,
+            12 => Self::Padded,
+            13 => Self::Cyan,
+            14 => Self::Magenta,
+            15 => Self::Yellow,
+            16 => Self::Key,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// `uncC`, ISO/IEC 23001-17 5.2.2
+///
+/// Version 1 boxes only carry a profile; they are expanded into the equivalent version 0 fields
+/// when read.
+#[derive(Debug)]
+pub struct UncompressedFrameConfigBox {
+    pub profile: FourCC,
+    pub components: Box<[UncompressedComponent]>,
+    pub sampling_type: SamplingType,
+    pub interleave_type: InterleaveType,
+    pub block_size: u8,
+    pub components_little_endian: bool,
+    pub block_pad_lsb: bool,
+    pub block_little_endian: bool,
+    pub block_reversed: bool,
+    pub pad_unknown: bool,
+    pub pixel_size: u32,
+    pub row_align_size: u32,
+    pub tile_align_size: u32,
+    pub num_tile_cols: u32,
+    pub num_tile_rows: u32,
+}
+
+impl_box!(UncompressedFrameConfigBox, b"uncC");
+
+impl UncompressedFrameConfigBox {
+    /// The components of the profiles a version 1 box may name, which need no `cmpd`.
+    pub const fn profile_components(profile: FourCC) -> Option<&'static [ComponentType]> {
+        use ComponentType::*;
+
+        match &profile.0 {
+            b"rgb3" => Some(&[Red, Green, Blue]),
+            b"rgba" => Some(&[Red, Green, Blue, Alpha]),
+            b"abgr" => Some(&[Alpha, Blue, Green, Red]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UncompressedComponent {
+    /// index into `cmpd`
+    pub component_index: u16,
+    pub bit_depth: u16,
+    pub format: ComponentFormat,
+    /// bytes each value is padded to, 0 for none
+    pub align_size: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentFormat {
+    Unsigned,
+    Float,
+    Complex,
+    Other(u8),
+}
+
+impl From<u8> for ComponentFormat {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Unsigned,
+            1 => Self::Float,
+            2 => Self::Complex,
+            other => Self::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingType {
+    NoSubsampling,
+    YCbCr422,
+    YCbCr420,
+    YCbCr411,
+}
+
+impl SamplingType {
+    /// horizontal and vertical subsampling factors of chroma components
+    pub const fn factors(&self) -> (u32, u32) {
+        match self {
+            Self::NoSubsampling => (1, 1),
+            Self::YCbCr422 => (2, 1),
+            Self::YCbCr420 => (2, 2),
+            Self::YCbCr411 => (4, 1),
+        }
+    }
+}
+
+impl TryFrom<u8> for SamplingType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::NoSubsampling),
+            1 => Ok(Self::YCbCr422),
+            2 => Ok(Self::YCbCr420),
+            3 => Ok(Self::YCbCr411),
+            other => Err(anyhow::anyhow!("invalid sampling_type: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterleaveType {
+    /// one plane per component
+    Component,
+    /// all components of a pixel together
+    Pixel,
+    /// luma planar, chroma components interleaved
+    Mixed,
+    /// one row of each component in turn
+    Row,
+    /// like `Component`, but tiles are interleaved within each component
+    TileComponent,
+    /// 4:2:2/4:1:1 pixel interleave sharing chroma samples, e.g. YUYV
+    MultiY,
+}
+
+impl TryFrom<u8> for InterleaveType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Component),
+            1 => Ok(Self::Pixel),
+            2 => Ok(Self::Mixed),
+            3 => Ok(Self::Row),
+            4 => Ok(Self::TileComponent),
+            5 => Ok(Self::MultiY),
+            other => Err(anyhow::anyhow!("invalid interleave_type: {}", other)),
+        }
+    }
+}
+
 //
 
 pub trait IsoBmffBox<'a> {
@@ -281,9 +927,10 @@ impl_box!(FileTypeBox<'a>, b"ftyp");
 
 #[derive(Debug)]
 pub struct MetaBox<'a> {
-    // Required boxes
+    // Required boxes, though only `hdlr` outside of `pict` metas, where the others default to
+    // empty
     pub handler: HandlerBox<'a>,
-    pub primary_item: PrimaryItemBox,
+    pub primary_item: Option<PrimaryItemBox>,
     pub item_info: ItemInfoBox<'a>,
     pub item_location: ItemLocationBox,
 
@@ -293,11 +940,254 @@ pub struct MetaBox<'a> {
 
     // Optional boxes
     pub data_information: Option<DataInformationBox<'a>>,
+    pub groups_list: Option<GroupsListBox>,
+    pub item_data: Option<ItemDataBox<'a>>,
+    pub item_protection: Option<ItemProtectionBox<'a>>,
+
+    /// children this crate doesn't parse, e.g. the `ilst` of `mdir` metadata
+    pub other_boxes: Box<[RawBox<'a>]>,
 }
 
 impl_box!(MetaBox<'a>, b"meta");
 
+/// A box kept as it is, for the caller to parse.
 #[derive(Debug)]
+pub struct RawBox<'a> {
+    pub kind: BoxKind<'a>,
+    /// everything after the box header
+    pub payload: &'a [u8],
+}
+
+impl<'a> MetaBox<'a> {
+    pub fn primary_item_id(&self) -> Option<u32> {
+        self.primary_item.as_ref().map(|pitm| pitm.item_id)
+    }
+
+    /// The first child of `kind` this crate doesn't parse.
+    pub fn other_box(&self, kind: &[u8; 4]) -> Option<&RawBox<'a>> {
+        self.other_boxes.iter().find(|raw| raw.kind.0 == kind)
+    }
+
+    /// The properties associated with `item_id` in `ipma`, in association order.
+    pub fn properties_of(&self, item_id: u32) -> impl Iterator<Item = &ItemProperty> {
+        let iprp = self.item_properties.as_ref();
+
+        iprp.and_then(|iprp| iprp.association.assoc.iter().find(|(id, _)| *id == item_id))
+            .map_or(&[][..], |(_, indices)| indices)
+            .iter()
+            // 1-based, with 0 meaning no property
+            .filter_map(move |&index| {
+                iprp?
+                    .container
+                    .properties
+                    .get((index as usize).checked_sub(1)?)
+            })
+    }
+
+    pub fn image(&self, item_id: u32) -> Option<ImageHandle<'_, 'a>> {
+        ImageHandle::new(self, item_id)
+    }
+
+    /// The items `item_id` references with `kind`, in reference order, e.g. the tiles of a grid
+    /// through `dimg`.
+    pub fn references_from(&self, item_id: u32, kind: &[u8; 4]) -> &[u32] {
+        self.item_references
+            .as_ref()
+            .and_then(|iref| {
+                iref.references
+                    .iter()
+                    .find(|r| r.kind.0 == kind && r.from_item_id == item_id)
+            })
+            .map_or(&[], |r| &r.to_item_ids)
+    }
+
+    /// The items referencing `item_id` with `kind`, in `iref` order, e.g. the tiles of a tile
+    /// base through `tbas`.
+    pub fn references_to(&self, item_id: u32, kind: &[u8; 4]) -> impl Iterator<Item = u32> {
+        self.item_references
+            .as_ref()
+            .map_or(&[][..], |iref| &iref.references)
+            .iter()
+            .filter(move |r| r.kind.0 == kind && r.to_item_ids.contains(&item_id))
+            .map(|r| r.from_item_id)
+    }
+
+    /// The `rgan` items describing `item_id` through `cdsc`.
+    pub fn region_items_for(&self, item_id: u32) -> impl Iterator<Item = u32> {
+        self.references_to(item_id, b"cdsc").filter(move |&id| {
+            self.image(id)
+                .is_some_and(|item| matches!(item.item_type(), ItemType::Rgan))
+        })
+    }
+
+    pub fn primary_image(&self) -> Option<ImageHandle<'_, 'a>> {
+        self.image(self.primary_item_id()?)
+    }
+
+    /// The images meant to be shown on their own, in `iinf` order: image items that aren't
+    /// hidden, inputs of a derived image (`dimg`), thumbnails (`thmb`), auxiliary images
+    /// (`auxl`) or masks of a region item (`mask`).
+    pub fn top_level_images(&self) -> impl Iterator<Item = ImageHandle<'_, 'a>> {
+        self.item_info
+            .item_info_entries
+            .iter()
+            .filter(|ItemInfoEntry::Fixed { hidden, .. }| !hidden)
+            .filter_map(|ItemInfoEntry::Fixed { item_id, .. }| self.image(*item_id))
+            .filter(|image| {
+                let item_id = image.item_id();
+
+                image.item_type().is_image()
+                    && self.references_from(item_id, b"thmb").is_empty()
+                    && self.references_from(item_id, b"auxl").is_empty()
+                    && self.references_to(item_id, b"dimg").next().is_none()
+                    && self.references_to(item_id, b"mask").next().is_none()
+            })
+    }
+
+    /// The `sinf` describing how `item_id` was protected, `None` for unprotected items and for
+    /// protection indices with no `ipro` entry.
+    pub fn protection_scheme_of(&self, item_id: u32) -> Option<&ProtectionSchemeInfoBox<'a>> {
+        let index = self.image(item_id)?.protection_index();
+
+        self.item_protection
+            .as_ref()?
+            .protection_schemes
+            .get((index as usize).checked_sub(1)?)
+    }
+
+    pub fn entity_groups(&self) -> &[EntityToGroupBox] {
+        self.groups_list
+            .as_ref()
+            .map_or(&[], |grpl| grpl.groups.as_ref())
+    }
+
+    /// The entities of the `prgr` group containing `item_id`, in the order to show them in.
+    pub fn progressive_order(&self, item_id: u32) -> Option<&[u32]> {
+        self.entity_groups()
+            .iter()
+            .find(|group| {
+                matches!(group.kind, EntityGroupKind::Progressive)
+                    && group.entity_ids.contains(&item_id)
+            })
+            .map(|group| group.entity_ids.as_ref())
+    }
+
+    /// The other items of the `altr` group containing `item_id`, in the writer's order of
+    /// preference.
+    pub fn alternatives_for(&self, item_id: u32) -> impl Iterator<Item = u32> {
+        self.entity_groups()
+            .iter()
+            .find(|group| {
+                matches!(group.kind, EntityGroupKind::Alternative)
+                    && group.entity_ids.contains(&item_id)
+            })
+            .map_or(&[][..], |group| &group.entity_ids)
+            .iter()
+            .copied()
+            .filter(move |&id| id != item_id)
+    }
+
+    /// The (left, right) views of the `ster` group containing `item_id`.
+    pub fn stereo_pair(&self, item_id: u32) -> Option<(u32, u32)> {
+        self.entity_groups().iter().find_map(|group| {
+            match (&group.kind, group.entity_ids.as_ref()) {
+                (EntityGroupKind::Stereo, &[left, right])
+                    if left == item_id || right == item_id =>
+                {
+                    Some((left, right))
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// `idat`, the payload of items with construction_method 1
+#[derive(Debug)]
+pub struct ItemDataBox<'a> {
+    /// absolute file offset of `data`
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+impl_box!(ItemDataBox<'a>, b"idat");
+
+/// A top-level `imda`, holding item data that `dref` entries refer to by identifier instead of
+/// by file offset.
+#[derive(Debug, Clone, Copy)]
+pub struct IdentifiedMediaDataBox<'a> {
+    pub imda_identifier: u32,
+    /// absolute file offset of `data`
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+impl_box!(IdentifiedMediaDataBox<'a>, b"imda");
+
+#[derive(Debug)]
+pub struct GroupsListBox {
+    pub groups: Box<[EntityToGroupBox]>,
+}
+
+impl_box!(GroupsListBox, b"grpl");
+
+/// An entity group. Entity ids are item ids or track ids.
+#[derive(Debug)]
+pub struct EntityToGroupBox {
+    pub kind: EntityGroupKind,
+    pub group_id: u32,
+    pub entity_ids: Box<[u32]>,
+}
+
+#[derive(Debug)]
+pub enum EntityGroupKind {
+    /// `altr`: any one of the entities may be shown, preferred first
+    Alternative,
+    /// `ster`: exactly two entities, the left view then the right
+    Stereo,
+    /// `brst`: a burst of images in capture order
+    Burst,
+    /// `pymd`: the same image at several resolutions
+    Pyramid(PyramidDescriptor),
+    /// `eqiv`: entities with equivalent content, e.g. an item and a sample of a track
+    Equivalence,
+    /// `prgr`: the order to show the entities in while the file loads, coarsest first
+    Progressive,
+    Other(FourCC),
+}
+
+/// Layout of the levels of an image pyramid, from `pymd`.
+#[derive(Debug)]
+pub struct PyramidDescriptor {
+    pub tile_size_x: u16,
+    pub tile_size_y: u16,
+    /// one per entity of the group, in the same order
+    pub layers: Box<[PyramidLayer]>,
+}
+
+#[derive(Debug)]
+pub struct PyramidLayer {
+    /// downsampling factor of this layer relative to the full resolution image
+    pub layer_binning: u16,
+    pub tiles_in_layer_row_minus1: u16,
+    pub tiles_in_layer_column_minus1: u16,
+}
+
+impl EntityGroupKind {
+    pub const fn four_cc(&self) -> FourCC {
+        match self {
+            Self::Alternative => FourCC(*b"altr"),
+            Self::Stereo => FourCC(*b"ster"),
+            Self::Burst => FourCC(*b"brst"),
+            Self::Pyramid(_) => FourCC(*b"pymd"),
+            Self::Equivalence => FourCC(*b"eqiv"),
+            Self::Progressive => FourCC(*b"prgr"),
+            Self::Other(kind) => *kind,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct ItemLocationBox {
     pub offset_size: u8,
     pub length_size: u8,
@@ -308,6 +1198,12 @@ pub struct ItemLocationBox {
 
 impl_box!(ItemLocationBox, b"iloc");
 
+impl ItemLocationBox {
+    pub fn reference(&self, item_id: u32) -> Option<&ItemLocationBoxReference> {
+        self.references.iter().find(|r| r.item_id == item_id)
+    }
+}
+
 #[derive(Debug)]
 pub struct ItemLocationBoxReference {
     pub item_id: u32,
@@ -317,3 +1213,242 @@ pub struct ItemLocationBoxReference {
     /// (offset, length)
     pub extents: Box<[(u64, u64)]>,
 }
+
+impl ItemLocationBoxReference {
+    /// where the item's data starts, i.e. the start of its first extent
+    pub fn data_offset(&self) -> u64 {
+        // both come from the file, so this is only good for locating errors
+        self.base_offset.saturating_add(
+            self.extents
+                .first()
+                .map_or(0, |(extent_offset, _)| *extent_offset),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct MovieBox<'a> {
+    pub header: MovieHeaderBox,
+    pub tracks: Box<[TrackBox<'a>]>,
+    /// in `moov` itself or its `udta`
+    pub meta_boxes: Box<[MetaBox<'a>]>,
+}
+
+impl_box!(MovieBox<'a>, b"moov");
+
+#[derive(Debug)]
+pub struct MovieHeaderBox {
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub timescale: u32,
+    pub duration: u64,
+    pub next_track_id: u32,
+}
+
+impl_box!(MovieHeaderBox, b"mvhd");
+
+#[derive(Debug)]
+pub struct TrackBox<'a> {
+    pub header: TrackHeaderBox,
+    pub media: MediaBox<'a>,
+
+    /// resolved from the sample tables in `stbl`, in decoding order
+    pub samples: Box<[Sample]>,
+    /// in `trak` itself or its `udta`
+    pub meta_boxes: Box<[MetaBox<'a>]>,
+}
+
+impl_box!(TrackBox<'a>, b"trak");
+
+impl TrackBox<'_> {
+    pub const fn track_id(&self) -> u32 {
+        self.header.track_id
+    }
+
+    /// ticks per second of the sample timestamps
+    pub const fn timescale(&self) -> u32 {
+        self.media.header.timescale
+    }
+
+    pub const fn handler(&self) -> &str {
+        self.media.handler.kind
+    }
+
+    pub fn sample_entry(&self, sample: &Sample) -> Option<&SampleEntry> {
+        let index = sample.sample_description_index.checked_sub(1)?;
+
+        self.media
+            .information
+            .sample_table
+            .sample_description
+            .entries
+            .get(index as usize)
+    }
+
+    pub fn hevc_configuration_record(&self) -> Option<&HEVCDecoderConfigurationRecord> {
+        self.media
+            .information
+            .sample_table
+            .sample_description
+            .entries
+            .iter()
+            .find_map(|entry| match entry {
+                SampleEntry::Hvc1(entry) => Some(&entry.config),
+                SampleEntry::Other { .. } => None,
+            })
+    }
+}
+
+/// One access unit of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// absolute file offset of the sample data
+    pub offset: u64,
+    pub size: u32,
+    /// in the track's timescale
+    pub decode_time: u64,
+    pub duration: u32,
+    pub is_sync: bool,
+    /// 1-based index into `stsd`
+    pub sample_description_index: u32,
+}
+
+#[derive(Debug)]
+pub struct TrackHeaderBox {
+    pub version_flag: VersionFlag,
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub track_id: u32,
+    pub duration: u64,
+    /// 16.16 fixed point
+    pub width: u32,
+    /// 16.16 fixed point
+    pub height: u32,
+}
+
+impl_box!(TrackHeaderBox, b"tkhd");
+
+#[derive(Debug)]
+pub struct MediaBox<'a> {
+    pub header: MediaHeaderBox,
+    pub handler: HandlerBox<'a>,
+    pub information: MediaInformationBox<'a>,
+}
+
+impl_box!(MediaBox<'a>, b"mdia");
+
+#[derive(Debug)]
+pub struct MediaHeaderBox {
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub timescale: u32,
+    pub duration: u64,
+    /// ISO-639-2/T, packed as three 5-bit letters
+    pub language: u16,
+}
+
+impl_box!(MediaHeaderBox, b"mdhd");
+
+#[derive(Debug)]
+pub struct MediaInformationBox<'a> {
+    pub data_information: Option<DataInformationBox<'a>>,
+    pub sample_table: SampleTableBox,
+}
+
+impl_box!(MediaInformationBox<'a>, b"minf");
+
+#[derive(Debug)]
+pub struct SampleTableBox {
+    pub sample_description: SampleDescriptionBox,
+    pub time_to_sample: TimeToSampleBox,
+    pub sample_to_chunk: SampleToChunkBox,
+    /// from either `stsz` or `stz2`
+    pub sample_size: SampleSizeBox,
+    /// from either `stco` or `co64`
+    pub chunk_offset: ChunkOffsetBox,
+    /// every sample is a sync sample when absent
+    pub sync_sample: Option<SyncSampleBox>,
+}
+
+impl_box!(SampleTableBox, b"stbl");
+
+#[derive(Debug)]
+pub struct SampleDescriptionBox {
+    pub entries: Box<[SampleEntry]>,
+}
+
+impl_box!(SampleDescriptionBox, b"stsd");
+
+#[derive(Debug)]
+pub enum SampleEntry {
+    Hvc1(VisualSampleEntry),
+    Other {
+        kind: FourCC,
+        data_reference_index: u16,
+    },
+}
+
+#[derive(Debug)]
+pub struct VisualSampleEntry {
+    pub data_reference_index: u16,
+    pub width: u16,
+    pub height: u16,
+    pub config: HEVCDecoderConfigurationRecord,
+}
+
+#[derive(Debug)]
+pub struct TimeToSampleBox {
+    /// (sample_count, sample_delta)
+    pub entries: Box<[(u32, u32)]>,
+}
+
+impl_box!(TimeToSampleBox, b"stts");
+
+#[derive(Debug)]
+pub struct SampleToChunkBox {
+    pub entries: Box<[SampleToChunkEntry]>,
+}
+
+impl_box!(SampleToChunkBox, b"stsc");
+
+#[derive(Debug)]
+pub struct SampleToChunkEntry {
+    /// 1-based
+    pub first_chunk: u32,
+    pub samples_per_chunk: u32,
+    pub sample_description_index: u32,
+}
+
+#[derive(Debug)]
+pub struct SampleSizeBox {
+    /// non-zero when every sample has this size, in which case `entry_sizes` is empty
+    pub sample_size: u32,
+    pub sample_count: u32,
+    pub entry_sizes: Box<[u32]>,
+}
+
+impl_box!(SampleSizeBox, b"stsz");
+
+impl SampleSizeBox {
+    pub fn size_of(&self, sample_index: usize) -> Option<u32> {
+        match self.sample_size {
+            0 => self.entry_sizes.get(sample_index).copied(),
+            size => Some(size),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChunkOffsetBox {
+    pub offsets: Box<[u64]>,
+}
+
+impl_box!(ChunkOffsetBox, b"stco");
+
+#[derive(Debug)]
+pub struct SyncSampleBox {
+    /// 1-based, in increasing order
+    pub sample_numbers: Box<[u32]>,
+}
+
+impl_box!(SyncSampleBox, b"stss");
@@ -0,0 +1,293 @@
+use anyhow::Result;
+
+use crate::av1::AV1CodecConfigurationRecord;
+use crate::heif::{
+    AccessibilityTextBox, AmbientViewingEnvironmentBox, AuxiliaryTypePropertyBox,
+    ColorInformationBox, ContentColourVolumeBox, ContentLightLevelBox, CreationTimeBox,
+    EntityGroupKind, HeifReader, ImageSpatialExtentsPropertyBox, ItemInfoEntry, ItemProperty,
+    ItemType, MasteringDisplayColourVolumeBox, MetaBox, ModificationTimeBox, PyramidDescriptor,
+    UserDescriptionBox,
+};
+use crate::hevc::{NalUnitKind, RbspReader, SeiPayloadType, sei_messages};
+use crate::vvc::VvcDecoderConfigurationRecord;
+
+/// An image item together with the `meta` box describing it.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageHandle<'h, 'a> {
+    meta: &'h MetaBox<'a>,
+    item_id: u32,
+}
+
+impl<'h, 'a> ImageHandle<'h, 'a> {
+    /// `None` when `item_id` has no `infe` entry.
+    pub fn new(meta: &'h MetaBox<'a>, item_id: u32) -> Option<Self> {
+        meta.item_info
+            .item_info_entries
+            .iter()
+            .any(|ItemInfoEntry::Fixed { item_id: id, .. }| *id == item_id)
+            .then_some(Self { meta, item_id })
+    }
+
+    pub const fn item_id(&self) -> u32 {
+        self.item_id
+    }
+
+    pub const fn meta(&self) -> &'h MetaBox<'a> {
+        self.meta
+    }
+
+    fn info_entry(&self) -> &'h ItemInfoEntry<'a> {
+        self.meta
+            .item_info
+            .item_info_entries
+            .iter()
+            .find(|ItemInfoEntry::Fixed { item_id, .. }| *item_id == self.item_id)
+            .expect("checked in ImageHandle::new")
+    }
+
+    pub fn item_type(&self) -> &'h ItemType<'a> {
+        let ItemInfoEntry::Fixed { item_type, .. } = self.info_entry();
+        item_type
+    }
+
+    /// Set on items that are only meant to be shown as part of another, e.g. grid tiles.
+    pub fn is_hidden(&self) -> bool {
+        let ItemInfoEntry::Fixed { hidden, .. } = self.info_entry();
+        *hidden
+    }
+
+    /// The item's 1-based `ipro` index, 0 when it isn't protected.
+    pub fn protection_index(&self) -> u16 {
+        let ItemInfoEntry::Fixed {
+            item_protection_index,
+            ..
+        } = self.info_entry();
+        *item_protection_index
+    }
+
+    /// Protected items are encrypted or otherwise transformed, and can't be decoded without
+    /// undoing the scheme in `MetaBox::protection_scheme_of`.
+    pub fn is_protected(&self) -> bool {
+        self.protection_index() != 0
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = &'h ItemProperty> {
+        self.meta.properties_of(self.item_id)
+    }
+
+    pub fn spatial_extents(&self) -> Option<&'h ImageSpatialExtentsPropertyBox> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::ImageSpatialExtentsProperty(ispe) => Some(ispe),
+            _ => None,
+        })
+    }
+
+    pub fn av1_configuration(&self) -> Option<&'h AV1CodecConfigurationRecord> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::Av1CodecConfiguration(av1c) => Some(av1c),
+            _ => None,
+        })
+    }
+
+    pub fn vvc_configuration(&self) -> Option<&'h VvcDecoderConfigurationRecord> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::VvcDecoderConfiguration(vvcc) => Some(vvcc),
+            _ => None,
+        })
+    }
+
+    /// The `nclx` colour information when the item has one, else its first `colr`.
+    pub fn color_information(&self) -> Option<&'h ColorInformationBox> {
+        let mut colr = self.properties().filter_map(|prop| match prop {
+            ItemProperty::ColorInformation(colr) => Some(colr),
+            _ => None,
+        });
+        let first = colr.next()?;
+
+        std::iter::once(first)
+            .chain(colr)
+            .find(|colr| colr.nclx.is_some())
+            .or(Some(first))
+    }
+
+    /// What this auxiliary image holds, from `auxC`.
+    pub fn auxiliary_type(&self) -> Option<&'h AuxiliaryTypePropertyBox> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::AuxiliaryType(auxc) => Some(auxc),
+            _ => None,
+        })
+    }
+
+    /// The `udes` descriptions of the item, one per language.
+    pub fn user_descriptions(&self) -> impl Iterator<Item = &'h UserDescriptionBox> {
+        self.properties().filter_map(|prop| match prop {
+            ItemProperty::UserDescription(udes) => Some(udes),
+            _ => None,
+        })
+    }
+
+    /// The `altt` alternative texts of the item, one per language.
+    pub fn accessibility_texts(&self) -> impl Iterator<Item = &'h AccessibilityTextBox> {
+        self.properties().filter_map(|prop| match prop {
+            ItemProperty::AccessibilityText(altt) => Some(altt),
+            _ => None,
+        })
+    }
+
+    pub fn creation_time(&self) -> Option<&'h CreationTimeBox> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::CreationTime(crtt) => Some(crtt),
+            _ => None,
+        })
+    }
+
+    pub fn modification_time(&self) -> Option<&'h ModificationTimeBox> {
+        self.properties().find_map(|prop| match prop {
+            ItemProperty::ModificationTime(mdft) => Some(mdft),
+            _ => None,
+        })
+    }
+
+    /// `clli`, else a content light level information SEI message in `hvcC`.
+    pub fn content_light_level(&self) -> Option<ContentLightLevelBox> {
+        self.properties()
+            .find_map(|prop| match prop {
+                ItemProperty::ContentLightLevel(clli) => Some(*clli),
+                _ => None,
+            })
+            .or_else(|| {
+                self.sei_message(SeiPayloadType::ContentLightLevelInfo, |reader| {
+                    reader.read_content_light_level()
+                })
+            })
+    }
+
+    /// `mdcv`, else a mastering display colour volume SEI message in `hvcC`.
+    pub fn mastering_display_colour_volume(&self) -> Option<MasteringDisplayColourVolumeBox> {
+        self.properties()
+            .find_map(|prop| match prop {
+                ItemProperty::MasteringDisplayColourVolume(mdcv) => Some(*mdcv),
+                _ => None,
+            })
+            .or_else(|| {
+                self.sei_message(SeiPayloadType::MasteringDisplayColourVolume, |reader| {
+                    reader.read_mastering_display_colour_volume()
+                })
+            })
+    }
+
+    /// `cclv`, else a content colour volume SEI message in `hvcC`.
+    pub fn content_colour_volume(&self) -> Option<ContentColourVolumeBox> {
+        self.properties()
+            .find_map(|prop| match prop {
+                ItemProperty::ContentColourVolume(cclv) => Some(*cclv),
+                _ => None,
+            })
+            .or_else(|| {
+                self.sei_message(SeiPayloadType::ContentColourVolume, |reader| {
+                    reader.read_content_colour_volume()
+                })
+            })
+    }
+
+    /// `amve`, else an ambient viewing environment SEI message in `hvcC`.
+    pub fn ambient_viewing_environment(&self) -> Option<AmbientViewingEnvironmentBox> {
+        self.properties()
+            .find_map(|prop| match prop {
+                ItemProperty::AmbientViewingEnvironment(amve) => Some(*amve),
+                _ => None,
+            })
+            .or_else(|| {
+                self.sei_message(SeiPayloadType::AmbientViewingEnvironment, |reader| {
+                    reader.read_ambient_viewing_environment()
+                })
+            })
+    }
+
+    // the first prefix SEI message of `kind` in the item's hvcC that `read` accepts
+    fn sei_message<T>(
+        &self,
+        kind: SeiPayloadType,
+        read: impl Fn(&mut HeifReader) -> Result<T>,
+    ) -> Option<T> {
+        let hvcc = self.properties().find_map(|prop| match prop {
+            ItemProperty::HevcDecoderConfiguration(hvcc) => Some(hvcc),
+            _ => None,
+        })?;
+
+        hvcc.arrays
+            .iter()
+            .filter(|array| array.nal_unit_type() == NalUnitKind::PrefixSEI)
+            .flat_map(|array| array.nal_units.iter())
+            .find_map(|nal_unit| {
+                // past the two byte NAL unit header
+                let rbsp = RbspReader::remove_emulation_prevention(nal_unit.data.get(2..)?);
+                let messages = sei_messages(&rbsp).ok()?;
+
+                messages
+                    .iter()
+                    .filter(|message| message.payload_type == kind)
+                    .find_map(|message| read(&mut HeifReader::new(message.payload)).ok())
+            })
+    }
+
+    /// (width, height) from `ispe`, before any transformative properties
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        self.spatial_extents()
+            .map(|ispe| (ispe.image_width, ispe.image_height))
+    }
+
+    /// The cheapest level of the `pymd` pyramid containing this image that still covers
+    /// `width` x `height`, or the largest level when none does. Images outside a pyramid are
+    /// their own only level.
+    pub fn best_level_for(&self, width: u32, height: u32) -> Self {
+        let Some((entity_ids, pyramid)) =
+            self.meta
+                .entity_groups()
+                .iter()
+                .find_map(|group| match &group.kind {
+                    EntityGroupKind::Pyramid(pyramid)
+                        if group.entity_ids.contains(&self.item_id) =>
+                    {
+                        Some((&group.entity_ids, pyramid))
+                    }
+                    _ => None,
+                })
+        else {
+            return *self;
+        };
+
+        let levels = entity_ids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &item_id)| {
+                let level = Self::new(self.meta, item_id)?;
+                let (w, h) = level
+                    .dimensions()
+                    .or_else(|| layer_dimensions(pyramid, i))?;
+
+                Some((level, w as u64, h as u64))
+            })
+            .collect::<Vec<_>>();
+
+        let pixels = |&(_, w, h): &(Self, u64, u64)| w * h;
+        let covering = levels
+            .iter()
+            .filter(|(_, w, h)| *w >= width as u64 && *h >= height as u64)
+            .min_by_key(|level| pixels(level));
+
+        covering
+            .or_else(|| levels.iter().max_by_key(|level| pixels(level)))
+            .map_or(*self, |(level, _, _)| *level)
+    }
+}
+
+// for levels without an ispe, the size implied by their tiling
+fn layer_dimensions(pyramid: &PyramidDescriptor, index: usize) -> Option<(u32, u32)> {
+    let layer = pyramid.layers.get(index)?;
+
+    Some((
+        pyramid.tile_size_x as u32 * (layer.tiles_in_layer_row_minus1 as u32 + 1),
+        pyramid.tile_size_y as u32 * (layer.tiles_in_layer_column_minus1 as u32 + 1),
+    ))
+}
@@ -0,0 +1,153 @@
+use crate::error::{ErrorLocation, HeifError};
+
+/// Upper bounds on attacker-controlled counts and sizes.
+///
+/// Every count read from the file is checked against these before anything is allocated for it,
+/// so a malformed upload fails with `HeifError::LimitExceeded` instead of exhausting memory.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeLimits {
+    /// entries in `iinf`, `iloc` and `ipma`
+    pub max_items: u32,
+    /// properties in `ipco`, and associations per item in `ipma`
+    pub max_properties: u32,
+    /// extents per item in `iloc`
+    pub max_extents: u32,
+    /// width * height of any single image, from `ispe` or the SPS
+    pub max_image_pixels: u64,
+    /// tiles referenced by a `grid` item, or `hvt1` tiles making up one `hvc1` picture
+    pub max_grid_tiles: u32,
+    /// bytes allocated while parsing and decoding, across all of the above
+    pub max_total_allocation: u64,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_items: 1_000,
+            max_properties: 1_000,
+            max_extents: 32,
+            max_image_pixels: 32_768 * 32_768,
+            max_grid_tiles: 4_096,
+            max_total_allocation: 2 << 30,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// No limits at all, for trusted input.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_items: u32::MAX,
+            max_properties: u32::MAX,
+            max_extents: u32::MAX,
+            max_image_pixels: u64::MAX,
+            max_grid_tiles: u32::MAX,
+            max_total_allocation: u64::MAX,
+        }
+    }
+}
+
+pub fn ensure_within(
+    limit: &'static str,
+    value: u64,
+    max: u64,
+    at: impl FnOnce() -> ErrorLocation,
+) -> Result<(), HeifError> {
+    if value > max {
+        return Err(HeifError::LimitExceeded {
+            at: at(),
+            limit,
+            value,
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+/// Running total of bytes allocated on behalf of one file, checked against
+/// `DecodeLimits::max_total_allocation`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllocationBudget {
+    used: u64,
+}
+
+impl AllocationBudget {
+    pub fn charge(
+        &mut self,
+        bytes: u64,
+        limits: &DecodeLimits,
+        at: impl FnOnce() -> ErrorLocation,
+    ) -> Result<(), HeifError> {
+        let used = self.used.saturating_add(bytes);
+        ensure_within("total allocation", used, limits.max_total_allocation, at)?;
+        self.used = used;
+
+        Ok(())
+    }
+}
+
+/// What an `ItemDecoder` may allocate for one item.
+///
+/// `HeicDecoder` charges the item's `ispe` before decoding; decoders that size buffers from
+/// their own bitstream, such as an SPS or a JPEG frame header, check those sizes here first.
+#[derive(Debug, Clone)]
+pub struct DecodeBudget {
+    limits: DecodeLimits,
+    budget: AllocationBudget,
+    at: ErrorLocation,
+}
+
+impl Default for DecodeBudget {
+    fn default() -> Self {
+        Self::new(DecodeLimits::default())
+    }
+}
+
+impl DecodeBudget {
+    /// A fresh budget, for decoding an item on its own.
+    pub fn new(limits: DecodeLimits) -> Self {
+        Self::with_allocation(
+            limits,
+            AllocationBudget::default(),
+            ErrorLocation {
+                box_path: Box::default(),
+                offset: 0,
+            },
+        )
+    }
+
+    pub(crate) const fn with_allocation(
+        limits: DecodeLimits,
+        budget: AllocationBudget,
+        at: ErrorLocation,
+    ) -> Self {
+        Self { limits, budget, at }
+    }
+
+    pub(crate) const fn allocation(&self) -> AllocationBudget {
+        self.budget
+    }
+
+    pub const fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Counts `bytes` against `DecodeLimits::max_total_allocation`.
+    pub fn charge(&mut self, bytes: u64) -> Result<(), HeifError> {
+        self.budget.charge(bytes, &self.limits, || self.at.clone())
+    }
+
+    /// Checks a `width` x `height` picture against `DecodeLimits::max_image_pixels`, then
+    /// charges the `bytes` its buffers take.
+    pub fn charge_image(&mut self, width: u32, height: u32, bytes: u64) -> Result<(), HeifError> {
+        ensure_within(
+            "image pixels",
+            width as u64 * height as u64,
+            self.limits.max_image_pixels,
+            || self.at.clone(),
+        )?;
+
+        self.charge(bytes)
+    }
+}
@@ -1,5 +1,16 @@
 mod grammar;
+mod image;
+mod limits;
 mod reader;
+mod region;
+mod resolver;
+mod sniff;
 
 pub use grammar::*;
+pub use image::ImageHandle;
+pub(crate) use limits::ensure_within;
+pub use limits::{DecodeBudget, DecodeLimits};
 pub use reader::*;
+pub use region::{Region, RegionItem};
+pub use resolver::{DataResolver, FileDataResolver};
+pub use sniff::{FileKind, Sniff, sniff};
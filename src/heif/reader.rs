@@ -1,84 +1,532 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+
 use anyhow::{Result, anyhow, bail, ensure};
 
+use crate::error::{ErrorLocation, HeifError};
+use crate::heif::limits::{AllocationBudget, DecodeBudget, DecodeLimits, ensure_within};
+use crate::heif::resolver::DataResolver;
 use crate::heif::{
-    BoxKind, ColorInformationBox, DataEntryBaseBox, DataEntryImdaBox, DataEntrySeqNumImdaBox,
-    DataEntryUrlBox, DataEntryUrnBox, DataInformationBox, DataReferenceBox, FileTypeBox,
-    HandlerBox, Heif, ImageRotationBox, ImageSpatialExtentsPropertyBox, IsoBmffBox, ItemInfoBox,
-    ItemInfoEntry, ItemLocationBox, ItemLocationBoxReference, ItemPropertiesBox, ItemProperty,
-    ItemPropertyAssociationBox, ItemPropertyContainerBox, ItemReferenceBox, ItemType, MetaBox,
-    PixelInformationPropertyBox, PrimaryItemBox, RootBox, SingleItemReferenceBox, VersionFlag,
+    AccessibilityTextBox, AmbientViewingEnvironmentBox, AuxiliaryTypePropertyBox, BoxKind,
+    ChunkOffsetBox, ColorInformationBox, ComponentDefinition, ComponentDefinitionBox,
+    ComponentFormat, ComponentType, ContentColourVolumeBox, ContentLightLevelBox, CreationTimeBox,
+    DataEntryBaseBox, DataEntryImdaBox, DataEntrySeqNumImdaBox, DataEntryUrlBox, DataEntryUrnBox,
+    DataInformationBox, DataReferenceBox, EntityGroupKind, EntityToGroupBox, FileTypeBox, FourCC,
+    GroupsListBox, HandlerBox, Heif, IdentifiedMediaDataBox, ImageRotationBox,
+    ImageSpatialExtentsPropertyBox, InterleaveType, IsoBmffBox, ItemDataBox, ItemInfoBox,
+    ItemInfoEntry, ItemInfoExtension, ItemLocationBox, ItemLocationBoxReference, ItemPropertiesBox,
+    ItemProperty, ItemPropertyAssociationBox, ItemPropertyContainerBox, ItemProtectionBox,
+    ItemReferenceBox, ItemType, JpegConfigurationBox, LayerSelectorBox, MaskConfigurationBox,
+    MasteringDisplayColourVolumeBox, MediaBox, MediaHeaderBox, MediaInformationBox, MetaBox,
+    ModificationTimeBox, MovieBox, MovieHeaderBox, NclxColourInformation, OperatingPoint,
+    OperatingPointLayer, OperatingPointLayerDependency, OperatingPointProfileTierLevel,
+    OperatingPointsInformationBox, PixelInformationPropertyBox, PrimaryItemBox,
+    ProtectionSchemeInfoBox, PyramidDescriptor, PyramidLayer, RawBox, RegionItem, RootBox, Sample,
+    SampleDescriptionBox, SampleEntry, SampleSizeBox, SampleTableBox, SampleToChunkBox,
+    SampleToChunkEntry, SamplingType, SchemeInformationBox, SchemeTypeBox, SingleItemReferenceBox,
+    SyncSampleBox, TimeToSampleBox, TrackBox, TrackHeaderBox, UncompressedComponent,
+    UncompressedFrameConfigBox, UserDescriptionBox, VersionFlag, VisualSampleEntry,
 };
 
-use crate::hevc::{HEVCDecoderConfigurationRecord, NalArray, RawNalUnit};
+use crate::av1::{AV1CodecConfigurationRecord, ObuKind};
+use crate::hevc::{
+    HEVCDecoderConfigurationRecord, LHEVCDecoderConfigurationRecord, NalArray, RawNalUnit,
+};
 use crate::impl_read_for_datatype;
+use crate::vvc::{
+    NalUnitKind as VvcNalUnitKind, VvcDecoderConfigurationRecord, VvcNalArray, VvcPtlInfo,
+    VvcPtlRecord,
+};
+
+/// Controls how the reader reacts to files that bend the spec.
+///
+/// In lenient mode (the default) recoverable problems such as trailing bytes inside a box, a
+/// duplicate `pitm`, `ipma` entries for unknown items or a second `pict` meta are recorded in
+/// `Heif::warnings` and parsing continues. Strict mode turns them into errors, which is what
+/// validation tooling wants. Skipped unknown boxes are recorded as warnings in both modes.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    pub strict: bool,
+    /// The input may end anywhere after `meta` and `moov`, as it does while a file is still
+    /// arriving: a top-level box cut short at the end is ignored, and
+    /// `HeifReader::is_item_available` tells which items are already complete.
+    pub partial: bool,
+    pub limits: DecodeLimits,
+}
+
+// what the extents of an item are relative to
+enum ItemSource<'a> {
+    // bytes of the file, and their absolute offset in it
+    File(&'a [u8], usize),
+    // another file, as ("url" or "urn", its location)
+    External(&'static str, &'a str),
+}
 
 #[derive(Debug)]
 pub struct HeifReader<'a> {
     cursor: usize,
     data: &'a [u8],
+    options: ParseOptions,
 
-    // a debug feature that helps point out where we are in the box tree
+    // points out where we are in the box tree, reported as the box path of any error
     box_stack: Vec<BoxKind<'a>>,
+
+    // (kind, start, end) of every top-level box seen so far, to locate errors found in item data
+    top_level_boxes: Vec<(BoxKind<'a>, usize, usize)>,
+
+    // problems tolerated in lenient mode
+    warnings: Vec<HeifError>,
+
+    // the top-level `imda` boxes seen so far, for items whose `dref` entry names one
+    identified_media_data: Vec<IdentifiedMediaDataBox<'a>>,
+
+    // for items whose `dref` entry names another file
+    resolver: Option<&'a dyn DataResolver>,
+
+    // a Cell so that item data read through `&self`, e.g. from other files, is charged too
+    budget: Cell<AllocationBudget>,
 }
 
 impl<'a> HeifReader<'a> {
     pub fn new(data: &'a [u8]) -> Self {
+        Self::with_options(data, ParseOptions::default())
+    }
+
+    pub fn with_options(data: &'a [u8], options: ParseOptions) -> Self {
         Self {
             cursor: 0,
             data,
+            options,
             box_stack: vec![RootBox::KIND],
+            top_level_boxes: Vec::new(),
+            warnings: Vec::new(),
+            identified_media_data: Vec::new(),
+            resolver: None,
+            budget: Cell::default(),
+        }
+    }
+
+    /// Reads the data of items in other files, named by `url ` and `urn ` entries that aren't
+    /// self-contained, through `resolver`. Without one, such items fail with
+    /// `HeifError::UnresolvedDataReference`.
+    pub fn with_resolver(mut self, resolver: &'a dyn DataResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    pub const fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Counts `bytes` against `DecodeLimits::max_total_allocation`. Decoders call this before
+    /// allocating picture buffers, so parsing and decoding share one budget.
+    pub fn charge_allocation(&mut self, bytes: u64) -> Result<(), HeifError> {
+        self.charge_at(bytes, || self.location())
+    }
+
+    fn charge_at(&self, bytes: u64, at: impl FnOnce() -> ErrorLocation) -> Result<(), HeifError> {
+        let mut budget = self.budget.get();
+        budget.charge(bytes, &self.options.limits, at)?;
+        self.budget.set(budget);
+
+        Ok(())
+    }
+
+    /// What's left of the allocation budget, for an `ItemDecoder` decoding the item at `offset`.
+    /// Hand it back with `spend` once the item is decoded.
+    pub(crate) fn item_budget(&self, offset: usize) -> DecodeBudget {
+        DecodeBudget::with_allocation(
+            self.options.limits,
+            self.budget.get(),
+            self.location_of(offset),
+        )
+    }
+
+    pub(crate) fn spend(&self, budget: &DecodeBudget) {
+        self.budget.set(budget.allocation());
+    }
+
+    /// The current box path and cursor, as reported in errors.
+    pub fn location(&self) -> ErrorLocation {
+        ErrorLocation {
+            box_path: self.box_stack.iter().map(FourCC::from).collect(),
+            offset: self.cursor,
         }
     }
 
-    pub fn get_item_data(&self, item_id: u32, iloc: &ItemLocationBox) -> Result<&'a [u8]> {
-        let item_ref = iloc
-            .references
+    /// Locates an absolute offset within the top-level boxes read so far. Used for errors found
+    /// after parsing, e.g. in item data or parameter sets.
+    pub fn location_of(&self, offset: usize) -> ErrorLocation {
+        let mut box_path = vec![FourCC::from(&RootBox::KIND)];
+
+        if let Some((kind, _, _)) = self
+            .top_level_boxes
             .iter()
-            .find(|r| r.item_id == item_id)
-            .ok_or_else(|| anyhow!("item {} not found in iloc", item_id))?;
+            .find(|(_, start, end)| (*start..*end).contains(&offset))
+        {
+            box_path.push(FourCC::from(kind));
+        }
 
-        match item_ref.construction_method {
-            0 => {}
-            1 => todo!("idat construction method not yet supported"),
-            n => bail!("unknown construction_method {}", n),
+        ErrorLocation {
+            box_path: box_path.into_boxed_slice(),
+            offset,
         }
+    }
+
+    /// The payload of `item_id`, from the file or the `imda` its `dref` entry names for
+    /// construction_method 0, or from `idat` for construction_method 1.
+    /// Data in other files is read through the reader's `DataResolver`, see `with_resolver`.
+    pub fn get_item_data(
+        &self,
+        item_id: u32,
+        meta: &MetaBox<'a>,
+    ) -> Result<Cow<'a, [u8]>, HeifError> {
+        let item_ref =
+            meta.item_location
+                .reference(item_id)
+                .ok_or_else(|| HeifError::MissingItem {
+                    at: self.location_of(self.cursor),
+                    item_id,
+                })?;
+
+        let data_offset = item_ref.base_offset as usize;
+
+        // (bytes the extents are relative to, their absolute offset in the file)
+        let (source, origin) = match (item_ref.construction_method, &meta.item_data) {
+            (0, _) => match self.data_reference(item_ref, meta)? {
+                ItemSource::File(source, origin) => (source, origin),
+                ItemSource::External(reference, location) => {
+                    return self
+                        .read_external(item_ref, reference, location)
+                        .map(Cow::Owned);
+                }
+            },
+            (1, Some(idat)) => (idat.data, idat.offset),
+            (1, None) => {
+                return Err(HeifError::MissingBox {
+                    at: self.location_of(data_offset),
+                    kind: FourCC::from(&ItemDataBox::KIND),
+                });
+            }
+            (n, _) => {
+                return Err(HeifError::InvalidBox {
+                    at: self.location_of(data_offset),
+                    reason: format!("unsupported construction_method {n} for item {item_id}"),
+                });
+            }
+        };
+
+        // todo: multiple extents should probably be concatenated
+        let [(extent_offset, extent_length)] = *item_ref.extents else {
+            return Err(HeifError::InvalidBox {
+                at: self.location_of(origin.saturating_add(data_offset)),
+                reason: format!(
+                    "item {item_id} has {} extents, only one is supported",
+                    item_ref.extents.len()
+                ),
+            });
+        };
+
+        let truncated = |start: u64| HeifError::Truncated {
+            at: self.location_of(origin.saturating_add(start as usize)),
+            needed: extent_length as usize,
+            available: source.len().saturating_sub(start as usize),
+        };
 
-        if item_ref.extents.len() != 1 {
-            todo!("multiple extents not yet supported (probably concatenate them though)");
+        let start = item_ref
+            .base_offset
+            .checked_add(extent_offset)
+            .ok_or_else(|| truncated(u64::MAX))?;
+        let end = start
+            .checked_add(extent_length)
+            .ok_or_else(|| truncated(start))?;
+
+        source
+            .get(start as usize..end as usize)
+            .map(Cow::Borrowed)
+            .ok_or_else(|| truncated(start))
+    }
+
+    /// Whether `get_item_data` can return all of `item_id` from the input read so far, which
+    /// only matters for `ParseOptions::partial` input. Items in other files count as available
+    /// when there's a resolver to read them.
+    pub fn is_item_available(&self, item_id: u32, meta: &MetaBox<'a>) -> bool {
+        let external = meta
+            .item_location
+            .reference(item_id)
+            .filter(|item_ref| item_ref.construction_method == 0)
+            .and_then(|item_ref| self.data_reference(item_ref, meta).ok())
+            .is_some_and(|source| matches!(source, ItemSource::External(..)));
+
+        if external {
+            self.resolver.is_some()
+        } else {
+            self.get_item_data(item_id, meta).is_ok()
         }
+    }
 
-        let (extent_offset, extent_length) = item_ref.extents[0];
-        let start = (item_ref.base_offset + extent_offset) as usize;
-        let end = start + extent_length as usize;
+    /// The items of `meta` whose data is all there, see `is_item_available`.
+    pub fn available_items<'m>(&'m self, meta: &'m MetaBox<'a>) -> impl Iterator<Item = u32> + 'm
+    where
+        'a: 'm,
+    {
+        meta.item_info
+            .item_info_entries
+            .iter()
+            .map(|ItemInfoEntry::Fixed { item_id, .. }| *item_id)
+            .filter(|&item_id| self.is_item_available(item_id, meta))
+    }
 
-        self.data
-            .get(start..end)
-            .ok_or_else(|| anyhow!("item {} data out of bounds", item_id))
+    // where the extents of a construction_method 0 item are
+    fn data_reference(
+        &self,
+        item_ref: &ItemLocationBoxReference,
+        meta: &MetaBox<'a>,
+    ) -> Result<ItemSource<'a>, HeifError> {
+        let item_id = item_ref.item_id;
+        let unresolved = |reference: String| HeifError::UnresolvedDataReference {
+            at: self.location_of(item_ref.base_offset as usize),
+            item_id,
+            reference,
+        };
+
+        // 0 is this file
+        let Some(index) = (item_ref.data_reference_index as usize).checked_sub(1) else {
+            return Ok(ItemSource::File(self.data, 0));
+        };
+
+        let entries = meta
+            .data_information
+            .as_ref()
+            .map_or(&[][..], |dinf| &dinf.0.entries);
+        let Some(entry) = entries.get(index) else {
+            return Err(unresolved(format!(
+                "dref entry {} of {}",
+                index + 1,
+                entries.len()
+            )));
+        };
+
+        match entry {
+            DataEntryBaseBox::Url(url) if url.self_contained => Ok(ItemSource::File(self.data, 0)),
+            DataEntryBaseBox::Urn(urn) if urn.self_contained => Ok(ItemSource::File(self.data, 0)),
+            DataEntryBaseBox::Url(url) => Ok(ItemSource::External("url", url.location)),
+            DataEntryBaseBox::Urn(urn) if urn.location.is_empty() => {
+                Ok(ItemSource::External("urn", urn.name))
+            }
+            DataEntryBaseBox::Urn(urn) => Ok(ItemSource::External("urn", urn.location)),
+            DataEntryBaseBox::Imda(imdt) => self
+                .identified_media_data
+                .iter()
+                .find(|imda| imda.imda_identifier == imdt.imda_ref_identifier)
+                .map(|imda| ItemSource::File(imda.data, imda.offset))
+                .ok_or_else(|| {
+                    unresolved(format!(
+                        "imda {}, which isn't in the file",
+                        imdt.imda_ref_identifier
+                    ))
+                }),
+            // only meaningful for movie fragments
+            DataEntryBaseBox::SeqNumImda(_) => Err(unresolved("an snim entry".to_string())),
+        }
+    }
+
+    // the data of an item in another file, through the resolver
+    fn read_external(
+        &self,
+        item_ref: &ItemLocationBoxReference,
+        reference: &str,
+        location: &str,
+    ) -> Result<Vec<u8>, HeifError> {
+        let at = || self.location_of(item_ref.base_offset as usize);
+        let unresolved = |why: String| HeifError::UnresolvedDataReference {
+            at: at(),
+            item_id: item_ref.item_id,
+            reference: format!("{reference} {location:?}{why}"),
+        };
+
+        let Some(resolver) = self.resolver else {
+            return Err(unresolved(String::new()));
+        };
+
+        let [(extent_offset, extent_length)] = *item_ref.extents else {
+            return Err(HeifError::InvalidBox {
+                at: at(),
+                reason: format!(
+                    "item {} has {} extents, only one is supported",
+                    item_ref.item_id,
+                    item_ref.extents.len()
+                ),
+            });
+        };
+
+        // iloc's length 0 for the whole file, which the resolver can't be asked for
+        if extent_length == 0 {
+            return Err(unresolved(
+                ", as a whole file without an extent length".to_string(),
+            ));
+        }
+        self.charge_at(extent_length, at)?;
+        let offset = item_ref
+            .base_offset
+            .checked_add(extent_offset)
+            .ok_or_else(|| unresolved(", at an offset past 2^64".to_string()))?;
+
+        resolver
+            .read(location, offset, extent_length)
+            .map_err(|err| unresolved(format!(": {err}")))
+    }
+
+    /// The regions of the `rgan` item `item_id`, with its referenced masks resolved through its
+    /// `mask` references.
+    pub fn read_region_item(
+        &self,
+        item_id: u32,
+        meta: &MetaBox<'a>,
+    ) -> Result<RegionItem, HeifError> {
+        let data = self.get_item_data(item_id, meta)?;
+
+        RegionItem::parse(&data, meta.references_from(item_id, b"mask")).map_err(|err| {
+            let offset = meta
+                .item_location
+                .reference(item_id)
+                .map_or(0, |r| r.data_offset() as usize);
+
+            HeifError::InvalidBox {
+                at: self.location_of(offset),
+                reason: format!("region item {item_id}: {err:#}"),
+            }
+        })
+    }
+
+    /// The bytes of one sample of a track, as listed in `TrackBox::samples`.
+    pub fn get_sample_data(&self, sample: &Sample) -> Result<&'a [u8], HeifError> {
+        let start = sample.offset as usize;
+
+        sample
+            .offset
+            .checked_add(sample.size as u64)
+            .and_then(|end| self.data.get(start..end as usize))
+            .ok_or_else(|| HeifError::Truncated {
+                at: self.location_of(start),
+                needed: sample.size as usize,
+                available: self.data.len().saturating_sub(start),
+            })
     }
 
-    pub fn read(&mut self) -> Result<Heif<'a>> {
+    pub fn read(&mut self) -> Result<Heif<'a>, HeifError> {
+        self.read_heif()
+            .map_err(|err| HeifError::from_anyhow(err, || self.location()))
+    }
+
+    fn read_heif(&mut self) -> Result<Heif<'a>> {
         let file_type_box = self.read_file_type_box()?;
+        self.top_level_boxes
+            .push((FileTypeBox::KIND, 0, self.cursor));
 
         let mut meta_box = None;
+        let mut other_meta_boxes = Vec::new();
+        let mut movie_box = None;
 
         loop {
             if self.cursor == self.data.len() {
                 break;
             }
 
-            match self.peek_box_kind()? {
+            let start = self.cursor;
+
+            if self.options.partial && self.box_is_cut_off() {
+                match self.peek_box_kind() {
+                    // still required whole
+                    Ok(b"meta" | b"moov") => {}
+                    kind => {
+                        if let Ok(kind) = kind {
+                            self.top_level_boxes
+                                .push((BoxKind(kind), start, self.data.len()));
+                        }
+                        break;
+                    }
+                }
+            }
+
+            let kind = self.peek_box_kind()?;
+
+            match kind {
                 b"meta" => {
-                    meta_box = Some(self.read_meta_box()?);
+                    let meta = self.read_meta_box()?;
+
+                    if meta.handler.kind != "pict" {
+                        other_meta_boxes.push(meta);
+                    } else if meta_box.is_some() {
+                        self.warn_or_bail(
+                            self.invalid_box("duplicate pict meta box, keeping the first"),
+                        )?;
+                        other_meta_boxes.push(meta);
+                    } else {
+                        meta_box = Some(meta);
+                    }
+                }
+                b"moov" => {
+                    let moov = self.read_movie_box()?;
+
+                    if movie_box.is_some() {
+                        self.warn_or_bail(
+                            self.invalid_box("duplicate moov box, keeping the first"),
+                        )?;
+                    } else {
+                        movie_box = Some(moov);
+                    }
+                }
+                b"imda" => {
+                    let imda = self.read_identified_media_data_box()?;
+                    let duplicate = self
+                        .identified_media_data
+                        .iter()
+                        .any(|other| other.imda_identifier == imda.imda_identifier);
+
+                    if duplicate {
+                        self.warn_or_bail(
+                            self.invalid_box("duplicate imda identifier, keeping the first"),
+                        )?;
+                    } else {
+                        self.identified_media_data.push(imda);
+                    }
                 }
-                foreign => self.skip_box(foreign)?,
+                _ => self.skip_box()?,
             }
+
+            self.top_level_boxes
+                .push((BoxKind(kind), start, self.cursor));
         }
 
-        let meta_box = meta_box.ok_or_else(|| anyhow!("missing required meta box"))?;
+        // a file may carry only image sequences, but it has to carry something
+        if meta_box.is_none() && movie_box.is_none() {
+            bail!(HeifError::MissingBox {
+                at: self.location(),
+                kind: FourCC::from(&MetaBox::KIND),
+            });
+        }
 
         Ok(Heif {
             file_type_box,
             meta_box,
+            other_meta_boxes: other_meta_boxes.into_boxed_slice(),
+            movie_box,
+            warnings: std::mem::take(&mut self.warnings).into_boxed_slice(),
+        })
+    }
+
+    fn read_identified_media_data_box(&mut self) -> Result<IdentifiedMediaDataBox<'a>> {
+        self.with_box(&IdentifiedMediaDataBox::KIND, |this, start, box_size| {
+            let imda_identifier = this.read_u32()?;
+            let offset = this.cursor;
+            let remainder = this.remaining_bytes_in_box(start, box_size);
+
+            Ok(IdentifiedMediaDataBox {
+                imda_identifier,
+                offset,
+                data: this.read_slice(remainder)?,
+            })
         })
     }
 
@@ -99,7 +547,7 @@ impl<'a> HeifReader<'a> {
 
     fn read_meta_box(&mut self) -> Result<MetaBox<'a>> {
         self.with_full_box(&MetaBox::KIND, |this, start, box_size, version_flag| {
-            ensure!(version_flag.version() == 0);
+            this.ensure_version(&version_flag, 0)?;
 
             let handler = this.read_handler_box()?;
 
@@ -111,9 +559,13 @@ impl<'a> HeifReader<'a> {
             let mut item_properties = None;
             let mut item_references = None;
             let mut data_information = None;
+            let mut groups_list = None;
+            let mut item_data = None;
+            let mut item_protection = None;
+            let mut other_boxes = Vec::new();
 
             loop {
-                if this.cursor == start + box_size {
+                if this.cursor >= start + box_size {
                     break;
                 }
 
@@ -122,7 +574,15 @@ impl<'a> HeifReader<'a> {
                         data_information = Some(this.read_data_information_box()?);
                     }
                     b"pitm" => {
-                        primary_item = Some(this.read_primary_item_box()?);
+                        let pitm = this.read_primary_item_box()?;
+
+                        if primary_item.is_some() {
+                            this.warn_or_bail(
+                                this.invalid_box("duplicate pitm box, keeping the first"),
+                            )?;
+                        } else {
+                            primary_item = Some(pitm);
+                        }
                     }
                     b"iinf" => {
                         item_info = Some(this.read_item_info_box()?);
@@ -136,37 +596,172 @@ impl<'a> HeifReader<'a> {
                     b"iloc" => {
                         item_location = Some(this.read_item_location_box()?);
                     }
-                    foreign => {
-                        this.skip_box(foreign)?;
-                        continue;
+                    b"grpl" => {
+                        groups_list = Some(this.read_groups_list_box()?);
+                    }
+                    b"idat" => {
+                        item_data = Some(this.read_item_data_box()?);
+                    }
+                    b"ipro" => {
+                        item_protection = Some(this.read_item_protection_box()?);
+                    }
+                    _ => other_boxes.push(this.read_raw_box()?),
+                }
+            }
+
+            // other handlers, such as `mdir`, needn't describe any items
+            let (primary_item, item_info, item_location) = if handler.kind == "pict" {
+                (
+                    Some(this.required(primary_item, &PrimaryItemBox::KIND)?),
+                    this.required(item_info, &ItemInfoBox::KIND)?,
+                    this.required(item_location, &ItemLocationBox::KIND)?,
+                )
+            } else {
+                (
+                    primary_item,
+                    item_info.unwrap_or_default(),
+                    item_location.unwrap_or_default(),
+                )
+            };
+
+            if let Some(iprp) = &item_properties {
+                for (item_id, _) in &iprp.association.assoc {
+                    let known = item_info
+                        .item_info_entries
+                        .iter()
+                        .any(|ItemInfoEntry::Fixed { item_id: id, .. }| id == item_id);
+
+                    if !known {
+                        this.warn_or_bail(HeifError::MissingItem {
+                            at: this.location(),
+                            item_id: *item_id,
+                        })?;
                     }
                 }
             }
 
             Ok(MetaBox {
                 handler,
-                primary_item: primary_item.ok_or_else(|| anyhow!("missing required pitm box"))?,
-                item_info: item_info.ok_or_else(|| anyhow!("missing required iinf box"))?,
-                item_location: item_location.ok_or_else(|| anyhow!("missing required iloc box"))?,
+                primary_item,
+                item_info,
+                item_location,
                 item_properties,
                 item_references,
                 data_information,
+                groups_list,
+                item_data,
+                item_protection,
+                other_boxes: other_boxes.into_boxed_slice(),
+            })
+        })
+    }
+
+    fn read_item_protection_box(&mut self) -> Result<ItemProtectionBox<'a>> {
+        self.with_full_box(
+            &ItemProtectionBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let protection_count = this.read_u16()?;
+
+                Ok(ItemProtectionBox {
+                    protection_schemes: this.read_slice_fn(
+                        protection_count as usize,
+                        Self::read_protection_scheme_info_box,
+                    )?,
+                })
+            },
+        )
+    }
+
+    fn read_protection_scheme_info_box(&mut self) -> Result<ProtectionSchemeInfoBox<'a>> {
+        self.with_box(&ProtectionSchemeInfoBox::KIND, |this, start, box_size| {
+            let original_format = this.with_box(&BoxKind(b"frma"), |this, _start, _box_size| {
+                Ok(FourCC::from(&this.read_box_kind()?))
+            })?;
+
+            let mut scheme_type = None;
+            let mut scheme_information = None;
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"schm" => {
+                        scheme_type = Some(this.read_scheme_type_box()?);
+                    }
+                    b"schi" => {
+                        scheme_information = Some(this.with_box(
+                            &SchemeInformationBox::KIND,
+                            |this, start, box_size| {
+                                let remainder = this.remaining_bytes_in_box(start, box_size);
+                                Ok(SchemeInformationBox(this.read_slice(remainder)?))
+                            },
+                        )?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            Ok(ProtectionSchemeInfoBox {
+                original_format,
+                scheme_type,
+                scheme_information,
             })
         })
     }
 
+    fn read_scheme_type_box(&mut self) -> Result<SchemeTypeBox<'a>> {
+        self.with_full_box(
+            &SchemeTypeBox::KIND,
+            |this, start, box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let scheme_type = FourCC::from(&this.read_box_kind()?);
+                let scheme_version = this.read_u32()?;
+                let scheme_uri = if (version_flag.flags() & 1) == 1 {
+                    Some(this.read_utf8_string(start, box_size)?)
+                } else {
+                    None
+                };
+
+                Ok(SchemeTypeBox {
+                    scheme_type,
+                    scheme_version,
+                    scheme_uri,
+                })
+            },
+        )
+    }
+
     fn read_handler_box(&mut self) -> Result<HandlerBox<'a>> {
         self.with_full_box(&HandlerBox::KIND, |this, start, box_size, version_flag| {
-            ensure!(version_flag.version() == 0);
-            ensure!(version_flag.flags() == 0);
-            ensure!(this.read_u32()? == 0, "predefined must be 0");
-            ensure!(this.read_box_kind()? == BoxKind(b"pict"));
-            ensure!(this.read_slice_fn(3, Self::read_u32)?.as_ref() == &[0, 0, 0]);
+            this.ensure_version(&version_flag, 0)?;
+            ensure!(
+                version_flag.flags() == 0,
+                this.invalid_box("hdlr flags must be 0")
+            );
+            ensure!(
+                this.read_u32()? == 0,
+                this.invalid_box("predefined must be 0")
+            );
+
+            let handler_type = this.read_box_kind()?;
+
+            // iTunes writes its vendor code here in `mdir` handlers
+            if *this.read_slice_fn(3, Self::read_u32)? != [0, 0, 0] {
+                this.warn_or_bail(this.invalid_box("reserved must be 0"))?;
+            }
 
             let remainder = this.remaining_bytes_in_box(start, box_size);
             let name = str::from_utf8(this.read_slice(remainder)?)?;
 
-            Ok(HandlerBox { kind: "pict", name })
+            Ok(HandlerBox {
+                kind: str::from_utf8(handler_type.0)?,
+                name,
+            })
         })
     }
 
@@ -180,7 +775,7 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &DataReferenceBox::KIND,
             |this, _start, _box_size, version_flag| {
-                ensure!(version_flag.version() == 0);
+                this.ensure_version(&version_flag, 0)?;
 
                 let entry_count = this.read_u32()?;
 
@@ -200,7 +795,10 @@ impl<'a> HeifReader<'a> {
             DataEntrySeqNumImdaBox::KIND => {
                 DataEntryBaseBox::SeqNumImda(self.read_data_entry_seq_num_imda_box()?)
             }
-            foreign => todo!("encountered foreign box {:?}", foreign),
+            foreign => bail!(HeifError::UnsupportedBox {
+                at: self.location(),
+                kind: FourCC(*foreign.0),
+            }),
         };
 
         Ok(b)
@@ -210,12 +808,11 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &DataEntryUrlBox::KIND,
             |this, start, box_size, version_flag| {
-                ensure!(version_flag.version() == 0);
-
-                let remainder = this.remaining_bytes_in_box(start, box_size);
+                this.ensure_version(&version_flag, 0)?;
 
                 Ok(DataEntryUrlBox {
-                    location: str::from_utf8(this.read_slice(remainder)?)?,
+                    self_contained: (version_flag.flags() & 1) == 1,
+                    location: this.read_utf8_string(start, box_size)?,
                 })
             },
         )
@@ -225,7 +822,7 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &DataEntryUrnBox::KIND,
             |this, start, box_size, version_flag| {
-                ensure!(version_flag.version() == 0);
+                this.ensure_version(&version_flag, 0)?;
 
                 let remainder = this.remaining_bytes_in_box(start, box_size);
                 let bytes = this.read_slice(remainder)?;
@@ -233,11 +830,14 @@ impl<'a> HeifReader<'a> {
                 let name_end = bytes
                     .iter()
                     .position(|&b| b == 0x00)
-                    .ok_or_else(|| anyhow::anyhow!("Missing null terminator after name"))?;
+                    .ok_or_else(|| this.invalid_box("missing null terminator after name"))?;
+
+                let location = bytes[name_end + 1..].split(|&b| b == 0x00).next();
 
                 Ok(DataEntryUrnBox {
+                    self_contained: (version_flag.flags() & 1) == 1,
                     name: str::from_utf8(&bytes[..name_end])?,
-                    location: str::from_utf8(&bytes[name_end..])?,
+                    location: str::from_utf8(location.unwrap_or_default())?,
                 })
             },
         )
@@ -247,7 +847,7 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &DataEntryImdaBox::KIND,
             |this, _start, _box_size, version_flag| {
-                ensure!(version_flag.version() == 0);
+                this.ensure_version(&version_flag, 0)?;
 
                 Ok(DataEntryImdaBox {
                     version_flag,
@@ -260,8 +860,8 @@ impl<'a> HeifReader<'a> {
     fn read_data_entry_seq_num_imda_box(&mut self) -> Result<DataEntrySeqNumImdaBox> {
         self.with_full_box(
             &DataEntrySeqNumImdaBox::KIND,
-            |_this, _start, _box_size, version_flag| {
-                ensure!(version_flag.version() == 0);
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
 
                 Ok(DataEntrySeqNumImdaBox(version_flag))
             },
@@ -283,9 +883,13 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &ItemInfoBox::KIND,
             |this, _start, _box_size, version_flag| {
-                ensure!(version_flag.flags() == 0);
+                ensure!(
+                    version_flag.flags() == 0,
+                    this.invalid_box("iinf flags must be 0")
+                );
 
                 let len = this.read_versioned_u32(version_flag.version(), 1)?;
+                this.ensure_limit("items", len as u64, this.limits().max_items as u64)?;
 
                 Ok(ItemInfoBox {
                     item_info_entries: this
@@ -299,15 +903,48 @@ impl<'a> HeifReader<'a> {
         self.with_full_box(
             &ItemInfoEntry::KIND,
             |this, start, box_size, version_flag| {
+                let hidden = (version_flag.flags() & 1) == 1;
+
                 let item_info_entry = match version_flag.version() {
-                    0 | 1 => todo!("how does version 1, 2 parse"),
+                    // no item_type: these describe files, by MIME type
+                    v @ (0 | 1) => {
+                        let item_id = this.read_u16()? as u32;
+                        let item_protection_index = this.read_u16()?;
+                        let item_name = this.read_utf8_string(start, box_size)?;
+                        let content_type = this.read_utf8_string(start, box_size)?;
+                        // optional
+                        let content_encoding = this.read_utf8_string(start, box_size)?;
+
+                        let extension =
+                            if v == 1 && this.remaining_bytes_in_box(start, box_size) >= 4 {
+                                Some(this.read_item_info_extension(start, box_size)?)
+                            } else {
+                                None
+                            };
+
+                        ItemInfoEntry::Fixed {
+                            item_id,
+                            item_name,
+                            item_protection_index,
+                            item_type: ItemType::Mime {
+                                content_type,
+                                content_encoding,
+                            },
+                            hidden,
+                            extension,
+                        }
+                    }
                     v => {
                         let item_id = if v == 2 {
                             this.read_u16()? as u32
                         } else if v == 3 {
                             this.read_u32()?
                         } else {
-                            bail!("unrecognized version {v}");
+                            bail!(HeifError::UnsupportedVersion {
+                                at: this.location(),
+                                kind: FourCC::from(&ItemInfoEntry::KIND),
+                                version: v,
+                            });
                         };
 
                         let item_protection_index = this.read_u16()?;
@@ -319,22 +956,27 @@ impl<'a> HeifReader<'a> {
                         let item_name_end = remainder
                             .iter()
                             .position(|&b| b == 0x00)
-                            .ok_or_else(|| anyhow!("expected string"))?;
+                            .ok_or_else(|| this.invalid_box("expected null-terminated string"))?;
 
                         let item_name = str::from_utf8(&remainder[..item_name_end])?;
 
-                        remainder = &remainder[item_name_end..];
+                        remainder = &remainder[item_name_end + 1..];
 
                         let item_type = match item_type {
                             b"mime" => {
-                                let content_type_end = remainder
-                                    .iter()
-                                    .position(|&b| b == 0x00)
-                                    .ok_or_else(|| anyhow!("expected string"))?;
+                                let content_type_end =
+                                    remainder.iter().position(|&b| b == 0x00).ok_or_else(|| {
+                                        this.invalid_box("expected null-terminated string")
+                                    })?;
 
                                 let content_type = str::from_utf8(&remainder[..content_type_end])?;
-                                let content_encoding =
-                                    str::from_utf8(&remainder[content_type_end..])?;
+                                // optional, and not always null-terminated when it's the last field
+                                let content_encoding = str::from_utf8(
+                                    remainder[content_type_end + 1..]
+                                        .split(|&b| b == 0x00)
+                                        .next()
+                                        .unwrap_or_default(),
+                                )?;
 
                                 ItemType::Mime {
                                     content_type,
@@ -343,10 +985,9 @@ impl<'a> HeifReader<'a> {
                             }
                             b"uri " => {
                                 let item_uri_type_end =
-                                    remainder
-                                        .iter()
-                                        .position(|&b| b == 0x00)
-                                        .ok_or_else(|| anyhow!("expected string"))?;
+                                    remainder.iter().position(|&b| b == 0x00).ok_or_else(|| {
+                                        this.invalid_box("expected null-terminated string")
+                                    })?;
 
                                 let item_uri_type =
                                     str::from_utf8(&remainder[..item_uri_type_end])?;
@@ -354,9 +995,18 @@ impl<'a> HeifReader<'a> {
                                 ItemType::Uri { item_uri_type }
                             }
                             b"hvc1" => ItemType::Hvc1,
+                            b"hvt1" => ItemType::Hvt1,
+                            b"lhv1" => ItemType::Lhv1,
+                            b"vvc1" => ItemType::Vvc1,
+                            b"av01" => ItemType::Av01,
+                            b"unci" => ItemType::Unci,
+                            b"jpeg" => ItemType::Jpeg,
                             b"grid" => ItemType::Grid,
+                            b"tmap" => ItemType::Tmap,
+                            b"rgan" => ItemType::Rgan,
+                            b"mski" => ItemType::Mski,
                             b"Exif" => ItemType::Exif,
-                            _ => bail!("unrecognized item type {:?}", str::from_utf8(item_type)),
+                            other => ItemType::Other(FourCC(other.try_into()?)),
                         };
 
                         ItemInfoEntry::Fixed {
@@ -364,6 +1014,8 @@ impl<'a> HeifReader<'a> {
                             item_name,
                             item_protection_index,
                             item_type,
+                            hidden,
+                            extension: None,
                         }
                     }
                 };
@@ -373,6 +1025,42 @@ impl<'a> HeifReader<'a> {
         )
     }
 
+    fn read_item_info_extension(
+        &mut self,
+        start: usize,
+        box_size: usize,
+    ) -> Result<ItemInfoExtension<'a>> {
+        let extension_type = self.read_box_kind()?;
+
+        let extension = match extension_type.0 {
+            b"fdel" => {
+                let content_location = self.read_utf8_string(start, box_size)?;
+                let content_md5 = self.read_utf8_string(start, box_size)?;
+                let content_length = self.read_u64()?;
+                let transfer_length = self.read_u64()?;
+                let entry_count = self.read_u8()?;
+
+                ItemInfoExtension::FileDelivery {
+                    content_location,
+                    content_md5,
+                    content_length,
+                    transfer_length,
+                    group_ids: self.read_slice_fn(entry_count as usize, Self::read_u32)?,
+                }
+            }
+            _ => {
+                let remainder = self.remaining_bytes_in_box(start, box_size);
+
+                ItemInfoExtension::Other {
+                    extension_type: FourCC::from(&extension_type),
+                    data: self.read_slice(remainder)?,
+                }
+            }
+        };
+
+        Ok(extension)
+    }
+
     fn read_item_reference_box(&mut self) -> Result<ItemReferenceBox<'a>> {
         self.with_full_box(
             &ItemReferenceBox::KIND,
@@ -380,7 +1068,7 @@ impl<'a> HeifReader<'a> {
                 let mut references = Vec::new();
 
                 loop {
-                    if this.cursor == start + box_size {
+                    if this.cursor >= start + box_size {
                         break;
                     }
 
@@ -421,6 +1109,95 @@ impl<'a> HeifReader<'a> {
         })
     }
 
+    fn read_item_data_box(&mut self) -> Result<ItemDataBox<'a>> {
+        self.with_box(&ItemDataBox::KIND, |this, start, box_size| {
+            let offset = this.cursor;
+            let remainder = this.remaining_bytes_in_box(start, box_size);
+
+            Ok(ItemDataBox {
+                offset,
+                data: this.read_slice(remainder)?,
+            })
+        })
+    }
+
+    fn read_groups_list_box(&mut self) -> Result<GroupsListBox> {
+        self.with_box(&GroupsListBox::KIND, |this, start, box_size| {
+            let mut groups = Vec::new();
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                groups.extend(this.read_entity_to_group_box()?);
+            }
+
+            Ok(GroupsListBox {
+                groups: groups.into_boxed_slice(),
+            })
+        })
+    }
+
+    // None for a group that's dropped in lenient mode
+    fn read_entity_to_group_box(&mut self) -> Result<Option<EntityToGroupBox>> {
+        self.with_box_unchecked(|this, kind, start, box_size| {
+            let version_flag = this.read_version_flag()?;
+            this.ensure_version(&version_flag, 0)?;
+
+            let group_id = this.read_u32()?;
+            let num_entities_in_group = this.read_u32()?;
+            let entity_ids = this.read_slice_fn(num_entities_in_group as usize, Self::read_u32)?;
+            let mut valid = true;
+
+            let kind = match kind.0 {
+                b"altr" => EntityGroupKind::Alternative,
+                b"ster" => {
+                    if entity_ids.len() != 2 {
+                        let err = this.invalid_box(&format!(
+                            "stereo pair group {group_id} has {} entities",
+                            entity_ids.len()
+                        ));
+                        this.warn_or_bail(err)?;
+                        valid = false;
+                    }
+
+                    EntityGroupKind::Stereo
+                }
+                b"brst" => EntityGroupKind::Burst,
+                b"pymd" => {
+                    let tile_size_x = this.read_u16()?;
+                    let tile_size_y = this.read_u16()?;
+
+                    EntityGroupKind::Pyramid(PyramidDescriptor {
+                        tile_size_x,
+                        tile_size_y,
+                        layers: this.read_slice_fn(entity_ids.len(), |this| {
+                            Ok(PyramidLayer {
+                                layer_binning: this.read_u16()?,
+                                tiles_in_layer_row_minus1: this.read_u16()?,
+                                tiles_in_layer_column_minus1: this.read_u16()?,
+                            })
+                        })?,
+                    })
+                }
+                b"eqiv" => EntityGroupKind::Equivalence,
+                b"prgr" => EntityGroupKind::Progressive,
+                _ => EntityGroupKind::Other(FourCC::from(&kind)),
+            };
+
+            // todo: fields of grouping types we don't know about
+            let remainder = this.remaining_bytes_in_box(start, box_size);
+            this.read_slice(remainder)?;
+
+            Ok(valid.then_some(EntityToGroupBox {
+                kind,
+                group_id,
+                entity_ids,
+            }))
+        })
+    }
+
     fn read_item_properties_box(&mut self) -> Result<ItemPropertiesBox> {
         self.with_box(&ItemPropertiesBox::KIND, |this, _start, _box_size| {
             Ok(ItemPropertiesBox {
@@ -435,7 +1212,7 @@ impl<'a> HeifReader<'a> {
             let mut properties = Vec::new();
 
             loop {
-                if this.cursor == start + box_size {
+                if this.cursor >= start + box_size {
                     break;
                 }
 
@@ -446,6 +1223,23 @@ impl<'a> HeifReader<'a> {
                     BoxKind(b"hvcC") => ItemProperty::HevcDecoderConfiguration(
                         this.read_hevc_decoder_configuration_box()?,
                     ),
+                    BoxKind(b"lhvC") => ItemProperty::LHevcDecoderConfiguration(
+                        this.read_lhevc_decoder_configuration_box()?,
+                    ),
+                    OperatingPointsInformationBox::KIND => {
+                        ItemProperty::OperatingPointsInformation(
+                            this.read_operating_points_information_box()?,
+                        )
+                    }
+                    LayerSelectorBox::KIND => {
+                        ItemProperty::LayerSelector(this.read_layer_selector_box()?)
+                    }
+                    BoxKind(b"vvcC") => ItemProperty::VvcDecoderConfiguration(
+                        this.read_vvc_decoder_configuration_box()?,
+                    ),
+                    BoxKind(b"av1C") => ItemProperty::Av1CodecConfiguration(
+                        this.read_av1_codec_configuration_box()?,
+                    ),
                     ImageSpatialExtentsPropertyBox::KIND => {
                         ItemProperty::ImageSpatialExtentsProperty(
                             this.read_image_spatial_extents_property_box()?,
@@ -457,13 +1251,67 @@ impl<'a> HeifReader<'a> {
                     PixelInformationPropertyBox::KIND => ItemProperty::PixelInformationProperty(
                         this.read_pixel_information_property_box()?,
                     ),
+                    ComponentDefinitionBox::KIND => {
+                        ItemProperty::ComponentDefinition(this.read_component_definition_box()?)
+                    }
+                    UncompressedFrameConfigBox::KIND => ItemProperty::UncompressedFrameConfig(
+                        this.read_uncompressed_frame_config_box()?,
+                    ),
+                    JpegConfigurationBox::KIND => {
+                        ItemProperty::JpegConfiguration(this.read_jpeg_configuration_box()?)
+                    }
+                    AuxiliaryTypePropertyBox::KIND => {
+                        ItemProperty::AuxiliaryType(this.read_auxiliary_type_property_box()?)
+                    }
+                    ContentLightLevelBox::KIND => ItemProperty::ContentLightLevel(
+                        this.with_box(&ContentLightLevelBox::KIND, |this, _, _| {
+                            this.read_content_light_level()
+                        })?,
+                    ),
+                    MasteringDisplayColourVolumeBox::KIND => {
+                        ItemProperty::MasteringDisplayColourVolume(
+                            this.with_box(&MasteringDisplayColourVolumeBox::KIND, |this, _, _| {
+                                this.read_mastering_display_colour_volume()
+                            })?,
+                        )
+                    }
+                    ContentColourVolumeBox::KIND => ItemProperty::ContentColourVolume(
+                        this.with_box(&ContentColourVolumeBox::KIND, |this, _, _| {
+                            this.read_content_colour_volume()
+                        })?,
+                    ),
+                    MaskConfigurationBox::KIND => {
+                        ItemProperty::MaskConfiguration(this.read_mask_configuration_box()?)
+                    }
+                    UserDescriptionBox::KIND => {
+                        ItemProperty::UserDescription(this.read_user_description_box()?)
+                    }
+                    AccessibilityTextBox::KIND => {
+                        ItemProperty::AccessibilityText(this.read_accessibility_text_box()?)
+                    }
+                    CreationTimeBox::KIND => {
+                        ItemProperty::CreationTime(this.read_creation_time_box()?)
+                    }
+                    ModificationTimeBox::KIND => {
+                        ItemProperty::ModificationTime(this.read_modification_time_box()?)
+                    }
+                    AmbientViewingEnvironmentBox::KIND => ItemProperty::AmbientViewingEnvironment(
+                        this.with_box(&AmbientViewingEnvironmentBox::KIND, |this, _, _| {
+                            this.read_ambient_viewing_environment()
+                        })?,
+                    ),
                     foreign => {
-                        this.skip_box(foreign.0)?;
-                        continue;
+                        this.skip_box()?;
+                        ItemProperty::Other(FourCC::from(&foreign))
                     }
                 };
 
                 properties.push(property);
+                this.ensure_limit(
+                    "properties",
+                    properties.len() as u64,
+                    this.limits().max_properties as u64,
+                )?;
             }
 
             Ok(ItemPropertyContainerBox {
@@ -477,6 +1325,7 @@ impl<'a> HeifReader<'a> {
             &ItemPropertyAssociationBox::KIND,
             |this, _start, _box_size, version_flag| {
                 let entry_count = this.read_u32()?;
+                this.ensure_limit("items", entry_count as u64, this.limits().max_items as u64)?;
 
                 let mut out = Vec::with_capacity(entry_count as usize);
 
@@ -484,6 +1333,11 @@ impl<'a> HeifReader<'a> {
                     let item_id = this.read_versioned_u32(version_flag.version(), 1)?;
 
                     let assoc_ct = this.read_u8()?;
+                    this.ensure_limit(
+                        "properties",
+                        assoc_ct as u64,
+                        this.limits().max_properties as u64,
+                    )?;
                     let mut assocs = Vec::with_capacity(assoc_ct as usize);
 
                     for _ in 0..assoc_ct {
@@ -514,63 +1368,397 @@ impl<'a> HeifReader<'a> {
 
     fn read_color_information_box(&mut self) -> Result<ColorInformationBox> {
         self.with_box(&ColorInformationBox::KIND, |this, start, box_size| {
-            match this.read_slice(4)? {
-                b"prof" => {
+            let colour_type = this.read_slice(4)?;
+
+            let nclx = match colour_type {
+                b"nclx" => Some(NclxColourInformation {
+                    colour_primaries: this.read_u16()?,
+                    transfer_characteristics: this.read_u16()?,
+                    matrix_coefficients: this.read_u16()?,
+                    full_range_flag: (this.read_u8()? & 0x80) != 0,
+                }),
+                _ => {
+                    // todo: parse ICC profiles, IsoBMFF 12.1.5.2
                     let remainder = this.remaining_bytes_in_box(start, box_size);
-                    let _icc_profile_data = this.read_slice(remainder)?;
+                    this.read_slice(remainder)?;
 
-                    // todo: proper faff!
-                    // let _profile = ICCProfileReader::new(this.read_slice(remainder)?).read()?;
+                    None
                 }
-                foreign => todo!(
-                    "encountered todo profile type: {:?}. IsoBMFF 12.1.5.2",
-                    str::from_utf8(foreign)
-                ),
             };
 
-            Ok(ColorInformationBox {})
+            Ok(ColorInformationBox {
+                colour_type: FourCC(colour_type.try_into()?),
+                nclx,
+            })
         })
     }
 
-    fn read_image_spatial_extents_property_box(
-        &mut self,
-    ) -> Result<ImageSpatialExtentsPropertyBox> {
+    fn read_auxiliary_type_property_box(&mut self) -> Result<AuxiliaryTypePropertyBox> {
         self.with_full_box(
-            &ImageSpatialExtentsPropertyBox::KIND,
-            |this, _start, _box_size, _version_flag| {
-                Ok(ImageSpatialExtentsPropertyBox {
-                    image_width: this.read_u32()?,
-                    image_height: this.read_u32()?,
+            &AuxiliaryTypePropertyBox::KIND,
+            |this, start, box_size, _version_flag| {
+                let aux_type = this.read_utf8_string(start, box_size)?.into();
+                let remainder = this.remaining_bytes_in_box(start, box_size);
+
+                Ok(AuxiliaryTypePropertyBox {
+                    aux_type,
+                    aux_subtype: this.read_slice(remainder)?.into(),
                 })
             },
         )
     }
 
-    fn read_image_rotation_box(&mut self) -> Result<ImageRotationBox> {
-        self.with_box(&ImageRotationBox::KIND, |this, _start, _box_size| {
-            Ok(ImageRotationBox {
-                angle: this.read_u8()? & 0b11,
-            })
+    // the payloads of clli, mdcv, cclv and amve are laid out like the SEI messages they carry,
+    // so these also read those messages for `ImageHandle`
+
+    pub(super) fn read_content_light_level(&mut self) -> Result<ContentLightLevelBox> {
+        Ok(ContentLightLevelBox {
+            max_content_light_level: self.read_u16()?,
+            max_pic_average_light_level: self.read_u16()?,
         })
     }
 
-    fn read_pixel_information_property_box(&mut self) -> Result<PixelInformationPropertyBox> {
-        self.with_full_box(
-            &PixelInformationPropertyBox::KIND,
-            |this, _start, _box_size, _version_flag| {
-                let num_channels = this.read_u8()?;
+    pub(super) fn read_mastering_display_colour_volume(
+        &mut self,
+    ) -> Result<MasteringDisplayColourVolumeBox> {
+        let mut display_primaries = [(0, 0); 3];
+        for primary in &mut display_primaries {
+            *primary = (self.read_u16()?, self.read_u16()?);
+        }
 
-                Ok(PixelInformationPropertyBox {
-                    bits_per_channel: this.read_slice_fn(num_channels as usize, Self::read_u8)?,
-                })
-            },
-        )
+        Ok(MasteringDisplayColourVolumeBox {
+            display_primaries,
+            white_point: (self.read_u16()?, self.read_u16()?),
+            max_display_mastering_luminance: self.read_u32()?,
+            min_display_mastering_luminance: self.read_u32()?,
+        })
+    }
+
+    pub(super) fn read_content_colour_volume(&mut self) -> Result<ContentColourVolumeBox> {
+        let flags = self.read_u8()?;
+        // ccv_cancel_flag in the SEI message, reserved in the box
+        ensure!(
+            (flags & 0x80) == 0,
+            self.invalid_box("content colour volume is cancelled")
+        );
+
+        let primaries = if (flags & 0x20) != 0 {
+            let mut primaries = [(0, 0); 3];
+            for primary in &mut primaries {
+                *primary = (self.read_u32()? as i32, self.read_u32()? as i32);
+            }
+            Some(primaries)
+        } else {
+            None
+        };
+        let mut luminance = |mask: u8| -> Result<Option<u32>> {
+            if (flags & mask) != 0 {
+                Ok(Some(self.read_u32()?))
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(ContentColourVolumeBox {
+            primaries,
+            min_luminance_value: luminance(0x10)?,
+            max_luminance_value: luminance(0x08)?,
+            avg_luminance_value: luminance(0x04)?,
+        })
+    }
+
+    pub(super) fn read_ambient_viewing_environment(
+        &mut self,
+    ) -> Result<AmbientViewingEnvironmentBox> {
+        Ok(AmbientViewingEnvironmentBox {
+            ambient_illuminance: self.read_u32()?,
+            ambient_light_x: self.read_u16()?,
+            ambient_light_y: self.read_u16()?,
+        })
+    }
+
+    fn read_mask_configuration_box(&mut self) -> Result<MaskConfigurationBox> {
+        self.with_full_box(
+            &MaskConfigurationBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                Ok(MaskConfigurationBox {
+                    bits_per_pixel: this.read_u8()?,
+                })
+            },
+        )
+    }
+
+    fn read_user_description_box(&mut self) -> Result<UserDescriptionBox> {
+        self.with_full_box(
+            &UserDescriptionBox::KIND,
+            |this, start, box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                Ok(UserDescriptionBox {
+                    lang: this.read_utf8_string(start, box_size)?.into(),
+                    name: this.read_utf8_string(start, box_size)?.into(),
+                    description: this.read_utf8_string(start, box_size)?.into(),
+                    tags: this.read_utf8_string(start, box_size)?.into(),
+                })
+            },
+        )
+    }
+
+    fn read_accessibility_text_box(&mut self) -> Result<AccessibilityTextBox> {
+        self.with_full_box(
+            &AccessibilityTextBox::KIND,
+            |this, start, box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                Ok(AccessibilityTextBox {
+                    alt_text: this.read_utf8_string(start, box_size)?.into(),
+                    alt_lang: this.read_utf8_string(start, box_size)?.into(),
+                })
+            },
+        )
+    }
+
+    fn read_creation_time_box(&mut self) -> Result<CreationTimeBox> {
+        self.with_full_box(
+            &CreationTimeBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                Ok(CreationTimeBox {
+                    creation_time: this.read_u64()?,
+                })
+            },
+        )
+    }
+
+    fn read_modification_time_box(&mut self) -> Result<ModificationTimeBox> {
+        self.with_full_box(
+            &ModificationTimeBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                Ok(ModificationTimeBox {
+                    modification_time: this.read_u64()?,
+                })
+            },
+        )
+    }
+
+    fn read_image_spatial_extents_property_box(
+        &mut self,
+    ) -> Result<ImageSpatialExtentsPropertyBox> {
+        self.with_full_box(
+            &ImageSpatialExtentsPropertyBox::KIND,
+            |this, _start, _box_size, _version_flag| {
+                let image_width = this.read_u32()?;
+                let image_height = this.read_u32()?;
+
+                this.ensure_limit(
+                    "image pixels",
+                    image_width as u64 * image_height as u64,
+                    this.limits().max_image_pixels,
+                )?;
+
+                Ok(ImageSpatialExtentsPropertyBox {
+                    image_width,
+                    image_height,
+                })
+            },
+        )
+    }
+
+    fn read_image_rotation_box(&mut self) -> Result<ImageRotationBox> {
+        self.with_box(&ImageRotationBox::KIND, |this, _start, _box_size| {
+            Ok(ImageRotationBox {
+                angle: this.read_u8()? & 0b11,
+            })
+        })
+    }
+
+    fn read_pixel_information_property_box(&mut self) -> Result<PixelInformationPropertyBox> {
+        self.with_full_box(
+            &PixelInformationPropertyBox::KIND,
+            |this, _start, _box_size, _version_flag| {
+                let num_channels = this.read_u8()?;
+
+                Ok(PixelInformationPropertyBox {
+                    bits_per_channel: this.read_slice_fn(num_channels as usize, Self::read_u8)?,
+                })
+            },
+        )
+    }
+
+    fn read_jpeg_configuration_box(&mut self) -> Result<JpegConfigurationBox> {
+        self.with_box(&JpegConfigurationBox::KIND, |this, start, box_size| {
+            let remainder = this.remaining_bytes_in_box(start, box_size);
+
+            Ok(JpegConfigurationBox {
+                prefix: this.read_slice(remainder)?.into(),
+            })
+        })
+    }
+
+    fn read_component_definition_box(&mut self) -> Result<ComponentDefinitionBox> {
+        self.with_box(&ComponentDefinitionBox::KIND, |this, start, box_size| {
+            let component_count = this.read_u32()?;
+            // at least 2 bytes each
+            ensure!(
+                component_count as usize <= this.remaining_bytes_in_box(start, box_size) / 2,
+                this.invalid_box("component_count exceeds the box")
+            );
+
+            let mut components = Vec::with_capacity(component_count as usize);
+
+            for _ in 0..component_count {
+                let component_type = this.read_u16()?;
+
+                let uri = if component_type >= 0x8000 {
+                    let remainder = this.remaining_bytes_in_box(start, box_size);
+                    let bytes = this
+                        .data
+                        .get(this.cursor..this.cursor + remainder)
+                        .ok_or_else(|| this.truncated(remainder))?;
+                    let end = bytes
+                        .iter()
+                        .position(|&b| b == 0x00)
+                        .ok_or_else(|| this.invalid_box("expected null-terminated string"))?;
+
+                    let uri = str::from_utf8(this.read_slice(end)?)?;
+                    this.cursor += 1;
+
+                    Some(uri.into())
+                } else {
+                    None
+                };
+
+                components.push(ComponentDefinition {
+                    component_type: ComponentType::from(component_type),
+                    uri,
+                });
+            }
+
+            Ok(ComponentDefinitionBox {
+                components: components.into_boxed_slice(),
+            })
+        })
+    }
+
+    fn read_uncompressed_frame_config_box(&mut self) -> Result<UncompressedFrameConfigBox> {
+        self.with_full_box(
+            &UncompressedFrameConfigBox::KIND,
+            |this, start, box_size, version_flag| {
+                this.ensure_version(&version_flag, 1)?;
+
+                let invalid = |this: &Self, reason: String| HeifError::InvalidProperty {
+                    at: this.location(),
+                    property: FourCC::from(&UncompressedFrameConfigBox::KIND),
+                    reason,
+                };
+
+                let profile = FourCC(this.read_slice(4)?.try_into()?);
+
+                if version_flag.version() == 1 {
+                    // everything else is implied by the profile: 8 bits per component, pixel
+                    // interleaved
+                    let components = UncompressedFrameConfigBox::profile_components(profile)
+                        .ok_or_else(|| invalid(this, format!("unknown profile {profile}")))?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, _)| UncompressedComponent {
+                            component_index: i as u16,
+                            bit_depth: 8,
+                            format: ComponentFormat::Unsigned,
+                            align_size: 0,
+                        })
+                        .collect();
+
+                    return Ok(UncompressedFrameConfigBox {
+                        profile,
+                        components,
+                        sampling_type: SamplingType::NoSubsampling,
+                        interleave_type: InterleaveType::Pixel,
+                        block_size: 0,
+                        components_little_endian: false,
+                        block_pad_lsb: false,
+                        block_little_endian: false,
+                        block_reversed: false,
+                        pad_unknown: false,
+                        pixel_size: 0,
+                        row_align_size: 0,
+                        tile_align_size: 0,
+                        num_tile_cols: 1,
+                        num_tile_rows: 1,
+                    });
+                }
+
+                let component_count = this.read_u32()?;
+                // 5 bytes each
+                ensure!(
+                    component_count as usize <= this.remaining_bytes_in_box(start, box_size) / 5,
+                    invalid(this, "component_count exceeds the box".to_string())
+                );
+
+                let components = this.read_slice_fn(component_count as usize, |this| {
+                    Ok(UncompressedComponent {
+                        component_index: this.read_u16()?,
+                        bit_depth: this.read_u8()? as u16 + 1,
+                        format: ComponentFormat::from(this.read_u8()?),
+                        align_size: this.read_u8()?,
+                    })
+                })?;
+
+                let sampling_type = SamplingType::try_from(this.read_u8()?)
+                    .map_err(|err| invalid(this, format!("{err:#}")))?;
+                let interleave_type = InterleaveType::try_from(this.read_u8()?)
+                    .map_err(|err| invalid(this, format!("{err:#}")))?;
+                let block_size = this.read_u8()?;
+                let flags = this.read_u8()?;
+
+                let pixel_size = this.read_u32()?;
+                let row_align_size = this.read_u32()?;
+                let tile_align_size = this.read_u32()?;
+                let num_tile_cols = this.read_u32()?.checked_add(1);
+                let num_tile_rows = this.read_u32()?.checked_add(1);
+
+                let (Some(num_tile_cols), Some(num_tile_rows)) = (num_tile_cols, num_tile_rows)
+                else {
+                    bail!(invalid(this, "too many tiles".to_string()));
+                };
+
+                Ok(UncompressedFrameConfigBox {
+                    profile,
+                    components,
+                    sampling_type,
+                    interleave_type,
+                    block_size,
+                    components_little_endian: flags & 0x80 != 0,
+                    block_pad_lsb: flags & 0x40 != 0,
+                    block_little_endian: flags & 0x20 != 0,
+                    block_reversed: flags & 0x10 != 0,
+                    pad_unknown: flags & 0x08 != 0,
+                    pixel_size,
+                    row_align_size,
+                    tile_align_size,
+                    num_tile_cols,
+                    num_tile_rows,
+                })
+            },
+        )
     }
 
     fn read_hevc_decoder_configuration_box(&mut self) -> Result<HEVCDecoderConfigurationRecord> {
         self.with_box(&BoxKind(b"hvcC"), |this, _start, _box_size| {
             let configuration_version = this.read_u8()?;
-            ensure!(configuration_version == 1, "unsupported hvcC version");
+            ensure!(
+                configuration_version == 1,
+                HeifError::InvalidProperty {
+                    at: this.location(),
+                    property: FourCC(*b"hvcC"),
+                    reason: format!("unsupported configuration version {configuration_version}"),
+                }
+            );
 
             let general_profile_byte = this.read_u8()?;
             let general_profile_compatibility_flags = this.read_u32()?;
@@ -587,29 +1775,7 @@ impl<'a> HeifReader<'a> {
             let bit_depth_chroma_byte = this.read_u8()?;
             let avg_frame_rate = this.read_u16()?;
             let frame_rate_byte = this.read_u8()?;
-            let num_arrays = this.read_u8()?;
-            let mut arrays = Vec::with_capacity(num_arrays as usize);
-
-            for _ in 0..num_arrays {
-                let type_byte = this.read_u8()?;
-
-                let num_nal_units = this.read_u16()?;
-                let mut nal_units = Vec::with_capacity(num_nal_units as usize);
-
-                for _ in 0..num_nal_units {
-                    let nal_unit_length = this.read_u16()? as usize;
-                    let nal_data = this.read_slice(nal_unit_length)?;
-
-                    nal_units.push(RawNalUnit {
-                        data: nal_data.to_vec().into_boxed_slice(),
-                    });
-                }
-
-                arrays.push(NalArray {
-                    type_byte,
-                    nal_units: nal_units.into_boxed_slice(),
-                });
-            }
+            let arrays = this.read_hevc_nal_arrays()?;
 
             Ok(HEVCDecoderConfigurationRecord {
                 configuration_version,
@@ -624,7 +1790,375 @@ impl<'a> HeifReader<'a> {
                 bit_depth_chroma_byte,
                 avg_frame_rate,
                 frame_rate_byte,
-                arrays: arrays.into_boxed_slice(),
+                arrays,
+            })
+        })
+    }
+
+    fn read_lhevc_decoder_configuration_box(&mut self) -> Result<LHEVCDecoderConfigurationRecord> {
+        self.with_box(&BoxKind(b"lhvC"), |this, _start, _box_size| {
+            let configuration_version = this.read_u8()?;
+            ensure!(
+                configuration_version == 1,
+                HeifError::InvalidProperty {
+                    at: this.location(),
+                    property: FourCC(*b"lhvC"),
+                    reason: format!("unsupported configuration version {configuration_version}"),
+                }
+            );
+
+            Ok(LHEVCDecoderConfigurationRecord {
+                configuration_version,
+                min_spatial_segmentation: this.read_u16()?,
+                parallelism_byte: this.read_u8()?,
+                temporal_byte: this.read_u8()?,
+                arrays: this.read_hevc_nal_arrays()?,
+            })
+        })
+    }
+
+    // the parameter set arrays ending hvcC and lhvC
+    fn read_hevc_nal_arrays(&mut self) -> Result<Box<[NalArray]>> {
+        let num_arrays = self.read_u8()?;
+        let mut arrays = Vec::with_capacity(num_arrays as usize);
+
+        for _ in 0..num_arrays {
+            let type_byte = self.read_u8()?;
+
+            let num_nal_units = self.read_u16()?;
+            let mut nal_units = Vec::with_capacity(num_nal_units as usize);
+
+            for _ in 0..num_nal_units {
+                let nal_unit_length = self.read_u16()? as usize;
+                self.charge_allocation(nal_unit_length as u64)?;
+                let offset = self.cursor;
+                let nal_data = self.read_slice(nal_unit_length)?;
+
+                nal_units.push(RawNalUnit {
+                    offset,
+                    data: nal_data.to_vec().into_boxed_slice(),
+                });
+            }
+
+            arrays.push(NalArray {
+                type_byte,
+                nal_units: nal_units.into_boxed_slice(),
+            });
+        }
+
+        Ok(arrays.into_boxed_slice())
+    }
+
+    fn read_operating_points_information_box(&mut self) -> Result<OperatingPointsInformationBox> {
+        self.with_full_box(
+            &OperatingPointsInformationBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let scalability_mask = this.read_u16()?;
+                let num_profile_tier_level = this.read_u8()? & 0x3F;
+
+                let profile_tier_levels =
+                    this.read_slice_fn(num_profile_tier_level as usize, |this| {
+                        let b0 = this.read_u8()?;
+                        let profile_compatibility_flags = this.read_u32()?;
+                        let upper = this.read_u32()? as u64;
+                        let lower = this.read_u16()? as u64;
+
+                        Ok(OperatingPointProfileTierLevel {
+                            profile_space: b0 >> 6,
+                            tier_flag: b0 & 0x20 != 0,
+                            profile_idc: b0 & 0x1F,
+                            profile_compatibility_flags,
+                            constraint_indicator_flags: (upper << 16) | lower,
+                            level_idc: this.read_u8()?,
+                        })
+                    })?;
+
+                let num_operating_points = this.read_u16()?;
+                let operating_points =
+                    this.read_slice_fn(num_operating_points as usize, |this| {
+                        let output_layer_set_idx = this.read_u16()?;
+                        let max_temporal_id = this.read_u8()?;
+                        let layer_count = this.read_u8()?;
+
+                        let layers = this.read_slice_fn(layer_count as usize, |this| {
+                            let ptl_idx = this.read_u8()?;
+                            let b = this.read_u8()?;
+
+                            Ok(OperatingPointLayer {
+                                ptl_idx,
+                                layer_id: b >> 2,
+                                is_output_layer: b & 0x02 != 0,
+                                is_alternate_output_layer: b & 0x01 != 0,
+                            })
+                        })?;
+
+                        let min_picture_width = this.read_u16()?;
+                        let min_picture_height = this.read_u16()?;
+                        let max_picture_width = this.read_u16()?;
+                        let max_picture_height = this.read_u16()?;
+
+                        let b = this.read_u8()?;
+                        let frame_rate = if b & 0x02 != 0 {
+                            Some((this.read_u16()?, this.read_u8()? & 0x03))
+                        } else {
+                            None
+                        };
+                        let bit_rate = if b & 0x01 != 0 {
+                            Some((this.read_u32()?, this.read_u32()?))
+                        } else {
+                            None
+                        };
+
+                        Ok(OperatingPoint {
+                            output_layer_set_idx,
+                            max_temporal_id,
+                            layers,
+                            min_picture_width,
+                            min_picture_height,
+                            max_picture_width,
+                            max_picture_height,
+                            max_chroma_format: b >> 6,
+                            max_bit_depth_minus8: (b >> 3) & 0x07,
+                            frame_rate,
+                            bit_rate,
+                        })
+                    })?;
+
+                let max_layer_count = this.read_u8()?;
+                let layers = this.read_slice_fn(max_layer_count as usize, |this| {
+                    let layer_id = this.read_u8()?;
+                    let num_direct_ref_layers = this.read_u8()?;
+
+                    Ok(OperatingPointLayerDependency {
+                        layer_id,
+                        direct_ref_layer_ids: this
+                            .read_slice_fn(num_direct_ref_layers as usize, Self::read_u8)?,
+                        dimension_identifiers: this
+                            .read_slice_fn(scalability_mask.count_ones() as usize, Self::read_u8)?,
+                    })
+                })?;
+
+                Ok(OperatingPointsInformationBox {
+                    scalability_mask,
+                    profile_tier_levels,
+                    operating_points,
+                    layers,
+                })
+            },
+        )
+    }
+
+    fn read_layer_selector_box(&mut self) -> Result<LayerSelectorBox> {
+        self.with_box(&LayerSelectorBox::KIND, |this, _start, _box_size| {
+            Ok(LayerSelectorBox {
+                layer_id: this.read_u16()?,
+            })
+        })
+    }
+
+    fn read_vvc_decoder_configuration_box(&mut self) -> Result<VvcDecoderConfigurationRecord> {
+        self.with_full_box(
+            &BoxKind(b"vvcC"),
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let invalid = |this: &Self, reason: String| HeifError::InvalidProperty {
+                    at: this.location(),
+                    property: FourCC(*b"vvcC"),
+                    reason,
+                };
+
+                let b0 = this.read_u8()?;
+                let length_size_minus_one = (b0 >> 1) & 0x03;
+                let ptl_present_flag = b0 & 0x01 != 0;
+
+                let ptl = if ptl_present_flag {
+                    let b1 = this.read_u16()?;
+                    let b2 = this.read_u8()?;
+                    let num_sublayers = ((b1 >> 4) & 0x07) as u8;
+                    ensure!(
+                        num_sublayers >= 1,
+                        invalid(this, "num_sublayers is 0".to_string())
+                    );
+
+                    Some(VvcPtlInfo {
+                        ols_idx: b1 >> 7,
+                        num_sublayers,
+                        constant_frame_rate: ((b1 >> 2) & 0x03) as u8,
+                        chroma_format_idc: (b1 & 0x03) as u8,
+                        bit_depth_minus8: b2 >> 5,
+                        native_ptl: this.read_vvc_ptl_record(num_sublayers)?,
+                        max_picture_width: this.read_u16()?,
+                        max_picture_height: this.read_u16()?,
+                        avg_frame_rate: this.read_u16()?,
+                    })
+                } else {
+                    None
+                };
+
+                let num_of_arrays = this.read_u8()?;
+                let mut arrays = Vec::with_capacity(num_of_arrays as usize);
+
+                for _ in 0..num_of_arrays {
+                    let type_byte = this.read_u8()?;
+                    let nal_unit_type = VvcNalUnitKind::from(type_byte & 0x1F);
+
+                    // DCI and OPI arrays hold exactly one NAL unit and don't count them
+                    let num_nalus = match nal_unit_type {
+                        VvcNalUnitKind::Dci | VvcNalUnitKind::Opi => 1,
+                        _ => this.read_u16()?,
+                    };
+                    let mut nal_units = Vec::with_capacity(num_nalus as usize);
+
+                    for _ in 0..num_nalus {
+                        let nal_unit_length = this.read_u16()? as usize;
+                        this.charge_allocation(nal_unit_length as u64)?;
+                        let offset = this.cursor;
+                        let nal_data = this.read_slice(nal_unit_length)?;
+
+                        nal_units.push(RawNalUnit {
+                            offset,
+                            data: nal_data.to_vec().into_boxed_slice(),
+                        });
+                    }
+
+                    arrays.push(VvcNalArray {
+                        array_completeness: type_byte & 0x80 != 0,
+                        nal_unit_type,
+                        nal_units: nal_units.into_boxed_slice(),
+                    });
+                }
+
+                let sps_nal_unit = arrays
+                    .iter()
+                    .find(|a| a.nal_unit_type == VvcNalUnitKind::Sps)
+                    .and_then(|a| a.nal_units.first());
+                // the NAL units are kept as they are when the SPS doesn't parse
+                let sequence_parameter_set = match sps_nal_unit
+                    .map(|nal_unit| {
+                        crate::vvc::read_nal_unit(&nal_unit.data).and_then(|(_header, rbsp)| {
+                            crate::vvc::sequence_parameter_set_rbsp(&rbsp)
+                        })
+                    })
+                    .transpose()
+                {
+                    Ok(sps) => sps,
+                    Err(err) => {
+                        let err = invalid(this, format!("SPS: {err:#}"));
+                        this.warn_or_bail(err)?;
+                        None
+                    }
+                };
+
+                Ok(VvcDecoderConfigurationRecord {
+                    length_size_minus_one,
+                    ptl,
+                    arrays: arrays.into_boxed_slice(),
+                    sequence_parameter_set,
+                })
+            },
+        )
+    }
+
+    // VvcPTLRecord, ISO/IEC 14496-15 11.2.4.2.2
+    fn read_vvc_ptl_record(&mut self, num_sublayers: u8) -> Result<VvcPtlRecord> {
+        let num_bytes_constraint_info = (self.read_u8()? & 0x3F) as usize;
+        ensure!(
+            num_bytes_constraint_info >= 1,
+            HeifError::InvalidProperty {
+                at: self.location(),
+                property: FourCC(*b"vvcC"),
+                reason: "num_bytes_constraint_info is 0".to_string(),
+            }
+        );
+
+        let profile_tier = self.read_u8()?;
+        let general_level_idc = self.read_u8()?;
+        let general_constraint_info: Box<[u8]> = self.read_slice(num_bytes_constraint_info)?.into();
+
+        // a byte of presence flags for sublayers num_sublayers - 2 down to 0, then the levels
+        // of the present ones in the same order
+        let mut sublayer_level_idc = vec![None; num_sublayers as usize - 1];
+        if num_sublayers > 1 {
+            let present = self.read_u8()?;
+            let last = sublayer_level_idc.len() - 1;
+            for i in (0..=last).rev() {
+                if present & (0x80 >> (last - i)) != 0 {
+                    sublayer_level_idc[i] = Some(self.read_u8()?);
+                }
+            }
+        }
+
+        let ptl_num_sub_profiles = self.read_u8()?;
+
+        Ok(VvcPtlRecord {
+            general_profile_idc: profile_tier >> 1,
+            general_tier_flag: profile_tier & 0x01 != 0,
+            general_level_idc,
+            ptl_frame_only_constraint_flag: general_constraint_info[0] & 0x80 != 0,
+            ptl_multi_layer_enabled_flag: general_constraint_info[0] & 0x40 != 0,
+            general_constraint_info,
+            sublayer_level_idc: sublayer_level_idc.into_boxed_slice(),
+            general_sub_profile_idc: self
+                .read_slice_fn(ptl_num_sub_profiles as usize, Self::read_u32)?,
+        })
+    }
+
+    fn read_av1_codec_configuration_box(&mut self) -> Result<AV1CodecConfigurationRecord> {
+        self.with_box(&BoxKind(b"av1C"), |this, start, box_size| {
+            let invalid = |this: &Self, reason: String| HeifError::InvalidProperty {
+                at: this.location(),
+                property: FourCC(*b"av1C"),
+                reason,
+            };
+
+            let marker_version = this.read_u8()?;
+            ensure!(
+                marker_version == 0x81,
+                invalid(
+                    this,
+                    format!("unsupported marker and version {marker_version:#x}")
+                )
+            );
+
+            let b1 = this.read_u8()?;
+            let b2 = this.read_u8()?;
+            let b3 = this.read_u8()?;
+
+            let remainder = this.remaining_bytes_in_box(start, box_size);
+            let config_obus = this.read_slice(remainder)?;
+
+            // configOBUs may hold a sequence header and metadata OBUs; only the former is of use.
+            // The rest of the record describes the stream well enough without it.
+            let sequence_header = match crate::av1::read_obus(config_obus).and_then(|obus| {
+                obus.iter()
+                    .find(|(header, _)| header.kind == ObuKind::SequenceHeader)
+                    .map(|(_, payload)| crate::av1::sequence_header_obu(payload))
+                    .transpose()
+            }) {
+                Ok(sequence_header) => sequence_header,
+                Err(err) => {
+                    let err = invalid(this, format!("{err:#}"));
+                    this.warn_or_bail(err)?;
+                    None
+                }
+            };
+
+            Ok(AV1CodecConfigurationRecord {
+                version: marker_version & 0x7F,
+                seq_profile: b1 >> 5,
+                seq_level_idx_0: b1 & 0x1F,
+                seq_tier_0: b2 & 0x80 != 0,
+                high_bitdepth: b2 & 0x40 != 0,
+                twelve_bit: b2 & 0x20 != 0,
+                monochrome: b2 & 0x10 != 0,
+                chroma_subsampling_x: b2 & 0x08 != 0,
+                chroma_subsampling_y: b2 & 0x04 != 0,
+                chroma_sample_position: b2 & 0x03,
+                initial_presentation_delay_minus_one: (b3 & 0x10 != 0).then_some(b3 & 0x0F),
+                sequence_header,
             })
         })
     }
@@ -634,7 +2168,14 @@ impl<'a> HeifReader<'a> {
             &ItemLocationBox::KIND,
             |this, _start, _box_size, version_flag| {
                 let version = version_flag.version();
-                ensure!(version <= 2, "Unsupported iloc version: {}", version);
+                ensure!(
+                    version <= 2,
+                    HeifError::UnsupportedVersion {
+                        at: this.location(),
+                        kind: FourCC::from(&ItemLocationBox::KIND),
+                        version,
+                    }
+                );
 
                 let b1 = this.read_u8()?;
                 let offset_size = (b1 >> 4) & 0x0F;
@@ -645,6 +2186,7 @@ impl<'a> HeifReader<'a> {
                 let index_size = b2 & 0x0F;
 
                 let item_count = this.read_versioned_u32(version, 2)?;
+                this.ensure_limit("items", item_count as u64, this.limits().max_items as u64)?;
 
                 Ok(ItemLocationBox {
                     offset_size,
@@ -669,6 +2211,12 @@ impl<'a> HeifReader<'a> {
 
                             let base_offset = this.read_variable_size(base_offset_size)?;
                             let extent_count = this.read_u16()?;
+                            this.ensure_limit(
+                                "extents",
+                                extent_count as u64,
+                                this.limits().max_extents as u64,
+                            )?;
+                            this.charge_allocation(extent_count as u64 * 16)?;
 
                             let mut extents = Vec::with_capacity(extent_count as usize);
 
@@ -703,17 +2251,672 @@ impl<'a> HeifReader<'a> {
         )
     }
 
+    fn read_movie_box(&mut self) -> Result<MovieBox<'a>> {
+        self.with_box(&MovieBox::KIND, |this, start, box_size| {
+            let mut header = None;
+            let mut tracks = Vec::new();
+            let mut meta_boxes = Vec::new();
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"mvhd" => {
+                        header = Some(this.read_movie_header_box()?);
+                    }
+                    b"trak" => {
+                        tracks.push(this.read_track_box()?);
+                    }
+                    b"meta" => {
+                        meta_boxes.push(this.read_meta_box()?);
+                    }
+                    b"udta" => {
+                        meta_boxes.extend(this.read_user_data_meta_boxes()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            Ok(MovieBox {
+                header: this.required(header, &MovieHeaderBox::KIND)?,
+                tracks: tracks.into_boxed_slice(),
+                meta_boxes: meta_boxes.into_boxed_slice(),
+            })
+        })
+    }
+
+    fn read_movie_header_box(&mut self) -> Result<MovieHeaderBox> {
+        self.with_full_box(
+            &MovieHeaderBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 1)?;
+                let version = version_flag.version();
+
+                let creation_time = this.read_versioned_u64(version)?;
+                let modification_time = this.read_versioned_u64(version)?;
+                let timescale = this.read_u32()?;
+                let duration = this.read_versioned_u64(version)?;
+
+                // rate, volume, reserved, matrix, pre_defined
+                this.read_slice(4 + 2 + 2 + 8 + 36 + 24)?;
+
+                Ok(MovieHeaderBox {
+                    creation_time,
+                    modification_time,
+                    timescale,
+                    duration,
+                    next_track_id: this.read_u32()?,
+                })
+            },
+        )
+    }
+
+    fn read_track_box(&mut self) -> Result<TrackBox<'a>> {
+        self.with_box(&TrackBox::KIND, |this, start, box_size| {
+            let mut header = None;
+            let mut media = None;
+            let mut meta_boxes = Vec::new();
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"tkhd" => {
+                        header = Some(this.read_track_header_box()?);
+                    }
+                    b"mdia" => {
+                        media = Some(this.read_media_box()?);
+                    }
+                    b"meta" => {
+                        meta_boxes.push(this.read_meta_box()?);
+                    }
+                    b"udta" => {
+                        meta_boxes.extend(this.read_user_data_meta_boxes()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            let (media, samples) = this.required(media, &MediaBox::KIND)?;
+
+            Ok(TrackBox {
+                header: this.required(header, &TrackHeaderBox::KIND)?,
+                media,
+                samples,
+                meta_boxes: meta_boxes.into_boxed_slice(),
+            })
+        })
+    }
+
+    // the `meta` boxes of a `udta`, where iTunes keeps its `mdir` metadata
+    fn read_user_data_meta_boxes(&mut self) -> Result<Vec<MetaBox<'a>>> {
+        self.with_box(&BoxKind(b"udta"), |this, start, box_size| {
+            let mut meta_boxes = Vec::new();
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"meta" => meta_boxes.push(this.read_meta_box()?),
+                    _ => this.skip_box()?,
+                }
+            }
+
+            Ok(meta_boxes)
+        })
+    }
+
+    fn read_track_header_box(&mut self) -> Result<TrackHeaderBox> {
+        self.with_full_box(
+            &TrackHeaderBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 1)?;
+                let version = version_flag.version();
+
+                let creation_time = this.read_versioned_u64(version)?;
+                let modification_time = this.read_versioned_u64(version)?;
+                let track_id = this.read_u32()?;
+                let _reserved = this.read_u32()?;
+                let duration = this.read_versioned_u64(version)?;
+
+                // reserved, layer, alternate_group, volume, reserved, matrix
+                this.read_slice(8 + 2 + 2 + 2 + 2 + 36)?;
+
+                Ok(TrackHeaderBox {
+                    version_flag,
+                    creation_time,
+                    modification_time,
+                    track_id,
+                    duration,
+                    width: this.read_u32()?,
+                    height: this.read_u32()?,
+                })
+            },
+        )
+    }
+
+    // the sample tables are resolved while we still know where they are, so errors in them carry
+    // the stbl box path
+    fn read_media_box(&mut self) -> Result<(MediaBox<'a>, Box<[Sample]>)> {
+        self.with_box(&MediaBox::KIND, |this, start, box_size| {
+            let mut header = None;
+            let mut handler = None;
+            let mut information = None;
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"mdhd" => {
+                        header = Some(this.read_media_header_box()?);
+                    }
+                    b"hdlr" => {
+                        handler = Some(this.read_handler_box()?);
+                    }
+                    b"minf" => {
+                        information = Some(this.read_media_information_box()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            let (information, samples) = this.required(information, &MediaInformationBox::KIND)?;
+
+            Ok((
+                MediaBox {
+                    header: this.required(header, &MediaHeaderBox::KIND)?,
+                    handler: this.required(handler, &HandlerBox::KIND)?,
+                    information,
+                },
+                samples,
+            ))
+        })
+    }
+
+    fn read_media_header_box(&mut self) -> Result<MediaHeaderBox> {
+        self.with_full_box(
+            &MediaHeaderBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 1)?;
+                let version = version_flag.version();
+
+                let creation_time = this.read_versioned_u64(version)?;
+                let modification_time = this.read_versioned_u64(version)?;
+                let timescale = this.read_u32()?;
+                let duration = this.read_versioned_u64(version)?;
+                let language = this.read_u16()? & 0x7FFF;
+                let _pre_defined = this.read_u16()?;
+
+                Ok(MediaHeaderBox {
+                    creation_time,
+                    modification_time,
+                    timescale,
+                    duration,
+                    language,
+                })
+            },
+        )
+    }
+
+    fn read_media_information_box(&mut self) -> Result<(MediaInformationBox<'a>, Box<[Sample]>)> {
+        self.with_box(&MediaInformationBox::KIND, |this, start, box_size| {
+            let mut data_information = None;
+            let mut sample_table = None;
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"dinf" => {
+                        data_information = Some(this.read_data_information_box()?);
+                    }
+                    b"stbl" => {
+                        sample_table = Some(this.read_sample_table_box()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            let (sample_table, samples) = this.required(sample_table, &SampleTableBox::KIND)?;
+
+            Ok((
+                MediaInformationBox {
+                    data_information,
+                    sample_table,
+                },
+                samples,
+            ))
+        })
+    }
+
+    fn read_sample_table_box(&mut self) -> Result<(SampleTableBox, Box<[Sample]>)> {
+        self.with_box(&SampleTableBox::KIND, |this, start, box_size| {
+            let mut sample_description = None;
+            let mut time_to_sample = None;
+            let mut sample_to_chunk = None;
+            let mut sample_size = None;
+            let mut chunk_offset = None;
+            let mut sync_sample = None;
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"stsd" => {
+                        sample_description = Some(this.read_sample_description_box()?);
+                    }
+                    b"stts" => {
+                        time_to_sample = Some(this.read_time_to_sample_box()?);
+                    }
+                    b"stsc" => {
+                        sample_to_chunk = Some(this.read_sample_to_chunk_box()?);
+                    }
+                    b"stsz" => {
+                        sample_size = Some(this.read_sample_size_box()?);
+                    }
+                    b"stz2" => {
+                        sample_size = Some(this.read_compact_sample_size_box()?);
+                    }
+                    b"stco" => {
+                        chunk_offset = Some(this.read_chunk_offset_box(&ChunkOffsetBox::KIND)?);
+                    }
+                    b"co64" => {
+                        chunk_offset = Some(this.read_chunk_offset_box(&BoxKind(b"co64"))?);
+                    }
+                    b"stss" => {
+                        sync_sample = Some(this.read_sync_sample_box()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            let sample_table = SampleTableBox {
+                sample_description: this
+                    .required(sample_description, &SampleDescriptionBox::KIND)?,
+                time_to_sample: this.required(time_to_sample, &TimeToSampleBox::KIND)?,
+                sample_to_chunk: this.required(sample_to_chunk, &SampleToChunkBox::KIND)?,
+                sample_size: this.required(sample_size, &SampleSizeBox::KIND)?,
+                chunk_offset: this.required(chunk_offset, &ChunkOffsetBox::KIND)?,
+                sync_sample,
+            };
+
+            let samples = this.resolve_samples(&sample_table)?;
+
+            Ok((sample_table, samples))
+        })
+    }
+
+    // walks stsc chunk by chunk, handing out sizes from stsz and durations from stts in decoding
+    // order (ISO/IEC 14496-12 8.7.4)
+    fn resolve_samples(&mut self, stbl: &SampleTableBox) -> Result<Box<[Sample]>> {
+        let sample_count = stbl.sample_size.sample_count as usize;
+        self.charge_allocation((sample_count * size_of::<Sample>()) as u64)?;
+
+        let mut durations = stbl
+            .time_to_sample
+            .entries
+            .iter()
+            .flat_map(|&(count, delta)| std::iter::repeat_n(delta, count as usize));
+
+        let chunks = &stbl.chunk_offset.offsets;
+        let entries = &stbl.sample_to_chunk.entries;
+
+        let mut samples = Vec::with_capacity(sample_count);
+        let mut decode_time = 0u64;
+
+        'chunks: for (i, entry) in entries.iter().enumerate() {
+            let end_chunk = entries
+                .get(i + 1)
+                .map_or(chunks.len() as u64 + 1, |next| next.first_chunk as u64);
+
+            ensure!(
+                entry.first_chunk >= 1 && entry.first_chunk as u64 <= end_chunk,
+                self.invalid_box(&format!(
+                    "stsc entry {i} starts at chunk {}, expected increasing 1-based chunk numbers",
+                    entry.first_chunk
+                ))
+            );
+
+            for chunk in entry.first_chunk as u64..end_chunk {
+                let mut offset = *chunks.get(chunk as usize - 1).ok_or_else(|| {
+                    self.invalid_box(&format!(
+                        "stsc references chunk {chunk}, but there are only {} chunk offsets",
+                        chunks.len()
+                    ))
+                })?;
+
+                for _ in 0..entry.samples_per_chunk {
+                    if samples.len() == sample_count {
+                        break 'chunks;
+                    }
+
+                    let size = stbl.sample_size.size_of(samples.len()).ok_or_else(|| {
+                        self.invalid_box("stsz has fewer entries than sample_count")
+                    })?;
+                    let duration = durations
+                        .next()
+                        .ok_or_else(|| self.invalid_box("stts covers fewer samples than stsz"))?;
+                    let is_sync = stbl.sync_sample.as_ref().is_none_or(|stss| {
+                        stss.sample_numbers
+                            .binary_search(&(samples.len() as u32 + 1))
+                            .is_ok()
+                    });
+
+                    samples.push(Sample {
+                        offset,
+                        size,
+                        decode_time,
+                        duration,
+                        is_sync,
+                        sample_description_index: entry.sample_description_index,
+                    });
+
+                    offset = offset.checked_add(size as u64).ok_or_else(|| {
+                        self.invalid_box(&format!("sample in chunk {chunk} overflows u64"))
+                    })?;
+                    decode_time = decode_time.saturating_add(duration as u64);
+                }
+            }
+        }
+
+        ensure!(
+            samples.len() == sample_count,
+            self.invalid_box(&format!(
+                "stsc maps {} of {sample_count} samples to chunks",
+                samples.len()
+            ))
+        );
+
+        Ok(samples.into_boxed_slice())
+    }
+
+    fn read_sample_description_box(&mut self) -> Result<SampleDescriptionBox> {
+        self.with_full_box(
+            &SampleDescriptionBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 1)?;
+
+                let entry_count = this.read_u32()?;
+
+                Ok(SampleDescriptionBox {
+                    entries: this.read_slice_fn(entry_count as usize, Self::read_sample_entry)?,
+                })
+            },
+        )
+    }
+
+    fn read_sample_entry(&mut self) -> Result<SampleEntry> {
+        self.with_box_unchecked(|this, kind, start, box_size| {
+            // SampleEntry: reserved
+            this.read_slice(6)?;
+            let data_reference_index = this.read_u16()?;
+
+            if kind != BoxKind(b"hvc1") {
+                let remainder = this.remaining_bytes_in_box(start, box_size);
+                this.read_slice(remainder)?;
+
+                return Ok(SampleEntry::Other {
+                    kind: FourCC::from(&kind),
+                    data_reference_index,
+                });
+            }
+
+            // VisualSampleEntry: pre_defined, reserved, pre_defined[3]
+            this.read_slice(2 + 2 + 12)?;
+            let width = this.read_u16()?;
+            let height = this.read_u16()?;
+            // horizresolution, vertresolution, reserved, frame_count, compressorname, depth,
+            // pre_defined
+            this.read_slice(4 + 4 + 4 + 2 + 32 + 2 + 2)?;
+
+            let mut config = None;
+
+            loop {
+                if this.cursor >= start + box_size {
+                    break;
+                }
+
+                match this.peek_box_kind()? {
+                    b"hvcC" => {
+                        config = Some(this.read_hevc_decoder_configuration_box()?);
+                    }
+                    _ => this.skip_box()?,
+                }
+            }
+
+            Ok(SampleEntry::Hvc1(VisualSampleEntry {
+                data_reference_index,
+                width,
+                height,
+                config: this.required(config, &BoxKind(b"hvcC"))?,
+            }))
+        })
+    }
+
+    fn read_time_to_sample_box(&mut self) -> Result<TimeToSampleBox> {
+        self.with_full_box(
+            &TimeToSampleBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let entry_count = this.read_u32()?;
+
+                Ok(TimeToSampleBox {
+                    entries: this.read_slice_fn(entry_count as usize, |this| {
+                        Ok((this.read_u32()?, this.read_u32()?))
+                    })?,
+                })
+            },
+        )
+    }
+
+    fn read_sample_to_chunk_box(&mut self) -> Result<SampleToChunkBox> {
+        self.with_full_box(
+            &SampleToChunkBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let entry_count = this.read_u32()?;
+
+                Ok(SampleToChunkBox {
+                    entries: this.read_slice_fn(entry_count as usize, |this| {
+                        Ok(SampleToChunkEntry {
+                            first_chunk: this.read_u32()?,
+                            samples_per_chunk: this.read_u32()?,
+                            sample_description_index: this.read_u32()?,
+                        })
+                    })?,
+                })
+            },
+        )
+    }
+
+    fn read_sample_size_box(&mut self) -> Result<SampleSizeBox> {
+        self.with_full_box(
+            &SampleSizeBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let sample_size = this.read_u32()?;
+                let sample_count = this.read_u32()?;
+
+                let entry_sizes = if sample_size == 0 {
+                    this.read_slice_fn(sample_count as usize, Self::read_u32)?
+                } else {
+                    Box::default()
+                };
+
+                Ok(SampleSizeBox {
+                    sample_size,
+                    sample_count,
+                    entry_sizes,
+                })
+            },
+        )
+    }
+
+    fn read_compact_sample_size_box(&mut self) -> Result<SampleSizeBox> {
+        self.with_full_box(
+            &BoxKind(b"stz2"),
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let _reserved = this.read_slice(3)?;
+                let field_size = this.read_u8()?;
+                let sample_count = this.read_u32()?;
+
+                let entry_sizes = match field_size {
+                    4 => {
+                        let bytes = this.read_slice((sample_count as usize).div_ceil(2))?;
+                        this.charge_allocation(sample_count as u64 * 4)?;
+
+                        bytes
+                            .iter()
+                            .flat_map(|b| [(b >> 4) as u32, (b & 0x0F) as u32])
+                            .take(sample_count as usize)
+                            .collect()
+                    }
+                    8 => this
+                        .read_slice_fn(sample_count as usize, |this| Ok(this.read_u8()? as u32))?,
+                    16 => this
+                        .read_slice_fn(sample_count as usize, |this| Ok(this.read_u16()? as u32))?,
+                    _ => bail!(
+                        this.invalid_box(&format!("unsupported stz2 field_size {field_size}"))
+                    ),
+                };
+
+                Ok(SampleSizeBox {
+                    sample_size: 0,
+                    sample_count,
+                    entry_sizes,
+                })
+            },
+        )
+    }
+
+    fn read_chunk_offset_box(&mut self, kind: &BoxKind<'a>) -> Result<ChunkOffsetBox> {
+        let large = *kind == BoxKind(b"co64");
+
+        self.with_full_box(kind, |this, _start, _box_size, version_flag| {
+            this.ensure_version(&version_flag, 0)?;
+
+            let entry_count = this.read_u32()?;
+
+            Ok(ChunkOffsetBox {
+                offsets: this.read_slice_fn(entry_count as usize, |this| {
+                    if large {
+                        this.read_u64()
+                    } else {
+                        Ok(this.read_u32()? as u64)
+                    }
+                })?,
+            })
+        })
+    }
+
+    fn read_sync_sample_box(&mut self) -> Result<SyncSampleBox> {
+        self.with_full_box(
+            &SyncSampleBox::KIND,
+            |this, _start, _box_size, version_flag| {
+                this.ensure_version(&version_flag, 0)?;
+
+                let entry_count = this.read_u32()?;
+                let sample_numbers = this.read_slice_fn(entry_count as usize, Self::read_u32)?;
+
+                ensure!(
+                    sample_numbers.is_sorted_by(|a, b| a < b),
+                    this.invalid_box("stss sample numbers must be strictly increasing")
+                );
+
+                Ok(SyncSampleBox { sample_numbers })
+            },
+        )
+    }
+
     fn read_variable_size(&mut self, size: u8) -> Result<u64> {
         match size {
             0 => Ok(0),
             4 => Ok(self.read_u32()? as u64),
             8 => Ok(self.read_u64()?),
-            _ => bail!("unsupported size: {}", size),
+            _ => bail!(self.invalid_box(&format!("unsupported field size {size}"))),
         }
     }
 
     // some helper methods to reduce ceremony
 
+    fn invalid_box(&self, reason: &str) -> HeifError {
+        HeifError::InvalidBox {
+            at: self.location(),
+            reason: reason.to_string(),
+        }
+    }
+
+    const fn limits(&self) -> &DecodeLimits {
+        &self.options.limits
+    }
+
+    fn ensure_limit(&self, limit: &'static str, value: u64, max: u64) -> Result<()> {
+        Ok(ensure_within(limit, value, max, || self.location())?)
+    }
+
+    fn warn_or_bail(&mut self, err: HeifError) -> Result<()> {
+        if self.options.strict {
+            return Err(err.into());
+        }
+
+        self.warnings.push(err);
+        Ok(())
+    }
+
+    fn ensure_version(&self, version_flag: &VersionFlag, max: u8) -> Result<()> {
+        let version = version_flag.version();
+
+        ensure!(
+            version <= max,
+            HeifError::UnsupportedVersion {
+                at: self.location(),
+                kind: self
+                    .box_stack
+                    .last()
+                    .map_or_else(|| FourCC::from(&RootBox::KIND), FourCC::from),
+                version,
+            }
+        );
+
+        Ok(())
+    }
+
+    fn required<T>(&self, value: Option<T>, kind: &BoxKind<'a>) -> Result<T> {
+        value.ok_or_else(|| {
+            HeifError::MissingBox {
+                at: self.location(),
+                kind: FourCC::from(kind),
+            }
+            .into()
+        })
+    }
+
+    /// read u64 with version-dependent size: u32 in version 0, as in mvhd, tkhd and mdhd
+    fn read_versioned_u64(&mut self, version: u8) -> Result<u64> {
+        if version == 0 {
+            Ok(self.read_u32()? as u64)
+        } else {
+            self.read_u64()
+        }
+    }
+
     /// read u32 with version-dependent size: u16 if version < threshold, otherwise u32
     fn read_versioned_u32(&mut self, version: u8, threshold: u8) -> Result<u32> {
         let n = if version < threshold {
@@ -722,21 +2925,68 @@ impl<'a> HeifReader<'a> {
             self.read_u32()?
         };
 
-        Ok(n)
+        Ok(n)
+    }
+
+    // saturates so a child that overran its parent is caught by `ensure_box_end` rather than
+    // underflowing here
+    const fn remaining_bytes_in_box(&self, start: usize, box_size: usize) -> usize {
+        box_size.saturating_sub(self.cursor - start)
+    }
+
+    // a null-terminated UTF-8 string; one running to the end of the box without its terminator
+    // is taken as it is, as some writers drop the last one
+    fn read_utf8_string(&mut self, start: usize, box_size: usize) -> Result<&'a str> {
+        let remainder = self.remaining_bytes_in_box(start, box_size);
+        let bytes = self
+            .data
+            .get(self.cursor..self.cursor + remainder)
+            .ok_or_else(|| self.truncated(remainder))?;
+
+        let (len, terminator) = bytes
+            .iter()
+            .position(|&b| b == 0x00)
+            .map_or((bytes.len(), 0), |end| (end, 1));
+        let string = str::from_utf8(&bytes[..len])
+            .map_err(|err| self.invalid_box(&format!("invalid UTF-8 string: {err}")))?;
+        self.cursor += len + terminator;
+
+        Ok(string)
+    }
+
+    // whether the box at the cursor runs past the end of the input
+    fn box_is_cut_off(&self) -> bool {
+        let rest = &self.data[self.cursor..];
+        let be_u64 = |bytes: &[u8]| bytes.iter().fold(0u64, |n, &b| (n << 8) | b as u64);
+
+        match rest.get(..4).map(be_u64) {
+            None => true,
+            Some(_) if rest.len() < 8 => true,
+            // to the end of the file, however long that turns out to be
+            Some(0) => false,
+            Some(1) => rest
+                .get(8..16)
+                .is_none_or(|size| be_u64(size) > rest.len() as u64),
+            Some(size) => size > rest.len() as u64,
+        }
     }
 
-    const fn remaining_bytes_in_box(&self, start: usize, box_size: usize) -> usize {
-        box_size - (self.cursor - start)
+    fn read_raw_box(&mut self) -> Result<RawBox<'a>> {
+        let start = self.cursor;
+        let (kind, box_size) = self.read_box_header()?;
+        let payload = self.read_slice(box_size - (self.cursor - start))?;
+
+        Ok(RawBox { kind, payload })
     }
 
-    fn skip_box(&mut self, foreign_box_kind: &[u8]) -> Result<()> {
-        eprintln!(
-            "Skipping unrecognized box: {:?}\n\tbox stack: {:?}",
-            str::from_utf8(foreign_box_kind),
-            self.box_stack
-        );
+    // unknown boxes are allowed anywhere, so even strict mode only records them
+    fn skip_box(&mut self) -> Result<()> {
         let start = self.cursor;
-        let (_, box_size) = self.read_box_header()?;
+        let (kind, box_size) = self.read_box_header()?;
+        self.warnings.push(HeifError::UnsupportedBox {
+            at: self.location_of(start),
+            kind: FourCC::from(&kind),
+        });
         self.cursor = start + box_size;
 
         Ok(())
@@ -750,11 +3000,11 @@ impl<'a> HeifReader<'a> {
         self.box_stack.push(expected_kind.clone());
         let start = self.cursor;
         let (kind, box_size) = self.read_box_header()?;
-        ensure!(kind == *expected_kind);
+        self.ensure_kind(&kind, expected_kind, start)?;
 
         let result = f(self, start, box_size)?;
 
-        ensure!(self.cursor == start + box_size);
+        self.ensure_box_end(start, box_size)?;
         self.box_stack.pop();
 
         Ok(result)
@@ -768,11 +3018,11 @@ impl<'a> HeifReader<'a> {
         self.box_stack.push(expected_kind.clone());
         let start = self.cursor;
         let (kind, box_size, version_flag) = self.read_full_box_header()?;
-        ensure!(kind == *expected_kind);
+        self.ensure_kind(&kind, expected_kind, start)?;
 
         let result = f(self, start, box_size, version_flag)?;
 
-        ensure!(self.cursor == start + box_size);
+        self.ensure_box_end(start, box_size)?;
         self.box_stack.pop();
 
         Ok(result)
@@ -788,12 +3038,52 @@ impl<'a> HeifReader<'a> {
         self.box_stack.push(kind.clone());
         let result = f(self, kind, start, box_size)?;
 
-        ensure!(self.cursor == start + box_size);
+        self.ensure_box_end(start, box_size)?;
         self.box_stack.pop();
 
         Ok(result)
     }
 
+    fn ensure_kind(&self, kind: &BoxKind<'a>, expected: &BoxKind<'a>, start: usize) -> Result<()> {
+        ensure!(
+            kind == expected,
+            HeifError::UnexpectedBox {
+                at: ErrorLocation {
+                    offset: start,
+                    ..self.location()
+                },
+                expected: FourCC::from(expected),
+                found: FourCC::from(kind),
+            }
+        );
+
+        Ok(())
+    }
+
+    // trailing bytes after a box's known contents are tolerated in lenient mode, but reading past
+    // the end of a box never is
+    fn ensure_box_end(&mut self, start: usize, box_size: usize) -> Result<()> {
+        let end = start + box_size;
+
+        if self.cursor < end {
+            self.warn_or_bail(self.invalid_box(&format!(
+                "{} trailing bytes in box spanning {start:#x}..{end:#x}",
+                end - self.cursor
+            )))?;
+            self.cursor = end;
+        }
+
+        ensure!(
+            self.cursor == end,
+            self.invalid_box(&format!(
+                "box spans {start:#x}..{end:#x} but its contents ended at {:#x}",
+                self.cursor
+            ))
+        );
+
+        Ok(())
+    }
+
     fn read_version_flag(&mut self) -> Result<VersionFlag> {
         Ok(VersionFlag::from(self.read_u32()?))
     }
@@ -804,17 +3094,42 @@ impl<'a> HeifReader<'a> {
     }
 
     fn read_box_header(&mut self) -> Result<(BoxKind<'a>, usize)> {
+        let start = self.cursor;
         let mut size = self.read_u32()? as usize;
         let kind = self.read_box_kind()?;
 
-        if size == 1 {
-            size = self.read_u64()? as usize;
+        match size {
+            // the box extends to the end of the file
+            0 => size = self.data.len() - start,
+            1 => size = self.read_u64()? as usize,
+            _ => {}
         }
 
         if kind == b"uuid".into() {
             let _user_kind = self.read_slice(16)?;
         }
 
+        let header_size = self.cursor - start;
+        ensure!(
+            size >= header_size,
+            self.invalid_box(&format!(
+                "box size {size} is smaller than its {header_size} byte header"
+            ))
+        );
+
+        let available = self.data.len() - start;
+        ensure!(
+            size <= available,
+            HeifError::Truncated {
+                at: ErrorLocation {
+                    offset: start,
+                    ..self.location()
+                },
+                needed: size,
+                available,
+            }
+        );
+
         Ok((kind, size))
     }
 
@@ -822,7 +3137,7 @@ impl<'a> HeifReader<'a> {
     fn peek_box_kind(&self) -> Result<&'a [u8; 4]> {
         self.data
             .get(self.cursor + 4..self.cursor + 8)
-            .ok_or_else(|| anyhow!("oob"))?
+            .ok_or_else(|| self.truncated(8))?
             .try_into()
             .map_err(|_| anyhow!("should fit"))
     }
@@ -835,23 +3150,39 @@ impl<'a> HeifReader<'a> {
         let s = self
             .data
             .get(self.cursor..self.cursor + len)
-            .ok_or_else(|| anyhow!("oob"))?;
+            .ok_or_else(|| self.truncated(len))?;
 
         self.cursor += len;
         Ok(s)
     }
 
+    fn truncated(&self, needed: usize) -> HeifError {
+        HeifError::Truncated {
+            at: self.location(),
+            needed,
+            available: self.data.len().saturating_sub(self.cursor),
+        }
+    }
+
     fn read_fixed_slice<const N: usize>(&mut self) -> Result<&'a [u8; N]> {
         self.read_slice(N)?
             .try_into()
             .map_err(|_| anyhow!("should fit"))
     }
 
+    // every element takes at least one byte, so a count larger than what's left can only be
+    // garbage, and must not reach the allocator
     fn read_slice_fn<T>(
         &mut self,
         len: usize,
         f: impl Fn(&mut Self) -> Result<T>,
     ) -> Result<Box<[T]>> {
+        if len > self.data.len().saturating_sub(self.cursor) {
+            bail!(self.truncated(len));
+        }
+
+        self.charge_allocation((len * size_of::<T>()) as u64)?;
+
         (0..len)
             .map(|_| f(self))
             .collect::<Result<Vec<_>>>()
@@ -863,3 +3194,1144 @@ impl<'a> HeifReader<'a> {
     impl_read_for_datatype!(read_u32, u32);
     impl_read_for_datatype!(read_u64, u64);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn make_full_box(kind: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+        let mut body = (((version as u32) << 24) | flags).to_be_bytes().to_vec();
+        body.extend_from_slice(payload);
+        make_box(kind, &body)
+    }
+
+    fn ftyp() -> Vec<u8> {
+        make_box(b"ftyp", b"heic\0\0\0\0mif1heic")
+    }
+
+    fn hdlr(handler: &[u8; 4]) -> Vec<u8> {
+        let mut payload = vec![0; 4];
+        payload.extend_from_slice(handler);
+        payload.extend_from_slice(&[0; 12]);
+        payload.push(0);
+        make_full_box(b"hdlr", 0, 0, &payload)
+    }
+
+    fn pitm(item_id: u16) -> Vec<u8> {
+        make_full_box(b"pitm", 0, 0, &item_id.to_be_bytes())
+    }
+
+    fn iinf() -> Vec<u8> {
+        let infe = make_full_box(b"infe", 2, 0, b"\0\x01\0\0hvc1\0");
+        let mut payload = 1u16.to_be_bytes().to_vec();
+        payload.extend(infe);
+        make_full_box(b"iinf", 0, 0, &payload)
+    }
+
+    fn iinf_with_items(item_ids: &[u16]) -> Vec<u8> {
+        let mut payload = (item_ids.len() as u16).to_be_bytes().to_vec();
+        for item_id in item_ids {
+            let infe = [&item_id.to_be_bytes()[..], b"\0\0hvc1\0"].concat();
+            payload.extend(make_full_box(b"infe", 2, 0, &infe));
+        }
+        make_full_box(b"iinf", 0, 0, &payload)
+    }
+
+    fn iloc() -> Vec<u8> {
+        make_full_box(b"iloc", 0, 0, &[0x44, 0x00, 0x00, 0x00])
+    }
+
+    fn heif_with_meta(children: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = ftyp();
+        data.extend(make_full_box(b"meta", 0, 0, &children.concat()));
+        data
+    }
+
+    fn strict() -> ParseOptions {
+        ParseOptions {
+            strict: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_truncated_error_carries_box_path() {
+        let mut data = ftyp();
+        data.truncate(14);
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+
+        assert!(matches!(err, HeifError::Truncated { .. }), "{err}");
+        assert_eq!(err.box_path(), &[FourCC(*b"root"), FourCC(*b"ftyp")]);
+        assert_eq!(err.offset(), 0);
+    }
+
+    #[test]
+    fn test_missing_meta_box() {
+        let mut data = ftyp();
+        data.extend(make_box(b"mdat", &[0; 4]));
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+
+        assert!(
+            matches!(err, HeifError::MissingBox { kind, .. } if kind == FourCC(*b"meta")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_unexpected_box_kind() {
+        let data = make_box(b"moov", &[]);
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+
+        assert!(matches!(
+            err,
+            HeifError::UnexpectedBox { expected, found, .. }
+                if expected == FourCC(*b"ftyp") && found == FourCC(*b"moov")
+        ));
+        assert_eq!(err.offset(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_pitm() {
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), pitm(2), iinf(), iloc()]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        assert_eq!(heif.primary_item_id(), Some(1));
+        assert_eq!(heif.warnings.len(), 1);
+
+        let err = HeifReader::with_options(&data, strict())
+            .read()
+            .unwrap_err();
+        assert_eq!(
+            err.box_path(),
+            &[FourCC(*b"root"), FourCC(*b"meta")],
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_box_is_recorded() {
+        let mut data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc()]);
+        data.extend(make_box(b"abcd", b"xyz"));
+
+        let heif = HeifReader::with_options(&data, strict()).read().unwrap();
+        assert!(matches!(
+            heif.warnings.as_ref(),
+            [HeifError::UnsupportedBox { kind, .. }] if *kind == FourCC(*b"abcd")
+        ));
+    }
+
+    #[test]
+    fn test_other_meta_boxes() {
+        let title = make_box(b"\xa9nam", b"title");
+        // iTunes' hdlr has its vendor code in the first reserved field
+        let itunes_hdlr = make_full_box(b"hdlr", 0, 0, b"\0\0\0\0mdirappl\0\0\0\0\0\0\0\0\0");
+        let mdir = make_full_box(
+            b"meta",
+            0,
+            0,
+            &[itunes_hdlr, make_box(b"ilst", &title)].concat(),
+        );
+
+        let mut data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc()]);
+        data.extend(mdir);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        assert_eq!(heif.meta_box.as_ref().unwrap().handler.kind, "pict");
+        assert!(matches!(
+            heif.warnings.as_ref(),
+            [HeifError::InvalidBox { .. }]
+        ));
+
+        let mdir = heif.meta_box_with_handler("mdir").unwrap();
+        assert_eq!(mdir.primary_item_id(), None);
+        assert!(mdir.item_info.item_info_entries.is_empty());
+        assert_eq!(mdir.other_box(b"ilst").unwrap().payload, title);
+
+        assert!(HeifReader::with_options(&data, strict()).read().is_err());
+    }
+
+    #[test]
+    fn test_track_meta_boxes() {
+        let mut data = sequence_with_stsc(&[1, 1, 3, 1]);
+        let find = |kind: &[u8; 4]| data.windows(4).position(|w| w == kind).unwrap() - 4;
+        let (moov, trak) = (find(b"moov"), find(b"trak"));
+        let grow = |data: &mut [u8], at: usize, by: usize| {
+            let size = u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+            data[at..at + 4].copy_from_slice(&(size + by as u32).to_be_bytes());
+        };
+
+        // trak is the last box of moov, so appending to the file appends to both
+        let track_meta = make_full_box(
+            b"meta",
+            0,
+            0,
+            &[hdlr(b"pict"), pitm(1), iinf(), iloc()].concat(),
+        );
+        grow(&mut data, trak, track_meta.len());
+        grow(&mut data, moov, track_meta.len());
+        data.extend(track_meta);
+
+        let udta = make_box(b"udta", &make_full_box(b"meta", 0, 0, &hdlr(b"mdir")));
+        grow(&mut data, moov, udta.len());
+        data.extend(udta);
+
+        let heif = HeifReader::with_options(&data, strict()).read().unwrap();
+        assert!(heif.meta_box.is_none());
+
+        let handlers = heif.meta_boxes().map(|meta| meta.handler.kind);
+        assert_eq!(handlers.collect::<Vec<_>>(), ["mdir", "pict"]);
+        assert_eq!(heif.tracks()[0].meta_boxes[0].primary_item_id(), Some(1));
+    }
+
+    #[test]
+    fn test_trailing_bytes_in_box() {
+        let mut pitm = pitm(1);
+        pitm.extend_from_slice(&[0xAB, 0xCD]);
+        pitm[3] += 2;
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm, iinf(), iloc()]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        assert_eq!(heif.primary_item_id(), Some(1));
+        assert_eq!(heif.warnings.len(), 1);
+
+        let err = HeifReader::with_options(&data, strict())
+            .read()
+            .unwrap_err();
+        assert!(matches!(err, HeifError::InvalidBox { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_item_count_limit() {
+        let iloc = make_full_box(b"iloc", 1, 0, &[0x44, 0x00, 0xFF, 0xFF]);
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc]);
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+        assert!(
+            matches!(
+                err,
+                HeifError::LimitExceeded {
+                    limit: "items",
+                    value: 65535,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+        assert_eq!(err.box_path().last(), Some(&FourCC(*b"iloc")));
+    }
+
+    #[test]
+    fn test_huge_count_does_not_allocate() {
+        let dref = make_full_box(b"dref", 0, 0, &u32::MAX.to_be_bytes());
+        let dinf = make_box(b"dinf", &dref);
+        let data = heif_with_meta(&[hdlr(b"pict"), dinf, pitm(1), iinf(), iloc()]);
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+        assert!(matches!(err, HeifError::Truncated { .. }), "{err}");
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    // a moov with one hvc1 track of three samples: two in the first chunk, one in the second
+    fn sequence_with_stsc(stsc: &[u32]) -> Vec<u8> {
+        let hvcc = make_box(b"hvcC", &[&[1][..], &[0; 22]].concat());
+        let mut entry = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        entry.extend_from_slice(&[0; 16]);
+        entry.extend_from_slice(&[0, 64, 0, 48]);
+        entry.extend_from_slice(&[0; 50]);
+        entry.extend(hvcc);
+        let hvc1 = make_box(b"hvc1", &entry);
+
+        let stbl = make_box(
+            b"stbl",
+            &[
+                make_full_box(b"stsd", 0, 0, &[&u32s(&[1])[..], &hvc1].concat()),
+                make_full_box(b"stts", 0, 0, &u32s(&[1, 3, 512])),
+                make_full_box(b"stsc", 0, 0, &u32s(stsc)),
+                make_full_box(b"stsz", 0, 0, &u32s(&[0, 3, 10, 20, 30])),
+                make_full_box(b"stco", 0, 0, &u32s(&[2, 100, 200])),
+                make_full_box(b"stss", 0, 0, &u32s(&[1, 1])),
+            ]
+            .concat(),
+        );
+
+        let mdhd = make_full_box(b"mdhd", 0, 0, &u32s(&[0, 0, 1000, 1536, 0]));
+        let minf = make_box(b"minf", &stbl);
+        let mdia = make_box(b"mdia", &[mdhd, hdlr(b"pict"), minf].concat());
+        let tkhd = make_full_box(
+            b"tkhd",
+            0,
+            3,
+            &[
+                &u32s(&[0, 0, 1, 0, 1536])[..],
+                &[0; 52],
+                &u32s(&[64 << 16, 48 << 16]),
+            ]
+            .concat(),
+        );
+        let trak = make_box(b"trak", &[tkhd, mdia].concat());
+        let mvhd = make_full_box(
+            b"mvhd",
+            0,
+            0,
+            &[&u32s(&[0, 0, 1000, 1536])[..], &[0; 76], &u32s(&[2])].concat(),
+        );
+
+        let mut data = make_box(b"ftyp", b"msf1\0\0\0\0msf1hevc");
+        data.extend(make_box(b"moov", &[mvhd, trak].concat()));
+        data
+    }
+
+    #[test]
+    fn test_image_sequence_samples() {
+        let data = sequence_with_stsc(&[2, 1, 2, 1, 2, 1, 1]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        assert!(heif.meta_box.is_none());
+
+        let [track] = heif.tracks() else {
+            panic!("expected one track");
+        };
+        assert_eq!(track.track_id(), 1);
+        assert_eq!(track.timescale(), 1000);
+        assert_eq!(track.handler(), "pict");
+        assert!(track.hevc_configuration_record().is_some());
+
+        let sample = |offset, size, decode_time, is_sync| Sample {
+            offset,
+            size,
+            decode_time,
+            duration: 512,
+            is_sync,
+            sample_description_index: 1,
+        };
+        assert_eq!(
+            *track.samples,
+            [
+                sample(100, 10, 0, true),
+                sample(110, 20, 512, false),
+                sample(200, 30, 1024, false),
+            ]
+        );
+        assert!(matches!(
+            track.sample_entry(&track.samples[0]),
+            Some(SampleEntry::Hvc1(VisualSampleEntry {
+                width: 64,
+                height: 48,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_image_sequence_chunk_out_of_range() {
+        // one sample per chunk, so the third sample lands in a chunk stco doesn't have
+        let data = sequence_with_stsc(&[2, 1, 1, 1, 3, 1, 1]);
+
+        let err = HeifReader::new(&data).read().unwrap_err();
+        assert!(matches!(err, HeifError::InvalidBox { .. }), "{err}");
+        assert_eq!(err.box_path().last(), Some(&FourCC(*b"stbl")));
+    }
+
+    #[test]
+    fn test_entity_groups() {
+        let grpl = make_box(
+            b"grpl",
+            &[
+                make_full_box(b"altr", 0, 0, &u32s(&[10, 3, 1, 2, 3])),
+                make_full_box(b"ster", 0, 0, &u32s(&[11, 2, 4, 5])),
+                make_full_box(b"abcd", 0, 0, &u32s(&[12, 0])),
+                make_full_box(b"prgr", 0, 0, &u32s(&[13, 2, 4, 1])),
+            ]
+            .concat(),
+        );
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc(), grpl]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let meta = heif.meta_box.unwrap();
+
+        assert_eq!(meta.entity_groups().len(), 4);
+        assert_eq!(meta.alternatives_for(2).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(meta.alternatives_for(4).count(), 0);
+        assert_eq!(meta.stereo_pair(5), Some((4, 5)));
+        assert_eq!(meta.stereo_pair(1), None);
+        assert_eq!(meta.entity_groups()[2].kind.four_cc(), FourCC(*b"abcd"));
+        assert_eq!(meta.progressive_order(1), Some(&[4, 1][..]));
+        assert_eq!(meta.progressive_order(2), None);
+    }
+
+    #[test]
+    fn test_stereo_group_without_a_pair() {
+        let grpl = make_box(
+            b"grpl",
+            &[
+                make_full_box(b"ster", 0, 0, &u32s(&[11, 3, 4, 5, 6])),
+                make_full_box(b"altr", 0, 0, &u32s(&[10, 2, 1, 2])),
+            ]
+            .concat(),
+        );
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc(), grpl]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+        assert_eq!(meta.entity_groups().len(), 1);
+        assert_eq!(meta.stereo_pair(5), None);
+        assert!(matches!(
+            heif.warnings.as_ref(),
+            [HeifError::InvalidBox { .. }]
+        ));
+
+        assert!(HeifReader::with_options(&data, strict()).read().is_err());
+    }
+
+    #[test]
+    fn test_partial_input() {
+        // item 1 within ftyp, item 2 in the mdat that follows the meta
+        let with_item_2_at = |offset: u32| {
+            let mut iloc = vec![0x44, 0x00, 0, 2];
+            iloc.extend(u16s(&[1, 0, 1]));
+            iloc.extend(u32s(&[2, 3]));
+            iloc.extend(u16s(&[2, 0, 1]));
+            iloc.extend(u32s(&[offset, 4]));
+
+            heif_with_meta(&[
+                hdlr(b"pict"),
+                pitm(1),
+                iinf_with_items(&[1, 2]),
+                make_full_box(b"iloc", 0, 0, &iloc),
+            ])
+        };
+        let mut data = with_item_2_at(0);
+        data = with_item_2_at(data.len() as u32 + 8);
+        data.extend(make_box(b"mdat", b"abcd"));
+
+        let partial = ParseOptions {
+            partial: true,
+            ..strict()
+        };
+        for (cut, available) in [(0, &[1, 2][..]), (1, &[1]), (7, &[1])] {
+            let data = &data[..data.len() - cut];
+            let mut reader = HeifReader::with_options(data, partial);
+            let heif = reader.read().unwrap();
+            let meta = heif.meta_box.as_ref().unwrap();
+
+            assert_eq!(reader.available_items(meta).collect::<Vec<_>>(), available);
+        }
+
+        assert!(
+            HeifReader::with_options(&data[..data.len() - 1], strict())
+                .read()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_base_offset_past_the_end() {
+        // 8 byte base_offset: item 1 at u64::MAX, plus an extent offset of 1
+        let mut iloc = vec![0x44, 0x80, 0, 1];
+        iloc.extend(u16s(&[1, 0]));
+        iloc.extend(u64::MAX.to_be_bytes());
+        iloc.extend(u16s(&[1]));
+        iloc.extend(u32s(&[1, 3]));
+
+        let data = heif_with_meta(&[
+            hdlr(b"pict"),
+            pitm(1),
+            iinf_with_items(&[1]),
+            make_full_box(b"iloc", 0, 0, &iloc),
+        ]);
+        let mut reader = HeifReader::new(&data);
+        let heif = reader.read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+
+        assert_eq!(
+            meta.item_location.reference(1).unwrap().data_offset(),
+            u64::MAX
+        );
+        assert!(reader.get_item_data(1, meta).is_err());
+    }
+
+    #[test]
+    fn test_data_references() {
+        let dref = [
+            &u32s(&[5])[..],
+            &make_full_box(b"url ", 0, 1, &[]),
+            &make_full_box(b"imdt", 0, 0, &u32s(&[7])),
+            &make_full_box(b"url ", 0, 0, b"other.heic\0"),
+            &make_full_box(b"snim", 0, 0, &[]),
+            &make_full_box(b"imdt", 0, 0, &u32s(&[8])),
+        ]
+        .concat();
+        let dinf = make_box(b"dinf", &make_full_box(b"dref", 0, 0, &dref));
+
+        // (item_id, data_reference_index, extent_length), each with one extent at offset 2
+        let items = [
+            (1, 1, 3),
+            (2, 2, 3),
+            (3, 3, 3),
+            (4, 4, 3),
+            (5, 5, 3),
+            (6, 9, 3),
+            (7, 3, 0),
+        ];
+        let mut iloc = vec![0x44, 0x00, 0, items.len() as u8];
+        for (item_id, data_reference_index, extent_length) in items {
+            iloc.extend(u16s(&[item_id, data_reference_index, 1]));
+            iloc.extend(u32s(&[2, extent_length]));
+        }
+
+        let item_ids = items.map(|(item_id, _, _)| item_id);
+        let mut data = heif_with_meta(&[
+            hdlr(b"pict"),
+            pitm(1),
+            iinf_with_items(&item_ids),
+            make_full_box(b"iloc", 0, 0, &iloc),
+            dinf,
+        ]);
+        data.extend(make_box(b"imda", &[&u32s(&[7])[..], b"abcdefg"].concat()));
+
+        let mut reader = HeifReader::with_options(&data, strict());
+        let heif = reader.read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+
+        // self-contained, so within ftyp
+        assert_eq!(*reader.get_item_data(1, meta).unwrap(), *b"\0\x18f");
+        assert_eq!(*reader.get_item_data(2, meta).unwrap(), *b"cde");
+
+        for (item_id, reference) in [
+            (3, "url \"other.heic\""),
+            (4, "an snim entry"),
+            (5, "imda 8, which isn't in the file"),
+            (6, "dref entry 9 of 5"),
+        ] {
+            let err = reader.get_item_data(item_id, meta).unwrap_err();
+            assert!(
+                matches!(
+                    &err,
+                    HeifError::UnresolvedDataReference { item_id: id, reference: r, .. }
+                        if *id == item_id && r == reference
+                ),
+                "{err}"
+            );
+        }
+
+        // other.heic through a resolver
+        #[derive(Debug)]
+        struct OtherFile;
+
+        impl DataResolver for OtherFile {
+            fn read(&self, location: &str, offset: u64, length: u64) -> std::io::Result<Vec<u8>> {
+                assert_eq!(location, "other.heic");
+                Ok(b"0123456789"[offset as usize..(offset + length) as usize].to_vec())
+            }
+        }
+
+        let mut reader = HeifReader::new(&data).with_resolver(&OtherFile);
+        let heif = reader.read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+        assert_eq!(*reader.get_item_data(3, meta).unwrap(), *b"234");
+
+        // the whole of other.heic
+        let err = reader.get_item_data(7, meta).unwrap_err();
+        assert!(
+            err.to_string().contains("without an extent length"),
+            "{err}"
+        );
+
+        // every read counts against the same budget
+        let options = ParseOptions {
+            limits: DecodeLimits {
+                max_total_allocation: 4096,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut reader = HeifReader::with_options(&data, options).with_resolver(&OtherFile);
+        let heif = reader.read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+        let err = (0..2048)
+            .find_map(|_| reader.get_item_data(3, meta).err())
+            .unwrap();
+        assert!(
+            matches!(
+                err,
+                HeifError::LimitExceeded {
+                    limit: "total allocation",
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_item_info_entry_versions() {
+        let mut fdel = b"fdel".to_vec();
+        fdel.extend(b"http://example.com/a.jpg\0\0");
+        fdel.extend([
+            0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 12, 1, 0, 0, 0, 7,
+        ]);
+
+        let mut iinf = 4u32.to_be_bytes().to_vec();
+        iinf.extend(make_full_box(
+            b"infe",
+            0,
+            0,
+            b"\0\x01\0\0a\0text/plain\0gzip\0",
+        ));
+        iinf.extend(make_full_box(
+            b"infe",
+            1,
+            0,
+            &[&b"\0\x02\0\0a.jpg\0image/jpeg\0\0"[..], &fdel].concat(),
+        ));
+        iinf.extend(make_full_box(b"infe", 2, 0, b"\0\x03\0\0iovl\0"));
+        iinf.extend(make_full_box(b"infe", 3, 0, b"\0\x01\0\0\0\0av01\0"));
+
+        let data = heif_with_meta(&[
+            hdlr(b"pict"),
+            pitm(3),
+            make_full_box(b"iinf", 1, 0, &iinf),
+            iloc(),
+        ]);
+
+        let heif = HeifReader::with_options(&data, strict()).read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+
+        assert!(matches!(
+            meta.image(1).unwrap().item_type(),
+            ItemType::Mime {
+                content_type: "text/plain",
+                content_encoding: "gzip"
+            }
+        ));
+        assert!(matches!(
+            heif.item_info_by_item_id(2),
+            Some(ItemInfoEntry::Fixed {
+                item_name: "a.jpg",
+                extension: Some(ItemInfoExtension::FileDelivery {
+                    content_location: "http://example.com/a.jpg",
+                    content_md5: "",
+                    content_length: 9,
+                    transfer_length: 12,
+                    group_ids,
+                }),
+                ..
+            }) if **group_ids == [7]
+        ));
+        assert_eq!(
+            meta.image(3).unwrap().item_type().four_cc(),
+            FourCC(*b"iovl")
+        );
+        assert!(matches!(
+            meta.image(0x10000).unwrap().item_type(),
+            ItemType::Av01
+        ));
+    }
+
+    #[test]
+    fn test_protected_and_hidden_items() {
+        // 1: a hidden grid tile, 2: protected, 3: the grid, 4: its thumbnail, 5: hidden, 6: Exif
+        let infe = |item_id: u16, flags: u32, protection_index: u16, item_type: &[u8; 4]| {
+            let payload = [
+                &item_id.to_be_bytes()[..],
+                &protection_index.to_be_bytes(),
+                item_type,
+                b"\0",
+            ]
+            .concat();
+            make_full_box(b"infe", 2, flags, &payload)
+        };
+        let mut iinf = 6u16.to_be_bytes().to_vec();
+        iinf.extend(infe(1, 1, 0, b"hvc1"));
+        iinf.extend(infe(2, 0, 1, b"hvc1"));
+        iinf.extend(infe(3, 0, 0, b"grid"));
+        iinf.extend(infe(4, 0, 0, b"hvc1"));
+        iinf.extend(infe(5, 1, 0, b"hvc1"));
+        iinf.extend(infe(6, 0, 0, b"Exif"));
+
+        let iref = make_full_box(
+            b"iref",
+            0,
+            0,
+            &[
+                make_box(b"dimg", &u16s(&[3, 1, 1])),
+                make_box(b"thmb", &u16s(&[4, 1, 3])),
+            ]
+            .concat(),
+        );
+
+        let sinf = make_box(
+            b"sinf",
+            &[
+                make_box(b"frma", b"hvc1"),
+                make_full_box(b"schm", 0, 1, b"cenc\0\x01\0\0https://example.com\0"),
+                make_box(b"schi", &make_box(b"tenc", &[0; 4])),
+            ]
+            .concat(),
+        );
+        let ipro = make_full_box(b"ipro", 0, 0, &[&1u16.to_be_bytes()[..], &sinf].concat());
+
+        let data = heif_with_meta(&[
+            hdlr(b"pict"),
+            pitm(2),
+            make_full_box(b"iinf", 0, 0, &iinf),
+            iloc(),
+            iref,
+            ipro,
+        ]);
+
+        let heif = HeifReader::with_options(&data, strict()).read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+
+        let top_level = meta.top_level_images().map(|image| image.item_id());
+        assert_eq!(top_level.collect::<Vec<_>>(), [2, 3]);
+        assert!(meta.image(5).unwrap().is_hidden());
+
+        assert!(meta.image(2).unwrap().is_protected());
+        let sinf = meta.protection_scheme_of(2).unwrap();
+        let schm = sinf.scheme_type.as_ref().unwrap();
+        assert_eq!(sinf.original_format, FourCC(*b"hvc1"));
+        assert_eq!(
+            (schm.scheme_type, schm.scheme_version, schm.scheme_uri),
+            (FourCC(*b"cenc"), 0x10000, Some("https://example.com"))
+        );
+        assert_eq!(sinf.scheme_information.as_ref().unwrap().0.len(), 12);
+        assert!(meta.protection_scheme_of(3).is_none());
+
+        let err = crate::heic::HeicDecoder::default()
+            .decode_primary(&data, ParseOptions::default())
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                HeifError::ProtectedItem {
+                    item_id: 2,
+                    scheme: Some(FourCC(scheme)),
+                    ..
+                } if &scheme == b"cenc"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_pyramid_best_level() {
+        // 256x256 tiles: item 1 is 4x4 tiles, 2 is 2x2 and 3 is a single tile
+        let mut pymd = u32s(&[20, 3, 1, 2, 3]);
+        pymd.extend(
+            [256u16, 256, 1, 3, 3, 2, 1, 1, 4, 0, 0]
+                .iter()
+                .flat_map(|v| v.to_be_bytes()),
+        );
+        let grpl = make_box(b"grpl", &make_full_box(b"pymd", 0, 0, &pymd));
+
+        let data = heif_with_meta(&[
+            hdlr(b"pict"),
+            pitm(1),
+            iinf_with_items(&[1, 2, 3]),
+            iloc(),
+            grpl,
+        ]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let primary = heif.primary_image().unwrap();
+
+        let best = |w, h| primary.best_level_for(w, h).item_id();
+        assert_eq!(best(200, 100), 3);
+        assert_eq!(best(300, 256), 2);
+        assert_eq!(best(1024, 1024), 1);
+        assert_eq!(best(4000, 4000), 1);
+    }
+
+    // an av01 item with av1C holding `config_obus`
+    fn av1_file(config_obus: &[u8]) -> Vec<u8> {
+        let av1c = make_box(
+            b"av1C",
+            &[&[0x81, 0x00, 0x0C, 0x00][..], config_obus].concat(),
+        );
+        let ipco = make_box(b"ipco", &av1c);
+        let ipma = make_full_box(b"ipma", 0, 0, &[0, 0, 0, 1, 0, 1, 1, 0x81]);
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let infe = make_full_box(b"infe", 2, 0, b"\0\x01\0\0av01\0");
+        let iinf = make_full_box(b"iinf", 0, 0, &[&[0, 1][..], &infe].concat());
+
+        heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf, iloc(), iprp])
+    }
+
+    #[test]
+    fn test_av1_item() {
+        let data = av1_file(&[
+            0x0A, 0x09, 0x18, 0x3F, 0xC0, 0x0F, 0xC0, 0x0B, 0xDB, 0x00, 0x80,
+        ]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+        assert!(matches!(image.item_type(), ItemType::Av01));
+
+        let av1c = image.av1_configuration().unwrap();
+        assert_eq!(av1c.bit_depth(), 8);
+        assert!(av1c.chroma_subsampling_x && av1c.chroma_subsampling_y);
+
+        let sequence_header = av1c.sequence_header.as_ref().unwrap();
+        assert_eq!(sequence_header.max_frame_width(), 64);
+        assert_eq!(sequence_header.max_frame_height(), 48);
+    }
+
+    #[test]
+    fn test_broken_av1_sequence_header() {
+        // the OBU claims 9 bytes of sequence header, but has 1
+        let data = av1_file(&[0x0A, 0x09, 0x18]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let av1c = heif.primary_image().unwrap().av1_configuration().unwrap();
+        assert_eq!(av1c.bit_depth(), 8);
+        assert!(av1c.sequence_header.is_none());
+        assert!(matches!(
+            heif.warnings.as_ref(),
+            [HeifError::InvalidProperty { .. }]
+        ));
+
+        assert!(HeifReader::with_options(&data, strict()).read().is_err());
+    }
+
+    #[test]
+    fn test_layered_hevc_properties() {
+        let lhvc = make_box(b"lhvC", &[1, 0xF0, 0x00, 0xFC, 0xCF, 0]);
+
+        // one operating point outputting layer 0 of layers 0 and 1, 1 being an alpha layer
+        let mut oinf = vec![0x00, 0x08, 0x01, 0x01, 0x60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 90];
+        oinf.extend([0, 1, 0, 1, 0, 2, 0, 0x02, 0, 0x04]);
+        oinf.extend([0, 64, 0, 64, 0, 64, 0, 64, 0x42, 0, 0, 1]);
+        oinf.extend([2, 0, 0, 0, 1, 1, 0, 1]);
+        let oinf = make_full_box(b"oinf", 0, 0, &oinf);
+
+        let lsel = make_box(b"lsel", &[0, 1]);
+
+        let ipco = make_box(b"ipco", &[lhvc, oinf, lsel].concat());
+        let ipma = make_full_box(b"ipma", 0, 0, &[0, 0, 0, 1, 0, 1, 3, 0x81, 0x82, 0x83]);
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let infe = make_full_box(b"infe", 2, 0, b"\0\x01\0\0lhv1\0");
+        let iinf = make_full_box(b"iinf", 0, 0, &[&[0, 1][..], &infe].concat());
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf, iloc(), iprp]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+        assert!(matches!(image.item_type(), ItemType::Lhv1));
+
+        let [
+            ItemProperty::LHevcDecoderConfiguration(lhvc),
+            ItemProperty::OperatingPointsInformation(oinf),
+            ItemProperty::LayerSelector(lsel),
+        ] = *image.properties().collect::<Vec<_>>()
+        else {
+            panic!("expected lhvC, oinf and lsel");
+        };
+
+        assert_eq!(lhvc.length_size_minus_one(), 3);
+        assert_eq!(lhvc.num_temporal_layers(), 1);
+
+        assert_eq!(oinf.profile_tier_levels[0].level_idc, 90);
+        let operating_point = &oinf.operating_points[0];
+        assert_eq!(operating_point.output_layer_set_idx, 1);
+        assert_eq!(operating_point.output_layer_ids().collect::<Vec<_>>(), [0]);
+        assert_eq!(operating_point.frame_rate, Some((0, 1)));
+        assert_eq!(operating_point.bit_rate, None);
+        assert_eq!(*oinf.layers[1].direct_ref_layer_ids, [0]);
+        assert_eq!(*oinf.layers[1].dimension_identifiers, [1]);
+
+        assert_eq!(lsel.layer_id, 1);
+    }
+
+    #[test]
+    fn test_hdr_static_metadata() {
+        // a prefix SEI NAL unit with a content colour volume (min and max luminance only, with
+        // emulation prevention) and a content light level
+        let mut sei = vec![0x4E, 0x01, 149, 9, 0x18, 0, 0, 3, 0, 1, 0, 0, 3, 0, 2];
+        sei.extend([144, 4, 0, 1, 0, 2, 0x80]);
+        let mut hvcc = vec![1, 0x01, 0x60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 90];
+        hvcc.extend([0xF0, 0x00, 0xFC, 0xFD, 0xF8, 0xF8, 0, 0, 0x0F, 1, 39, 0, 1]);
+        hvcc.extend((sei.len() as u16).to_be_bytes());
+        hvcc.extend(sei);
+        let hvcc = make_box(b"hvcC", &hvcc);
+
+        let clli = make_box(b"clli", &[0x03, 0xE8, 0x01, 0x90]);
+        let mut mdcv = u16s(&[13250, 34500, 7500, 3000, 34000, 16000, 15635, 16450]);
+        mdcv.extend(u32s(&[10_000_000, 50]));
+        let mdcv = make_box(b"mdcv", &mdcv);
+        let amve = make_box(
+            b"amve",
+            &[&u32s(&[3_140_000])[..], &u16s(&[15635, 16450])].concat(),
+        );
+
+        let ipco = make_box(b"ipco", &[hvcc, clli, mdcv, amve].concat());
+        let ipma = make_full_box(
+            b"ipma",
+            0,
+            0,
+            &[0, 0, 0, 1, 0, 1, 4, 0x81, 0x82, 0x83, 0x84],
+        );
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc(), iprp]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+
+        // clli wins over the SEI message
+        let clli = image.content_light_level().unwrap();
+        assert_eq!(
+            (
+                clli.max_content_light_level,
+                clli.max_pic_average_light_level
+            ),
+            (1000, 400)
+        );
+
+        let mdcv = image.mastering_display_colour_volume().unwrap();
+        assert_eq!(mdcv.display_primaries[2], (34000, 16000));
+        assert_eq!(mdcv.white_point, (15635, 16450));
+        assert_eq!(mdcv.max_display_mastering_luminance, 10_000_000);
+
+        let amve = image.ambient_viewing_environment().unwrap();
+        assert_eq!(amve.ambient_illuminance, 3_140_000);
+
+        // only in the SEI message
+        let cclv = image.content_colour_volume().unwrap();
+        assert_eq!(cclv.primaries, None);
+        assert_eq!(
+            (cclv.min_luminance_value, cclv.max_luminance_value),
+            (Some(1), Some(2))
+        );
+        assert_eq!(cclv.avg_luminance_value, None);
+    }
+
+    #[test]
+    fn test_descriptive_properties() {
+        let udes_en = make_full_box(b"udes", 0, 0, b"en\0Pier\0Sunset at the pier\0sea,dusk\0");
+        let udes_fr = make_full_box(b"udes", 0, 0, b"fr\0Jet\xc3\xa9e\0\0\0");
+        // without the final terminator
+        let altt = make_full_box(b"altt", 0, 0, b"A pier at dusk\0en-GB");
+        let unix_epoch = 2_082_844_800_000_000u64;
+        let crtt = make_full_box(b"crtt", 0, 0, &unix_epoch.to_be_bytes());
+        let mdft = make_full_box(b"mdft", 0, 0, &(unix_epoch + 1_500_000).to_be_bytes());
+
+        let ipco = make_box(b"ipco", &[udes_en, udes_fr, altt, crtt, mdft].concat());
+        let ipma = make_full_box(
+            b"ipma",
+            0,
+            0,
+            &[0, 0, 0, 1, 0, 1, 5, 0x81, 0x82, 0x83, 0x84, 0x85],
+        );
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf(), iloc(), iprp]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+
+        let udes = image.user_descriptions().collect::<Vec<_>>();
+        assert_eq!(udes.len(), 2);
+        assert_eq!(
+            (&*udes[0].lang, &*udes[0].name, &*udes[0].description),
+            ("en", "Pier", "Sunset at the pier")
+        );
+        assert_eq!(&*udes[0].tags, "sea,dusk");
+        assert_eq!((&*udes[1].name, &*udes[1].tags), ("Jetée", ""));
+
+        let altt = image.accessibility_texts().next().unwrap();
+        assert_eq!(
+            (&*altt.alt_text, &*altt.alt_lang),
+            ("A pier at dusk", "en-GB")
+        );
+
+        assert_eq!(
+            image.creation_time().unwrap().system_time(),
+            Some(std::time::UNIX_EPOCH)
+        );
+        assert_eq!(
+            image.modification_time().unwrap().system_time(),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_millis(1500))
+        );
+    }
+
+    // a vvc1 item with vvcC holding `sps`
+    fn vvc_file(sps: &[u8]) -> Vec<u8> {
+        let mut vvcc = vec![0xFF, 0x00, 0x11, 0x5F, 0x01, 0x02, 0x33, 0x80, 0x00];
+        vvcc.extend([0, 64, 0, 48, 0, 0]);
+        vvcc.extend([1, 0x8F, 0, 1, 0, sps.len() as u8]);
+        vvcc.extend(sps);
+        let vvcc = make_full_box(b"vvcC", 0, 0, &vvcc);
+
+        let ipco = make_box(b"ipco", &vvcc);
+        let ipma = make_full_box(b"ipma", 0, 0, &[0, 0, 0, 1, 0, 1, 1, 0x81]);
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let infe = make_full_box(b"infe", 2, 0, b"\0\x01\0\0vvc1\0");
+        let iinf = make_full_box(b"iinf", 0, 0, &[&[0, 1][..], &infe].concat());
+
+        heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf, iloc(), iprp])
+    }
+
+    #[test]
+    fn test_vvc_item() {
+        // 64x48 with 4 rows cropped, 4:2:0, 10-bit Main 10
+        let data = vvc_file(&[
+            0x00, 0x79, 0x00, 0x0D, 0x02, 0x33, 0x80, 0x00, 0x00, 0x82, 0x0C, 0x7C, 0xA7,
+        ]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+        assert!(matches!(image.item_type(), ItemType::Vvc1));
+
+        let vvcc = image.vvc_configuration().unwrap();
+        assert_eq!(vvcc.length_size_minus_one, 3);
+        assert_eq!(vvcc.profile(), Some(crate::vvc::Profile::Main10));
+        assert_eq!(
+            vvcc.chroma_format(),
+            Some(crate::hevc::ChromaFormat::YUV420)
+        );
+        assert_eq!(vvcc.bit_depth(), Some(10));
+
+        let ptl = vvcc.ptl.as_ref().unwrap();
+        assert_eq!((ptl.max_picture_width, ptl.max_picture_height), (64, 48));
+        assert_eq!(ptl.native_ptl.general_level_idc, 0x33);
+        assert!(ptl.native_ptl.ptl_frame_only_constraint_flag);
+
+        let sps = vvcc.sequence_parameter_set.as_ref().unwrap();
+        assert_eq!(sps.ctb_size(), 128);
+        assert_eq!(sps.cropped_size(), (64, 40));
+    }
+
+    #[test]
+    fn test_broken_vvc_sps() {
+        let data = vvc_file(&[0x00, 0x79, 0x00]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let vvcc = heif.primary_image().unwrap().vvc_configuration().unwrap();
+        assert!(vvcc.sequence_parameter_set.is_none());
+        assert_eq!(*vvcc.arrays[0].nal_units[0].data, [0x00, 0x79, 0x00]);
+        assert!(matches!(
+            heif.warnings.as_ref(),
+            [HeifError::InvalidProperty { .. }]
+        ));
+
+        assert!(HeifReader::with_options(&data, strict()).read().is_err());
+    }
+
+    #[test]
+    fn test_gain_map_auxiliary_properties() {
+        let colr = make_box(
+            b"colr",
+            &[&b"nclx"[..], &[0, 12, 0, 13, 0, 6, 0x80]].concat(),
+        );
+        let auxc = make_full_box(b"auxC", 0, 0, b"urn:com:apple:photo:2020:aux:hdrgainmap\0");
+        let ipco = make_box(b"ipco", &[colr, auxc].concat());
+        let ipma = make_full_box(b"ipma", 0, 0, &[0, 0, 0, 1, 0, 1, 2, 0x81, 0x82]);
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let gain_map = make_full_box(b"infe", 2, 0, b"\0\x01\0\0hvc1\0");
+        let xmp = make_full_box(b"infe", 2, 0, b"\0\x02\0\0mimeXMP\0application/rdf+xml\0");
+        let iinf = make_full_box(b"iinf", 0, 0, &[&[0, 2][..], &gain_map, &xmp].concat());
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf, iloc(), iprp]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let meta = heif.meta_box.as_ref().unwrap();
+        let image = meta.image(1).unwrap();
+
+        let nclx = image.color_information().unwrap().nclx.unwrap();
+        assert_eq!(
+            (
+                nclx.colour_primaries,
+                nclx.transfer_characteristics,
+                nclx.matrix_coefficients
+            ),
+            (12, 13, 6)
+        );
+        assert!(nclx.full_range_flag);
+        assert_eq!(
+            &*image.auxiliary_type().unwrap().aux_type,
+            crate::heic::APPLE_HDR_GAIN_MAP
+        );
+
+        assert!(matches!(
+            meta.image(2).unwrap().item_type(),
+            ItemType::Mime {
+                content_type: "application/rdf+xml",
+                content_encoding: "",
+            }
+        ));
+    }
+
+    #[test]
+    fn test_unci_properties() {
+        // R, G, B and a user defined component
+        let cmpd = make_box(
+            b"cmpd",
+            &[&u32s(&[4])[..], &[0, 4, 0, 5, 0, 6, 0x80, 0], b"urn:x\0"].concat(),
+        );
+        // 3 x 10-bit components in 4-byte big-endian blocks, 2x1 tiles
+        let mut uncc = b"\0\0\0\0".to_vec();
+        uncc.extend(u32s(&[3]));
+        uncc.extend([0, 0, 9, 0, 0, 0, 1, 9, 0, 0, 0, 2, 9, 0, 0]);
+        uncc.extend([0, 1, 4, 0x40]);
+        uncc.extend(u32s(&[0, 0, 0, 1, 0]));
+        let uncc = make_full_box(b"uncC", 0, 0, &uncc);
+
+        // an unparsed property in between must not shift the ipma indices
+        let ipco = make_box(b"ipco", &[cmpd, make_box(b"zzzz", &[]), uncc].concat());
+        let ipma = make_full_box(b"ipma", 0, 0, &[0, 0, 0, 1, 0, 1, 2, 0x81, 0x83]);
+        let iprp = make_box(b"iprp", &[ipco, ipma].concat());
+
+        let infe = make_full_box(b"infe", 2, 0, b"\0\x01\0\0unci\0");
+        let iinf = make_full_box(b"iinf", 0, 0, &[&[0, 1][..], &infe].concat());
+
+        let data = heif_with_meta(&[hdlr(b"pict"), pitm(1), iinf, iloc(), iprp]);
+
+        let heif = HeifReader::new(&data).read().unwrap();
+        let image = heif.primary_image().unwrap();
+        assert!(matches!(image.item_type(), ItemType::Unci));
+
+        let [
+            ItemProperty::ComponentDefinition(cmpd),
+            ItemProperty::UncompressedFrameConfig(uncc),
+        ] = *image.properties().collect::<Vec<_>>()
+        else {
+            panic!("expected cmpd and uncC");
+        };
+
+        assert_eq!(cmpd.components[2].component_type, ComponentType::Blue);
+        assert_eq!(
+            cmpd.components[3].component_type,
+            ComponentType::Other(0x8000)
+        );
+        assert_eq!(cmpd.components[3].uri.as_deref(), Some("urn:x"));
+
+        assert_eq!(uncc.components.len(), 3);
+        assert_eq!(uncc.components[1].bit_depth, 10);
+        assert_eq!(uncc.interleave_type, InterleaveType::Pixel);
+        assert_eq!((uncc.block_size, uncc.block_pad_lsb), (4, true));
+        assert_eq!((uncc.num_tile_cols, uncc.num_tile_rows), (2, 1));
+    }
+}
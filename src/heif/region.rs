@@ -0,0 +1,364 @@
+use anyhow::{Result, bail, ensure};
+
+use crate::impl_read_for_datatype;
+
+/// The payload of an `rgan` region item, ISO/IEC 23008-12 6.10.1.
+///
+/// Coordinates are relative to a reference space of `reference_width` x `reference_height`,
+/// which maps onto the whole of each image the item describes through `cdsc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionItem {
+    pub reference_width: u32,
+    pub reference_height: u32,
+    pub regions: Box<[Region]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Region {
+    Point {
+        x: i32,
+        y: i32,
+    },
+    Rectangle {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    /// centred on (`x`, `y`)
+    Ellipse {
+        x: i32,
+        y: i32,
+        radius_x: u32,
+        radius_y: u32,
+    },
+    /// closed
+    Polygon(Box<[(i32, i32)]>),
+    /// open
+    Polyline(Box<[(i32, i32)]>),
+    /// a mask stored in the `mski` item `mask_item_id`, placed at (`x`, `y`) and scaled to
+    /// `width` x `height`
+    ReferencedMask {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        mask_item_id: u32,
+    },
+    /// one bit per pixel, most significant first, rows not padded
+    InlineMask {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        mask: Box<[u8]>,
+    },
+}
+
+impl RegionItem {
+    /// `mask_item_ids` are the item's `mask` references, taken in order by its referenced mask
+    /// regions.
+    pub fn parse(data: &[u8], mask_item_ids: &[u32]) -> Result<Self> {
+        let mut reader = RegionReader {
+            cursor: 0,
+            data,
+            field_size: 16,
+        };
+
+        let version = reader.read_u8()?;
+        ensure!(version == 0, "unsupported region item version {version}");
+        let flags = reader.read_u8()?;
+        reader.field_size = if (flags & 1) == 1 { 32 } else { 16 };
+
+        let reference_width = reader.read_field()?;
+        let reference_height = reader.read_field()?;
+        ensure!(
+            reference_width != 0 && reference_height != 0,
+            "empty {reference_width}x{reference_height} reference space"
+        );
+
+        let region_count = reader.read_u8()?;
+        let mut mask_item_ids = mask_item_ids.iter();
+        let mut regions = Vec::with_capacity(region_count as usize);
+
+        for _ in 0..region_count {
+            let geometry_type = reader.read_u8()?;
+
+            let region = match geometry_type {
+                0 => Region::Point {
+                    x: reader.read_signed_field()?,
+                    y: reader.read_signed_field()?,
+                },
+                1 => Region::Rectangle {
+                    x: reader.read_signed_field()?,
+                    y: reader.read_signed_field()?,
+                    width: reader.read_field()?,
+                    height: reader.read_field()?,
+                },
+                2 => Region::Ellipse {
+                    x: reader.read_signed_field()?,
+                    y: reader.read_signed_field()?,
+                    radius_x: reader.read_field()?,
+                    radius_y: reader.read_field()?,
+                },
+                3 => Region::Polygon(reader.read_points()?),
+                6 => Region::Polyline(reader.read_points()?),
+                4 => {
+                    let (x, y) = (reader.read_signed_field()?, reader.read_signed_field()?);
+                    let (width, height) = (reader.read_field()?, reader.read_field()?);
+                    let Some(&mask_item_id) = mask_item_ids.next() else {
+                        bail!("referenced mask region without a mask reference");
+                    };
+
+                    Region::ReferencedMask {
+                        x,
+                        y,
+                        width,
+                        height,
+                        mask_item_id,
+                    }
+                }
+                5 => {
+                    let (x, y) = (reader.read_signed_field()?, reader.read_signed_field()?);
+                    let (width, height) = (reader.read_field()?, reader.read_field()?);
+
+                    let mask_coding_method = reader.read_u8()?;
+                    ensure!(
+                        mask_coding_method == 0,
+                        "unsupported mask_coding_method {mask_coding_method}"
+                    );
+                    let len = (width as u64 * height as u64).div_ceil(8);
+                    ensure!(
+                        len <= reader.remaining() as u64,
+                        "{width}x{height} inline mask exceeds the item"
+                    );
+
+                    Region::InlineMask {
+                        x,
+                        y,
+                        width,
+                        height,
+                        mask: reader.read_slice(len as usize)?.into(),
+                    }
+                }
+                other => bail!("unknown geometry_type {other}"),
+            };
+
+            regions.push(region);
+        }
+
+        Ok(Self {
+            reference_width,
+            reference_height,
+            regions: regions.into_boxed_slice(),
+        })
+    }
+
+    /// The regions scaled from the reference space to an image of `image_width` x
+    /// `image_height`, rounded to whole pixels. Masks keep their bitmaps; only where they're
+    /// placed is scaled.
+    pub fn regions_in(&self, image_width: u32, image_height: u32) -> Box<[Region]> {
+        let scale_x = image_width as f64 / self.reference_width as f64;
+        let scale_y = image_height as f64 / self.reference_height as f64;
+
+        let x = |x: i32| (x as f64 * scale_x).round() as i32;
+        let y = |y: i32| (y as f64 * scale_y).round() as i32;
+        let width = |w: u32| (w as f64 * scale_x).round() as u32;
+        let height = |h: u32| (h as f64 * scale_y).round() as u32;
+        let points =
+            |points: &[(i32, i32)]| points.iter().map(|&(px, py)| (x(px), y(py))).collect();
+
+        self.regions
+            .iter()
+            .map(|region| match region {
+                Region::Point { x: px, y: py } => Region::Point {
+                    x: x(*px),
+                    y: y(*py),
+                },
+                Region::Rectangle {
+                    x: rx,
+                    y: ry,
+                    width: w,
+                    height: h,
+                } => Region::Rectangle {
+                    x: x(*rx),
+                    y: y(*ry),
+                    width: width(*w),
+                    height: height(*h),
+                },
+                Region::Ellipse {
+                    x: ex,
+                    y: ey,
+                    radius_x,
+                    radius_y,
+                } => Region::Ellipse {
+                    x: x(*ex),
+                    y: y(*ey),
+                    radius_x: width(*radius_x),
+                    radius_y: height(*radius_y),
+                },
+                Region::Polygon(vertices) => Region::Polygon(points(vertices)),
+                Region::Polyline(vertices) => Region::Polyline(points(vertices)),
+                Region::ReferencedMask {
+                    x: mx,
+                    y: my,
+                    width: w,
+                    height: h,
+                    mask_item_id,
+                } => Region::ReferencedMask {
+                    x: x(*mx),
+                    y: y(*my),
+                    width: width(*w),
+                    height: height(*h),
+                    mask_item_id: *mask_item_id,
+                },
+                Region::InlineMask {
+                    x: mx,
+                    y: my,
+                    width: w,
+                    height: h,
+                    mask,
+                } => Region::InlineMask {
+                    x: x(*mx),
+                    y: y(*my),
+                    width: width(*w),
+                    height: height(*h),
+                    mask: mask.clone(),
+                },
+            })
+            .collect()
+    }
+}
+
+struct RegionReader<'a> {
+    cursor: usize,
+    data: &'a [u8],
+    // in bits, from field_size_flag
+    field_size: u8,
+}
+
+impl RegionReader<'_> {
+    fn read_slice(&mut self, len: usize) -> Result<&[u8]> {
+        let Some(slice) = self.data.get(self.cursor..self.cursor + len) else {
+            bail!("region item is truncated");
+        };
+        self.cursor += len;
+
+        Ok(slice)
+    }
+
+    const fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.cursor)
+    }
+
+    fn read_field(&mut self) -> Result<u32> {
+        match self.field_size {
+            16 => Ok(self.read_u16()? as u32),
+            _ => self.read_u32(),
+        }
+    }
+
+    fn read_signed_field(&mut self) -> Result<i32> {
+        match self.field_size {
+            16 => Ok(self.read_u16()? as i16 as i32),
+            _ => Ok(self.read_u32()? as i32),
+        }
+    }
+
+    fn read_points(&mut self) -> Result<Box<[(i32, i32)]>> {
+        let point_count = self.read_field()?;
+        // each point takes two fields
+        ensure!(
+            point_count as u64 * self.field_size as u64 / 4 <= self.remaining() as u64,
+            "{point_count} points exceed the region item"
+        );
+
+        (0..point_count)
+            .map(|_| Ok((self.read_signed_field()?, self.read_signed_field()?)))
+            .collect()
+    }
+
+    impl_read_for_datatype!(read_u8, u8);
+    impl_read_for_datatype!(read_u16, u16);
+    impl_read_for_datatype!(read_u32, u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_item() {
+        // 16-bit fields in a 100x50 reference space
+        let mut data = vec![0, 0, 0, 100, 0, 50, 4];
+        // a point at (-2, 10)
+        data.extend([0, 0xFF, 0xFE, 0, 10]);
+        // a 20x10 rectangle at (10, 5)
+        data.extend([1, 0, 10, 0, 5, 0, 20, 0, 10]);
+        // a triangle
+        data.extend([3, 0, 3, 0, 0, 0, 0, 0, 50, 0, 0, 0, 0, 0, 50]);
+        // a referenced mask covering everything
+        data.extend([4, 0, 0, 0, 0, 0, 100, 0, 50]);
+
+        let item = RegionItem::parse(&data, &[7]).unwrap();
+        assert_eq!((item.reference_width, item.reference_height), (100, 50));
+        assert_eq!(item.regions[0], Region::Point { x: -2, y: 10 });
+        assert_eq!(
+            item.regions[2],
+            Region::Polygon([(0, 0), (50, 0), (0, 50)].into())
+        );
+
+        // onto a 200x200 image
+        let regions = item.regions_in(200, 200);
+        assert_eq!(regions[0], Region::Point { x: -4, y: 40 });
+        assert_eq!(
+            regions[1],
+            Region::Rectangle {
+                x: 20,
+                y: 20,
+                width: 40,
+                height: 40
+            }
+        );
+        assert_eq!(
+            regions[3],
+            Region::ReferencedMask {
+                x: 0,
+                y: 0,
+                width: 200,
+                height: 200,
+                mask_item_id: 7
+            }
+        );
+
+        // the referenced mask needs a mask reference
+        let err = RegionItem::parse(&data, &[]).unwrap_err();
+        assert!(
+            err.to_string().contains("without a mask reference"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_inline_mask() {
+        // 32-bit fields, one 3x3 inline mask with the centre set
+        let mut data = vec![0, 1, 0, 0, 0, 3, 0, 0, 0, 3, 1, 5];
+        data.extend([0; 8]);
+        data.extend([0, 0, 0, 3, 0, 0, 0, 3, 0]);
+        data.extend([0b0000_1000, 0b0000_0000]);
+
+        let item = RegionItem::parse(&data, &[]).unwrap();
+        assert_eq!(
+            item.regions[0],
+            Region::InlineMask {
+                x: 0,
+                y: 0,
+                width: 3,
+                height: 3,
+                mask: [0b0000_1000, 0].into()
+            }
+        );
+
+        assert!(RegionItem::parse(&data[..data.len() - 1], &[]).is_err());
+    }
+}
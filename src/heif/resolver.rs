@@ -0,0 +1,152 @@
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+/// Reads item data that a `dref` entry places outside of the HEIF file, given the entry's
+/// location: the `url ` location, or the `urn ` location, else its name.
+pub trait DataResolver: Debug {
+    fn read(&self, location: &str, offset: u64, length: u64) -> io::Result<Vec<u8>>;
+}
+
+/// Resolves locations as paths relative to the directory of the HEIF file.
+///
+/// Absolute paths and paths leaving that directory, also through symlinks, are refused, so a
+/// file can't read anything outside of its directory.
+#[derive(Debug, Clone)]
+pub struct FileDataResolver {
+    dir: PathBuf,
+}
+
+impl FileDataResolver {
+    /// For the HEIF file at `heif_path`.
+    pub fn new(heif_path: impl AsRef<Path>) -> Self {
+        Self {
+            dir: heif_path
+                .as_ref()
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf),
+        }
+    }
+
+    fn path_of(&self, location: &str) -> io::Result<PathBuf> {
+        // a relative file: url is just a path
+        let path = Path::new(location.strip_prefix("file:").unwrap_or(location));
+
+        let within_dir = !location.is_empty()
+            && !location.contains("://")
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !within_dir {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{location:?} isn't a path within {}", self.dir.display()),
+            ));
+        }
+
+        Ok(self.dir.join(path))
+    }
+
+    // with symlinks resolved, which may lead out of the directory as well
+    fn open(&self, location: &str) -> io::Result<File> {
+        let path = self.path_of(location)?.canonicalize()?;
+        let dir = if self.dir.as_os_str().is_empty() {
+            Path::new(".").canonicalize()?
+        } else {
+            self.dir.canonicalize()?
+        };
+
+        if !path.starts_with(&dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{location:?} leads out of {}", self.dir.display()),
+            ));
+        }
+
+        File::open(path)
+    }
+}
+
+impl DataResolver for FileDataResolver {
+    fn read(&self, location: &str, offset: u64, length: u64) -> io::Result<Vec<u8>> {
+        let mut file = self.open(location)?;
+
+        // don't trust length for the allocation before knowing the file has that much
+        let available = file.metadata()?.len().saturating_sub(offset);
+        if length > available {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("needed {length} bytes at {offset}, {available} available"),
+            ));
+        }
+
+        let mut data = vec![0; length as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_stay_in_the_directory() {
+        let resolver = FileDataResolver::new("/images/set/main.heic");
+
+        assert_eq!(
+            resolver.path_of("tiles/1.hvc").unwrap(),
+            Path::new("/images/set/tiles/1.hvc")
+        );
+        assert_eq!(
+            resolver.path_of("file:./2.hvc").unwrap(),
+            Path::new("/images/set/./2.hvc")
+        );
+
+        for location in [
+            "../secret",
+            "/etc/passwd",
+            "file:///etc/passwd",
+            "http://a/b",
+            "",
+        ] {
+            assert!(resolver.path_of(location).is_err(), "{location}");
+        }
+    }
+
+    #[test]
+    fn test_read_range() {
+        let dir = std::env::temp_dir().join(format!("heif-resolver-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tile.hvc"), b"0123456789").unwrap();
+
+        let resolver = FileDataResolver::new(dir.join("main.heic"));
+        assert_eq!(resolver.read("tile.hvc", 2, 3).unwrap(), b"234");
+        assert!(resolver.read("tile.hvc", 8, 3).is_err());
+        assert!(resolver.read("missing.hvc", 0, 1).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_stay_in_the_directory() {
+        let root = std::env::temp_dir().join(format!("heif-symlinks-{}", std::process::id()));
+        let dir = root.join("set");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(root.join("secret"), b"secret").unwrap();
+        std::fs::write(dir.join("tile.hvc"), b"tile").unwrap();
+        std::os::unix::fs::symlink(root.join("secret"), dir.join("escape.hvc")).unwrap();
+        std::os::unix::fs::symlink(dir.join("tile.hvc"), dir.join("alias.hvc")).unwrap();
+
+        let resolver = FileDataResolver::new(dir.join("main.heic"));
+        assert_eq!(resolver.read("alias.hvc", 0, 4).unwrap(), b"tile");
+        let err = resolver.read("escape.hvc", 0, 6).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
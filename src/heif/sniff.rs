@@ -0,0 +1,223 @@
+use crate::heic::HeicDecoder;
+use crate::heif::{FourCC, ItemType};
+
+/// What a file claims to be, going by its `ftyp` brands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// `heic`/`heix`: HEVC coded still images
+    HeicStill,
+    /// `hevc`/`hevx`: HEVC coded image sequence
+    HevcSequence,
+    /// `mif1` without a codec specific brand
+    GenericStill,
+    /// `msf1` without a codec specific brand
+    GenericSequence,
+    /// `avif`: AV1 coded still images
+    AvifStill,
+    /// `avis`: AV1 coded image sequence
+    AvifSequence,
+    /// an ISOBMFF file that isn't HEIF, e.g. mp4 or mov
+    IsoBmff,
+    /// not ISOBMFF at all
+    Unknown,
+}
+
+impl FileKind {
+    /// The item type the brand promises still images of, when it names a codec.
+    pub const fn coded_item_type(&self) -> Option<ItemType<'static>> {
+        match self {
+            Self::HeicStill => Some(ItemType::Hvc1),
+            Self::AvifStill => Some(ItemType::Av01),
+            _ => None,
+        }
+    }
+
+    pub const fn is_heif(&self) -> bool {
+        !matches!(self, Self::IsoBmff | Self::Unknown)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sniff {
+    pub kind: FileKind,
+    /// `None` when the file doesn't start with an `ftyp` box
+    pub major_brand: Option<FourCC>,
+    pub compatible_brands: Box<[FourCC]>,
+}
+
+impl Sniff {
+    /// Whether `decoder` can decode the images the brand promises. Only codec specific still
+    /// image brands promise anything, so other files report false even when their items decode.
+    pub fn is_decodable(&self, decoder: &HeicDecoder) -> bool {
+        self.kind
+            .coded_item_type()
+            .is_some_and(|item_type| decoder.can_decode(&item_type))
+    }
+}
+
+// codec specific brands. mif1/msf1 are only looked at once none of these matched, since almost
+// every HEIF file lists them alongside the codec brand.
+const BRANDS: [(&[u8; 4], FileKind); 6] = [
+    (b"heic", FileKind::HeicStill),
+    (b"heix", FileKind::HeicStill),
+    (b"avif", FileKind::AvifStill),
+    (b"hevc", FileKind::HevcSequence),
+    (b"hevx", FileKind::HevcSequence),
+    (b"avis", FileKind::AvifSequence),
+];
+
+const GENERIC_BRANDS: [(&[u8; 4], FileKind); 2] = [
+    (b"mif1", FileKind::GenericStill),
+    (b"msf1", FileKind::GenericSequence),
+];
+
+// boxes an ISOBMFF file may start with when it has no ftyp
+const LEADING_BOXES: [&[u8; 4]; 7] = [
+    b"moov", b"mdat", b"free", b"skip", b"wide", b"pdin", b"styp",
+];
+
+/// Classifies `data` from its leading `ftyp` box without parsing the rest of the file.
+///
+/// Only the first box is looked at, so a prefix of the file is enough. The major brand wins
+/// when it is one we know about; otherwise the compatible brands are searched.
+pub fn sniff(data: &[u8]) -> Sniff {
+    let unknown = Sniff {
+        kind: FileKind::Unknown,
+        major_brand: None,
+        compatible_brands: Box::default(),
+    };
+
+    let Some((kind, payload)) = first_box(data) else {
+        return unknown;
+    };
+
+    if kind != *b"ftyp" {
+        return Sniff {
+            kind: if LEADING_BOXES.contains(&&kind) {
+                FileKind::IsoBmff
+            } else {
+                FileKind::Unknown
+            },
+            ..unknown
+        };
+    }
+
+    // major_brand, minor_version, compatible_brands[]
+    let Some((major_brand, rest)) = payload.split_first_chunk::<4>() else {
+        return unknown;
+    };
+    let compatible_brands: Box<[FourCC]> = rest
+        .get(4..)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|brand| FourCC([brand[0], brand[1], brand[2], brand[3]]))
+        .collect();
+
+    let lookup = |table: &[(&[u8; 4], FileKind)]| {
+        let find = |brand: &[u8; 4]| {
+            table
+                .iter()
+                .find(|(known, _)| *known == brand)
+                .map(|(_, kind)| *kind)
+        };
+
+        find(major_brand).or_else(|| compatible_brands.iter().find_map(|brand| find(&brand.0)))
+    };
+
+    Sniff {
+        kind: lookup(&BRANDS)
+            .or_else(|| lookup(&GENERIC_BRANDS))
+            .unwrap_or(FileKind::IsoBmff),
+        major_brand: Some(FourCC(*major_brand)),
+        compatible_brands,
+    }
+}
+
+/// Kind and (possibly truncated) payload of the first box.
+fn first_box(data: &[u8]) -> Option<([u8; 4], &[u8])> {
+    let (size, rest) = data.split_first_chunk::<4>()?;
+    let (kind, rest) = rest.split_first_chunk::<4>()?;
+
+    let (header_size, size) = match u32::from_be_bytes(*size) {
+        // size 0 runs to the end of the file
+        0 => (8, data.len() as u64),
+        1 => (16, u64::from_be_bytes(*rest.first_chunk::<8>()?)),
+        size => (8, u64::from(size)),
+    };
+
+    if size < header_size {
+        return None;
+    }
+
+    let end = size.min(data.len() as u64) as usize;
+    let payload = data.get(header_size as usize..end).unwrap_or_default();
+
+    Some((*kind, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(major: &[u8; 4], compatible: &[&[u8; 4]]) -> Vec<u8> {
+        let size = 16 + 4 * compatible.len() as u32;
+
+        let mut data = size.to_be_bytes().to_vec();
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(major);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        for brand in compatible {
+            data.extend_from_slice(*brand);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_sniff_halfmoonbay() {
+        let data = std::fs::read("./halfmoonbay.heic").unwrap();
+
+        let sniff = sniff(&data);
+        assert_eq!(sniff.kind, FileKind::HeicStill);
+        // HEVC pictures aren't reconstructed yet
+        assert!(!sniff.is_decodable(&HeicDecoder::default()));
+    }
+
+    // major brand, compatible brands, expected kind
+    type Case = (&'static [u8; 4], &'static [&'static [u8; 4]], FileKind);
+
+    #[test]
+    fn test_sniff_brands() {
+        let cases: [Case; 7] = [
+            (b"heic", &[b"mif1", b"heic"], FileKind::HeicStill),
+            (b"mif1", &[b"mif1", b"heix"], FileKind::HeicStill),
+            (b"msf1", &[b"msf1", b"hevc"], FileKind::HevcSequence),
+            (b"mif1", &[b"mif1", b"miaf"], FileKind::GenericStill),
+            (b"avif", &[b"mif1", b"avif"], FileKind::AvifStill),
+            (b"avis", &[b"msf1", b"avif"], FileKind::AvifSequence),
+            (b"isom", &[b"isom", b"mp41"], FileKind::IsoBmff),
+        ];
+
+        for (major, compatible, expected) in cases {
+            let sniff = sniff(&ftyp(major, compatible));
+            assert_eq!(sniff.kind, expected, "{major:?} {compatible:?}");
+            assert_eq!(sniff.major_brand, Some(FourCC(*major)));
+            assert_eq!(sniff.compatible_brands.len(), compatible.len());
+        }
+    }
+
+    #[test]
+    fn test_sniff_non_heif() {
+        assert_eq!(
+            sniff(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR").kind,
+            FileKind::Unknown
+        );
+        assert_eq!(sniff(b"\0\0\0\x08moov").kind, FileKind::IsoBmff);
+        assert_eq!(sniff(b"").kind, FileKind::Unknown);
+
+        // truncated ftyp still reports the major brand
+        let sniff = sniff(&ftyp(b"heic", &[b"mif1"])[..12]);
+        assert_eq!(sniff.kind, FileKind::HeicStill);
+        assert!(sniff.compatible_brands.is_empty());
+    }
+}
@@ -220,6 +220,39 @@ impl HEVCDecoderConfigurationRecord {
     }
 }
 
+/// `lhvC`, ISO/IEC 14496-15 9.6.3: the parameter sets of the layers an `lhv1` item adds on top
+/// of its base layer
+#[derive(Debug)]
+pub struct LHEVCDecoderConfigurationRecord {
+    pub configuration_version: u8,
+    pub(crate) min_spatial_segmentation: u16,
+    pub(crate) parallelism_byte: u8,
+    pub(crate) temporal_byte: u8,
+    pub arrays: Box<[NalArray]>,
+}
+
+impl LHEVCDecoderConfigurationRecord {
+    pub const fn min_spatial_segmentation_idc(&self) -> u16 {
+        self.min_spatial_segmentation & 0x0FFF
+    }
+
+    pub const fn parallelism_type(&self) -> u8 {
+        self.parallelism_byte & 0x03
+    }
+
+    pub const fn num_temporal_layers(&self) -> u8 {
+        (self.temporal_byte >> 3) & 0x07
+    }
+
+    pub const fn temporal_id_nested(&self) -> bool {
+        (self.temporal_byte & 0x04) != 0
+    }
+
+    pub const fn length_size_minus_one(&self) -> u8 {
+        self.temporal_byte & 0x03
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NalUnitKind {
@@ -343,6 +376,8 @@ impl NalArray {
 
 #[derive(Debug)]
 pub struct RawNalUnit {
+    /// absolute file offset of `data`, for error reporting
+    pub offset: usize,
     pub data: Box<[u8]>,
 }
 
@@ -382,6 +417,88 @@ pub struct VideoParameterSet {
     // optional timing info
     pub vps_num_units_in_tick: Option<u32>,
     pub vps_time_scale: Option<u32>,
+    /// the nuh_layer_ids of each layer set; set 0 is the base layer alone
+    pub layer_sets: Box<[Box<[u8]>]>,
+    /// only parsed for multi-layer bitstreams
+    pub vps_extension: Option<VpsExtension>,
+}
+
+impl VideoParameterSet {
+    /// Output layer set `ols_idx`, from the extension; single layer bitstreams have none and
+    /// output their base layer.
+    pub fn output_layer_set(&self, ols_idx: usize) -> Option<&OutputLayerSet> {
+        self.vps_extension
+            .as_ref()
+            .and_then(|ext| ext.output_layer_sets.get(ols_idx))
+    }
+}
+
+/// vps_extension(), F.7.3.2.1.1, up to and including the output layer sets
+#[derive(Debug)]
+pub struct VpsExtension {
+    pub splitting_flag: bool,
+    /// scalability_mask_flag[i] in bit i
+    pub scalability_mask: u16,
+    /// in VPS order, the base layer first
+    pub layers: Box<[VpsLayer]>,
+    pub default_output_layer_idc: u8,
+    /// including the base layer's output layer set 0
+    pub output_layer_sets: Box<[OutputLayerSet]>,
+}
+
+impl VpsExtension {
+    pub fn layer(&self, nuh_layer_id: u8) -> Option<&VpsLayer> {
+        self.layers.iter().find(|l| l.nuh_layer_id == nuh_layer_id)
+    }
+}
+
+#[derive(Debug)]
+pub struct VpsLayer {
+    pub nuh_layer_id: u8,
+    /// ScalabilityId, indexed by scalability type (F.7.4.3.1.1 table F.1)
+    pub scalability_id: [u8; 16],
+    /// nuh_layer_ids of the layers this one directly predicts from
+    pub direct_ref_layers: Box<[u8]>,
+}
+
+impl VpsLayer {
+    pub const fn depth_layer_flag(&self) -> bool {
+        self.scalability_id[0] != 0
+    }
+
+    pub const fn view_order_idx(&self) -> u8 {
+        self.scalability_id[1]
+    }
+
+    pub const fn dependency_id(&self) -> u8 {
+        self.scalability_id[2]
+    }
+
+    /// non-zero for auxiliary layers, e.g. 1 for alpha and 2 for depth
+    pub const fn aux_id(&self) -> u8 {
+        self.scalability_id[3]
+    }
+}
+
+#[derive(Debug)]
+pub struct OutputLayerSet {
+    /// index into the VPS layer sets, which continue past `layer_sets` with the additional
+    /// layer sets of the extension
+    pub layer_set_idx: usize,
+    /// nuh_layer_ids, increasing
+    pub layer_ids: Box<[u8]>,
+    /// parallel to `layer_ids`
+    pub output_layer_flag: Box<[bool]>,
+    pub alt_output_layer_flag: bool,
+}
+
+impl OutputLayerSet {
+    pub fn output_layer_ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.layer_ids
+            .iter()
+            .zip(&self.output_layer_flag)
+            .filter_map(|(&id, &output)| output.then_some(id))
+    }
 }
 
 #[derive(Debug)]
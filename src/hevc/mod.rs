@@ -1,9 +1,11 @@
 pub mod grammar;
 mod parameter_set_reader;
 mod rbsp_reader;
+mod sei;
 mod slice;
 
 pub use grammar::*;
 pub use parameter_set_reader::*;
 pub use rbsp_reader::*;
+pub use sei::*;
 pub use slice::*;
@@ -1,8 +1,9 @@
 use crate::hevc::{
-    ChromaFormat, ColorPrimaries, MatrixCoefficients, PictureParameterSet, RbspReader,
-    SequenceParameterSet, TransferCharacteristics, VideoParameterSet,
+    ChromaFormat, ColorPrimaries, MatrixCoefficients, OutputLayerSet, PictureParameterSet,
+    RbspReader, SequenceParameterSet, TransferCharacteristics, VideoParameterSet, VpsExtension,
+    VpsLayer,
 };
-use anyhow::{Result, ensure};
+use anyhow::{Result, anyhow, ensure};
 
 pub fn video_parameter_set_rbsp(data: &[u8]) -> Result<VideoParameterSet> {
     let mut reader = RbspReader::new(data);
@@ -18,6 +19,82 @@ pub fn video_parameter_set_rbsp(data: &[u8]) -> Result<VideoParameterSet> {
     // skip profile_tier_level, because it's a lot of faff that heic don't need
     skip_profile_tier_level(&mut reader, true, vps_max_sub_layers_minus1)?;
 
+    let vps_sub_layer_ordering_info_present_flag = reader.read_flag()?;
+    let start_layer = if vps_sub_layer_ordering_info_present_flag {
+        0
+    } else {
+        vps_max_sub_layers_minus1
+    };
+
+    for _ in start_layer..=vps_max_sub_layers_minus1 {
+        let _vps_max_dec_pic_buffering_minus1 = reader.read_ue()?;
+        let _vps_max_num_reorder_pics = reader.read_ue()?;
+        let _vps_max_latency_increase_plus1 = reader.read_ue()?;
+    }
+
+    let vps_max_layer_id = reader.read_u8(6)?;
+    let vps_num_layer_sets_minus1 = reader.read_ue()?;
+    ensure!(
+        vps_num_layer_sets_minus1 <= 1023,
+        "invalid vps_num_layer_sets_minus1 {vps_num_layer_sets_minus1}"
+    );
+
+    let mut layer_sets = vec![Box::from([0])];
+    for _ in 1..=vps_num_layer_sets_minus1 {
+        let mut layer_ids = Vec::new();
+        for layer_id in 0..=vps_max_layer_id {
+            let layer_id_included_flag = reader.read_flag()?;
+            if layer_id_included_flag {
+                layer_ids.push(layer_id);
+            }
+        }
+        layer_sets.push(layer_ids.into_boxed_slice());
+    }
+
+    let vps_timing_info_present_flag = reader.read_flag()?;
+    let (vps_num_units_in_tick, vps_time_scale) = if vps_timing_info_present_flag {
+        let vps_num_units_in_tick = reader.read_u32(32)?;
+        let vps_time_scale = reader.read_u32(32)?;
+
+        let vps_poc_proportional_to_timing_flag = reader.read_flag()?;
+        if vps_poc_proportional_to_timing_flag {
+            let _vps_num_ticks_poc_diff_one_minus1 = reader.read_ue()?;
+        }
+
+        let vps_num_hrd_parameters = reader.read_ue()?;
+        ensure!(
+            vps_num_hrd_parameters <= vps_num_layer_sets_minus1 + 1,
+            "invalid vps_num_hrd_parameters {vps_num_hrd_parameters}"
+        );
+        for i in 0..vps_num_hrd_parameters {
+            let _hrd_layer_set_idx = reader.read_ue()?;
+            let cprms_present_flag = i == 0 || reader.read_flag()?;
+            skip_hrd_parameters(&mut reader, cprms_present_flag, vps_max_sub_layers_minus1)?;
+        }
+
+        (Some(vps_num_units_in_tick), Some(vps_time_scale))
+    } else {
+        (None, None)
+    };
+
+    // single layer streams have no use for whatever extension they carry
+    let vps_extension_flag = reader.read_flag()?;
+    let vps_extension = if vps_extension_flag && vps_max_layers_minus1 > 0 {
+        while !reader.is_byte_aligned() {
+            let _vps_extension_alignment_bit_equal_to_one = reader.read_flag()?;
+        }
+
+        Some(vps_extension(
+            &mut reader,
+            vps_base_layer_internal_flag,
+            vps_max_layers_minus1,
+            vps_max_sub_layers_minus1,
+            &layer_sets,
+        )?)
+    } else {
+        None
+    };
+
     Ok(VideoParameterSet {
         vps_video_parameter_set_id,
         vps_base_layer_internal_flag,
@@ -25,14 +102,306 @@ pub fn video_parameter_set_rbsp(data: &[u8]) -> Result<VideoParameterSet> {
         vps_max_layers_minus1,
         vps_max_sub_layers_minus1,
         vps_temporal_id_nesting_flag,
-        vps_max_layer_id: 0,
-        vps_num_layer_sets_minus1: 0,
-        vps_timing_info_present_flag: false,
-        vps_num_units_in_tick: None,
-        vps_time_scale: None,
+        vps_max_layer_id,
+        vps_num_layer_sets_minus1,
+        vps_timing_info_present_flag,
+        vps_num_units_in_tick,
+        vps_time_scale,
+        layer_sets: layer_sets.into_boxed_slice(),
+        vps_extension,
+    })
+}
+
+// F.7.3.2.1.1, as far as the output layer sets: which layers exist, what they depend on and
+// which of them each output layer set outputs. The derivations are those of F.7.4.3.1.1.
+fn vps_extension(
+    reader: &mut RbspReader,
+    vps_base_layer_internal_flag: bool,
+    vps_max_layers_minus1: u8,
+    vps_max_sub_layers_minus1: u8,
+    base_layer_sets: &[Box<[u8]>],
+) -> Result<VpsExtension> {
+    let max_layers = vps_max_layers_minus1 as usize + 1;
+
+    if vps_base_layer_internal_flag {
+        skip_profile_tier_level(reader, false, vps_max_sub_layers_minus1)?;
+    }
+
+    let splitting_flag = reader.read_flag()?;
+    let mut scalability_mask = 0u16;
+    for i in 0..16 {
+        let scalability_mask_flag = reader.read_flag()?;
+        if scalability_mask_flag {
+            scalability_mask |= 1 << i;
+        }
+    }
+    let num_scalability_types = scalability_mask.count_ones() as usize;
+
+    // in bits; with splitting_flag the last is whatever is left of the 6 bit nuh_layer_id
+    let mut dimension_id_len = vec![0; num_scalability_types];
+    for len in dimension_id_len
+        .iter_mut()
+        .take(num_scalability_types.saturating_sub(splitting_flag as usize))
+    {
+        *len = reader.read_u8(3)? + 1;
+    }
+    if splitting_flag && let Some((last, rest)) = dimension_id_len.split_last_mut() {
+        let offset = rest.iter().sum::<u8>();
+        ensure!(offset <= 5, "dimension ids exceed nuh_layer_id");
+        *last = 6 - offset;
+    }
+
+    let vps_nuh_layer_id_present_flag = reader.read_flag()?;
+    let mut layer_id_in_nuh = vec![0u8; max_layers];
+    let mut dimension_id = vec![vec![0u8; num_scalability_types]; max_layers];
+
+    for i in 1..max_layers {
+        layer_id_in_nuh[i] = if vps_nuh_layer_id_present_flag {
+            reader.read_u8(6)?
+        } else {
+            i as u8
+        };
+        ensure!(
+            layer_id_in_nuh[i] > layer_id_in_nuh[i - 1],
+            "layer_id_in_nuh is not increasing"
+        );
+
+        if splitting_flag {
+            let mut offset = 0;
+            for (j, &len) in dimension_id_len.iter().enumerate() {
+                dimension_id[i][j] = (layer_id_in_nuh[i] >> offset) & ((1 << len) - 1);
+                offset += len;
+            }
+        } else {
+            for (j, &len) in dimension_id_len.iter().enumerate() {
+                dimension_id[i][j] = reader.read_u8(len as usize)?;
+            }
+        }
+    }
+
+    let scalability_id = dimension_id
+        .iter()
+        .map(|ids| {
+            let mut scalability_id = [0u8; 16];
+            let types = (0..16).filter(|sm_idx| scalability_mask & (1 << sm_idx) != 0);
+            for (sm_idx, &id) in types.zip(ids) {
+                scalability_id[sm_idx] = id;
+            }
+            scalability_id
+        })
+        .collect::<Vec<_>>();
+
+    let mut view_order_idx = scalability_id.iter().map(|id| id[1]).collect::<Vec<_>>();
+    view_order_idx.sort_unstable();
+    view_order_idx.dedup();
+    let num_views = view_order_idx.len();
+
+    let view_id_len = reader.read_u8(4)?;
+    if view_id_len > 0 {
+        for _ in 0..num_views {
+            let _view_id_val = reader.read_bits(view_id_len as usize)?;
+        }
+    }
+
+    // direct_dependency_flag[i][j] and its transitive closure DependencyFlag, by VPS index
+    let mut direct_dependency_flag = vec![vec![false; max_layers]; max_layers];
+    let mut dependency_flag = vec![vec![false; max_layers]; max_layers];
+    for i in 1..max_layers {
+        for flag in &mut direct_dependency_flag[i][..i] {
+            *flag = reader.read_flag()?;
+        }
+        for j in 0..i {
+            dependency_flag[i][j] = direct_dependency_flag[i][j]
+                || (0..i).any(|k| direct_dependency_flag[i][k] && dependency_flag[k][j]);
+        }
+    }
+
+    // each independent layer heads a tree partition of the layers predicted from it
+    let mut tree_partitions: Vec<Vec<u8>> = Vec::new();
+    let mut in_partition = vec![false; max_layers];
+    for i in 0..max_layers {
+        if direct_dependency_flag[i].iter().any(|&flag| flag) {
+            continue;
+        }
+
+        let mut partition = vec![layer_id_in_nuh[i]];
+        for j in i + 1..max_layers {
+            if dependency_flag[j][i] && !in_partition[j] {
+                partition.push(layer_id_in_nuh[j]);
+                in_partition[j] = true;
+            }
+        }
+        tree_partitions.push(partition);
+    }
+
+    let num_add_layer_sets = if tree_partitions.len() > 1 {
+        reader.read_ue()?
+    } else {
+        0
+    };
+    ensure!(
+        num_add_layer_sets <= 1023,
+        "invalid num_add_layer_sets {num_add_layer_sets}"
+    );
+
+    let mut layer_sets = base_layer_sets.to_vec();
+    for _ in 0..num_add_layer_sets {
+        let mut layer_ids = Vec::new();
+        for partition in &tree_partitions[1..] {
+            let highest_layer_idx_plus1 = reader.read_bits(ceil_log2(partition.len() + 1))?;
+            ensure!(
+                highest_layer_idx_plus1 <= partition.len(),
+                "invalid highest_layer_idx_plus1 {highest_layer_idx_plus1}"
+            );
+            layer_ids.extend(&partition[..highest_layer_idx_plus1]);
+        }
+        layer_ids.sort_unstable();
+        layer_sets.push(layer_ids.into_boxed_slice());
+    }
+
+    let vps_sub_layers_max_minus1_present_flag = reader.read_flag()?;
+    if vps_sub_layers_max_minus1_present_flag {
+        for _ in 0..max_layers {
+            let _sub_layers_vps_max_minus1 = reader.read_u8(3)?;
+        }
+    }
+
+    let max_tid_ref_present_flag = reader.read_flag()?;
+    if max_tid_ref_present_flag {
+        // one per direct dependency
+        let direct_dependencies = direct_dependency_flag.iter().flatten().filter(|&&f| f);
+        for _ in 0..direct_dependencies.count() {
+            let _max_tid_il_ref_pics_plus1 = reader.read_u8(3)?;
+        }
+    }
+
+    let _default_ref_layers_active_flag = reader.read_flag()?;
+
+    let vps_num_profile_tier_level_minus1 = reader.read_ue()?;
+    ensure!(
+        vps_num_profile_tier_level_minus1 <= 63,
+        "invalid vps_num_profile_tier_level_minus1 {vps_num_profile_tier_level_minus1}"
+    );
+    let first_ptl = if vps_base_layer_internal_flag { 2 } else { 1 };
+    for _ in first_ptl..=vps_num_profile_tier_level_minus1 {
+        let vps_profile_present_flag = reader.read_flag()?;
+        skip_profile_tier_level(reader, vps_profile_present_flag, vps_max_sub_layers_minus1)?;
+    }
+
+    let num_layer_sets = layer_sets.len();
+    let (num_add_olss, default_output_layer_idc) = if num_layer_sets > 1 {
+        (reader.read_ue()?, reader.read_u8(2)?.min(2))
+    } else {
+        (0, 0)
+    };
+    ensure!(num_add_olss <= 1023, "invalid num_add_olss {num_add_olss}");
+
+    let layer_idx = |layer_id: u8| {
+        layer_id_in_nuh
+            .iter()
+            .position(|&id| id == layer_id)
+            .ok_or_else(|| anyhow!("layer set refers to unknown layer {layer_id}"))
+    };
+    let aux_id = |idx: usize| scalability_id[idx][3];
+
+    let mut output_layer_sets = vec![OutputLayerSet {
+        layer_set_idx: 0,
+        layer_ids: Box::from([0]),
+        output_layer_flag: Box::from([true]),
+        alt_output_layer_flag: false,
+    }];
+
+    for i in 1..num_layer_sets + num_add_olss as usize {
+        let layer_set_idx = if i < num_layer_sets {
+            i
+        } else if num_layer_sets > 2 {
+            reader.read_bits(ceil_log2(num_layer_sets - 1))? + 1
+        } else {
+            1
+        };
+        let layer_ids = layer_sets
+            .get(layer_set_idx)
+            .ok_or_else(|| anyhow!("invalid layer_set_idx_for_ols_minus1 {layer_set_idx}"))?
+            .clone();
+        let indices = layer_ids
+            .iter()
+            .map(|&id| layer_idx(id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let output_layer_flag: Box<[bool]> =
+            if i >= base_layer_sets.len() || default_output_layer_idc == 2 {
+                (0..layer_ids.len())
+                    .map(|_| reader.read_flag())
+                    .collect::<Result<_>>()?
+            } else if default_output_layer_idc == 0 {
+                vec![true; layer_ids.len()].into()
+            } else {
+                // only the highest primary (non-auxiliary) layer
+                let highest = indices.iter().rposition(|&idx| aux_id(idx) == 0);
+                (0..layer_ids.len()).map(|j| Some(j) == highest).collect()
+            };
+
+        // the output layers and everything they predict from
+        let necessary_layer_flag = (0..indices.len())
+            .map(|j| {
+                output_layer_flag[j]
+                    || (j + 1..indices.len())
+                        .any(|k| output_layer_flag[k] && dependency_flag[indices[k]][indices[j]])
+            })
+            .collect::<Vec<_>>();
+
+        if vps_num_profile_tier_level_minus1 > 0 {
+            for _ in necessary_layer_flag.iter().filter(|&&flag| flag) {
+                let _profile_tier_level_idx =
+                    reader.read_bits(ceil_log2(vps_num_profile_tier_level_minus1 as usize + 1))?;
+            }
+        }
+
+        let output_indices = indices
+            .iter()
+            .zip(&output_layer_flag)
+            .filter_map(|(&idx, &output)| output.then_some(idx))
+            .collect::<Vec<_>>();
+        let alt_output_layer_flag = match output_indices[..] {
+            [idx] if direct_dependency_flag[idx].iter().any(|&flag| flag) => reader.read_flag()?,
+            _ => false,
+        };
+
+        output_layer_sets.push(OutputLayerSet {
+            layer_set_idx,
+            layer_ids,
+            output_layer_flag,
+            alt_output_layer_flag,
+        });
+    }
+
+    // rep formats, DPB sizes and the rest aren't needed to pick a layer
+
+    let layers = (0..max_layers)
+        .map(|i| VpsLayer {
+            nuh_layer_id: layer_id_in_nuh[i],
+            scalability_id: scalability_id[i],
+            direct_ref_layers: (0..i)
+                .filter(|&j| direct_dependency_flag[i][j])
+                .map(|j| layer_id_in_nuh[j])
+                .collect(),
+        })
+        .collect();
+
+    Ok(VpsExtension {
+        splitting_flag,
+        scalability_mask,
+        layers,
+        default_output_layer_idc,
+        output_layer_sets: output_layer_sets.into_boxed_slice(),
     })
 }
 
+// Ceil(Log2(n)), the width of the u(v) fields indexing n things
+const fn ceil_log2(n: usize) -> usize {
+    (usize::BITS - n.saturating_sub(1).leading_zeros()) as usize
+}
+
 pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet> {
     let mut reader = RbspReader::new(data);
 
@@ -55,6 +424,13 @@ pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet>
     let pic_width_in_luma_samples = reader.read_ue()?;
     let pic_height_in_luma_samples = reader.read_ue()?;
 
+    // keeps the derived picture sizes in SequenceParameterSet from overflowing
+    ensure!(
+        (1..=u16::MAX as u32).contains(&pic_width_in_luma_samples)
+            && (1..=u16::MAX as u32).contains(&pic_height_in_luma_samples),
+        "invalid picture size {pic_width_in_luma_samples}x{pic_height_in_luma_samples}"
+    );
+
     let conformance_window_flag = reader.read_flag()?;
 
     let (conf_win_left_offset, conf_win_right_offset, conf_win_top_offset, conf_win_bottom_offset) =
@@ -71,6 +447,10 @@ pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet>
 
     let bit_depth_luma_minus8 = reader.read_ue()?;
     let bit_depth_chroma_minus8 = reader.read_ue()?;
+    ensure!(
+        bit_depth_luma_minus8 <= 8 && bit_depth_chroma_minus8 <= 8,
+        "bit depth out of range"
+    );
 
     let log2_max_pic_order_cnt_lsb_minus4 = reader.read_ue()?;
 
@@ -91,6 +471,19 @@ pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet>
     let log2_diff_max_min_luma_coding_block_size = reader.read_ue()?;
     let log2_min_luma_transform_block_size_minus2 = reader.read_ue()?;
     let log2_diff_max_min_luma_transform_block_size = reader.read_ue()?;
+
+    // 7.4.3.2.1: MinCbLog2SizeY >= 3 and 4 <= CtbLog2SizeY <= 6
+    ensure!(
+        log2_min_luma_coding_block_size_minus3 <= 3
+            && log2_diff_max_min_luma_coding_block_size <= 3
+            && (4..=6).contains(
+                &(log2_min_luma_coding_block_size_minus3
+                    + 3
+                    + log2_diff_max_min_luma_coding_block_size)
+            ),
+        "invalid coding block sizes"
+    );
+
     let max_transform_hierarchy_depth_inter = reader.read_ue()?;
     let max_transform_hierarchy_depth_intra = reader.read_ue()?;
 
@@ -145,7 +538,7 @@ pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet>
     let vui_parameters_present_flag = reader.read_flag()?;
     let (color_primaries, transfer_characteristics, matrix_coeffs) = if vui_parameters_present_flag
     {
-        parse_vui_parameters(&mut reader)?
+        parse_vui_parameters(&mut reader, sps_max_sub_layers_minus1)?
     } else {
         (None, None, None)
     };
@@ -251,6 +644,7 @@ fn skip_st_ref_pic_set(reader: &mut RbspReader) -> Result<()> {
 
 fn parse_vui_parameters(
     reader: &mut RbspReader,
+    sps_max_sub_layers_minus1: u8,
 ) -> Result<(
     Option<ColorPrimaries>,
     Option<TransferCharacteristics>,
@@ -315,8 +709,7 @@ fn parse_vui_parameters(
         }
         let vui_hrd_parameters_present_flag = reader.read_flag()?;
         if vui_hrd_parameters_present_flag {
-            // skip HRD parameters (very complex)
-            skip_hrd_parameters(reader)?;
+            skip_hrd_parameters(reader, true, sps_max_sub_layers_minus1)?;
         }
     }
 
@@ -335,14 +728,74 @@ fn parse_vui_parameters(
     Ok((color_primaries, transfer_characteristics, matrix_coeffs))
 }
 
-fn skip_hrd_parameters(reader: &mut RbspReader) -> Result<()> {
-    let nal_hrd_parameters_present_flag = reader.read_flag()?;
-    let vcl_hrd_parameters_present_flag = reader.read_flag()?;
+// E.2.2, shared by the VPS and the VUI
+fn skip_hrd_parameters(
+    reader: &mut RbspReader,
+    common_inf_present_flag: bool,
+    max_sub_layers_minus1: u8,
+) -> Result<()> {
+    let mut nal_hrd_parameters_present_flag = false;
+    let mut vcl_hrd_parameters_present_flag = false;
+    let mut sub_pic_hrd_params_present_flag = false;
+
+    if common_inf_present_flag {
+        nal_hrd_parameters_present_flag = reader.read_flag()?;
+        vcl_hrd_parameters_present_flag = reader.read_flag()?;
+
+        if nal_hrd_parameters_present_flag || vcl_hrd_parameters_present_flag {
+            sub_pic_hrd_params_present_flag = reader.read_flag()?;
+            if sub_pic_hrd_params_present_flag {
+                let _tick_divisor_minus2 = reader.read_u8(8)?;
+                let _du_cpb_removal_delay_increment_length_minus1 = reader.read_u8(5)?;
+                let _sub_pic_cpb_params_in_pic_timing_sei_flag = reader.read_flag()?;
+                let _dpb_output_delay_du_length_minus1 = reader.read_u8(5)?;
+            }
+
+            let _bit_rate_scale = reader.read_u8(4)?;
+            let _cpb_size_scale = reader.read_u8(4)?;
+            if sub_pic_hrd_params_present_flag {
+                let _cpb_size_du_scale = reader.read_u8(4)?;
+            }
+            let _initial_cpb_removal_delay_length_minus1 = reader.read_u8(5)?;
+            let _au_cpb_removal_delay_length_minus1 = reader.read_u8(5)?;
+            let _dpb_output_delay_length_minus1 = reader.read_u8(5)?;
+        }
+    }
+
+    for _ in 0..=max_sub_layers_minus1 {
+        let fixed_pic_rate_general_flag = reader.read_flag()?;
+        let fixed_pic_rate_within_cvs_flag = fixed_pic_rate_general_flag || reader.read_flag()?;
+
+        let low_delay_hrd_flag = if fixed_pic_rate_within_cvs_flag {
+            let _elemental_duration_in_tc_minus1 = reader.read_ue()?;
+            false
+        } else {
+            reader.read_flag()?
+        };
 
-    if nal_hrd_parameters_present_flag || vcl_hrd_parameters_present_flag {
-        let _sub_pic_hrd_params_present_flag = reader.read_flag()?;
-        // crazy stuff here, skip for now
-        // note: does heif files have detailed HRD params?
+        let cpb_cnt_minus1 = if low_delay_hrd_flag {
+            0
+        } else {
+            reader.read_ue()?
+        };
+        ensure!(
+            cpb_cnt_minus1 <= 31,
+            "invalid cpb_cnt_minus1 {cpb_cnt_minus1}"
+        );
+
+        let sub_layer_hrd_parameters_count =
+            nal_hrd_parameters_present_flag as u8 + vcl_hrd_parameters_present_flag as u8;
+        for _ in 0..sub_layer_hrd_parameters_count {
+            for _ in 0..=cpb_cnt_minus1 {
+                let _bit_rate_value_minus1 = reader.read_ue()?;
+                let _cpb_size_value_minus1 = reader.read_ue()?;
+                if sub_pic_hrd_params_present_flag {
+                    let _cpb_size_du_value_minus1 = reader.read_ue()?;
+                    let _bit_rate_du_value_minus1 = reader.read_ue()?;
+                }
+                let _cbr_flag = reader.read_flag()?;
+            }
+        }
     }
 
     Ok(())
@@ -549,3 +1002,36 @@ fn skip_profile_tier_level(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vps_extension_with_alpha_layer() {
+        // a base layer and an auxiliary (alpha) layer predicted from it, with layer set 1
+        // holding both and default_output_layer_idc 1
+        let vps = [
+            0x0C, 0x11, 0xFF, 0xFF, 0x01, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x5A, 0xF0, 0x56, 0xFF, 0x5A, 0x08, 0x00, 0x04, 0x28, 0x2B, 0xFF,
+        ];
+
+        let vps = video_parameter_set_rbsp(&vps).unwrap();
+        assert_eq!(vps.vps_max_layer_id, 1);
+        assert_eq!(*vps.layer_sets, [Box::from([0]), Box::from([0, 1])]);
+
+        let ext = vps.vps_extension.as_ref().unwrap();
+        assert_eq!(ext.scalability_mask, 1 << 3);
+        assert_eq!(ext.default_output_layer_idc, 1);
+
+        let alpha = ext.layer(1).unwrap();
+        assert_eq!(alpha.aux_id(), 1);
+        assert_eq!(*alpha.direct_ref_layers, [0]);
+
+        // the alpha layer is never the inferred output layer
+        let ols = vps.output_layer_set(1).unwrap();
+        assert_eq!(*ols.layer_ids, [0, 1]);
+        assert_eq!(ols.output_layer_ids().collect::<Vec<_>>(), [0]);
+        assert!(vps.output_layer_set(2).is_none());
+    }
+}
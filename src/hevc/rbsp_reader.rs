@@ -52,11 +52,11 @@ impl<'a> RbspReader<'a> {
 
     pub fn byte_alignment(&mut self) -> Result<()> {
         let one_bit = self.read_bit()?;
-        debug_assert_eq!(one_bit, 1);
+        ensure!(one_bit == 1, "expected alignment_bit_equal_to_one");
 
         while !self.is_byte_aligned() {
             let zero_bit = self.read_bit()?;
-            debug_assert_eq!(zero_bit, 0);
+            ensure!(zero_bit == 0, "expected alignment_bit_equal_to_zero");
         }
 
         Ok(())
@@ -88,6 +88,7 @@ impl<'a> RbspReader<'a> {
         let mut leading_zero_bits = 0;
         while !self.read_flag()? {
             leading_zero_bits += 1;
+            ensure!(leading_zero_bits < 32, "exp-golomb code exceeds 32 bits");
         }
 
         if leading_zero_bits == 0 {
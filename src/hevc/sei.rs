@@ -0,0 +1,99 @@
+use anyhow::{Result, bail, ensure};
+
+/// payloadType of an SEI message, H.265 7.3.5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeiPayloadType {
+    MasteringDisplayColourVolume,
+    ContentLightLevelInfo,
+    AmbientViewingEnvironment,
+    ContentColourVolume,
+    Other(u32),
+}
+
+impl From<u32> for SeiPayloadType {
+    fn from(value: u32) -> Self {
+        match value {
+            137 => Self::MasteringDisplayColourVolume,
+            144 => Self::ContentLightLevelInfo,
+            148 => Self::AmbientViewingEnvironment,
+            149 => Self::ContentColourVolume,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// One sei_message(), with its payload left unparsed.
+#[derive(Debug)]
+pub struct SeiMessage<'a> {
+    pub payload_type: SeiPayloadType,
+    pub payload: &'a [u8],
+}
+
+/// The messages of an sei_rbsp(), H.265 7.3.2.4, i.e. an SEI NAL unit without its header and
+/// with emulation prevention removed.
+pub fn sei_messages(rbsp: &[u8]) -> Result<Vec<SeiMessage<'_>>> {
+    let mut messages = Vec::new();
+    let mut rest = rbsp;
+
+    // more_rbsp_data(): anything before the rbsp_trailing_bits() byte
+    while rest.len() > 1 || rest.first().is_some_and(|&b| b != 0x80) {
+        let payload_type = read_ff_coded(&mut rest)?;
+        let payload_size = read_ff_coded(&mut rest)? as usize;
+        ensure!(
+            payload_size <= rest.len(),
+            "SEI payload of {payload_size} bytes, but only {} bytes are left",
+            rest.len()
+        );
+
+        let (payload, tail) = rest.split_at(payload_size);
+        messages.push(SeiMessage {
+            payload_type: SeiPayloadType::from(payload_type),
+            payload,
+        });
+        rest = tail;
+    }
+
+    Ok(messages)
+}
+
+// a run of 0xFF bytes, each adding 255, then the last byte
+fn read_ff_coded(data: &mut &[u8]) -> Result<u32> {
+    let mut value = 0u32;
+
+    loop {
+        let Some((&byte, rest)) = data.split_first() else {
+            bail!("truncated SEI message header");
+        };
+        *data = rest;
+        value = value.saturating_add(byte as u32);
+
+        if byte != 0xFF {
+            return Ok(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sei_messages() {
+        // content light level info, then a user data payload of 255 + 1 bytes
+        let mut rbsp = vec![144, 4, 0x03, 0xE8, 0x01, 0x90, 5, 0xFF, 1];
+        rbsp.extend([0; 256]);
+        rbsp.push(0x80);
+
+        let messages = sei_messages(&rbsp).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].payload_type,
+            SeiPayloadType::ContentLightLevelInfo
+        );
+        assert_eq!(messages[0].payload, [0x03, 0xE8, 0x01, 0x90]);
+        assert_eq!(messages[1].payload_type, SeiPayloadType::Other(5));
+        assert_eq!(messages[1].payload.len(), 256);
+
+        assert!(sei_messages(&[144, 4, 0x03, 0x80]).is_err());
+    }
+}
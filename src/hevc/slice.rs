@@ -5,7 +5,7 @@ use crate::{
         SliceKind, SliceSegmentHeader,
     },
 };
-use anyhow::Result;
+use anyhow::{Result, bail, ensure};
 
 pub struct SliceSegmentReader<'a> {
     cabac_decoder: CabacDecoder<'a>,
@@ -41,6 +41,14 @@ impl<'a> SliceSegmentReader<'a> {
         })
     }
 
+    pub const fn nal_header(&self) -> NalUnitHeader {
+        self.nal_header
+    }
+
+    pub const fn slice_header(&self) -> &SliceSegmentHeader {
+        &self.slice_header
+    }
+
     fn read_header(
         reader: &mut RbspReader,
         nal_header: NalUnitHeader,
@@ -57,7 +65,7 @@ impl<'a> SliceSegmentReader<'a> {
         };
 
         let slice_pic_parameter_set_id = reader.read_ue()?;
-        assert!(
+        ensure!(
             first_slice_segment_in_pic_flag,
             "first slice segement in pic flag should always be true"
         );
@@ -104,7 +112,7 @@ impl<'a> SliceSegmentReader<'a> {
             };
 
         if !matches!(slice_kind, SliceKind::I) {
-            unimplemented!("P/B slice headers not yet implemented");
+            bail!("P/B slice headers not yet implemented");
         }
 
         let slice_qp_delta = reader.read_se()?;
@@ -247,11 +255,11 @@ impl<'a> SliceSegmentReader<'a> {
     }
 
     fn sao(&self, _rx: u32, _ry: u32) -> Result<()> {
-        todo!()
+        bail!("todo: sao")
     }
 
     fn coding_quadtree(&self, _x0: u32, _y0: u32, _cqt_depth: usize) -> Result<()> {
-        todo!()
+        bail!("todo: coding_quadtree")
     }
 }
 
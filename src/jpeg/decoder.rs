@@ -0,0 +1,947 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+use anyhow::{Result, anyhow, bail, ensure};
+
+use crate::jpeg::{ComponentPlane, FrameComponent, FrameHeader, FrameKind, JpegImage};
+
+const SOF0: u8 = 0xC0;
+const SOF1: u8 = 0xC1;
+const DHT: u8 = 0xC4;
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DQT: u8 = 0xDB;
+const DNL: u8 = 0xDC;
+const DRI: u8 = 0xDD;
+const TEM: u8 = 0x01;
+
+const fn is_restart(marker: u8) -> bool {
+    matches!(marker, 0xD0..=0xD7)
+}
+
+// the SOFn we don't decode: progressive, lossless, hierarchical and arithmetic coded
+const fn is_unsupported_frame(marker: u8) -> bool {
+    matches!(marker, 0xC2 | 0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF)
+}
+
+/// Natural (row-major) index of the k-th coefficient in zig-zag order, T.81 figure A.6
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// Reads the frame header without decoding the image, e.g. to check its size first.
+pub fn read_frame_header(data: &[u8]) -> Result<FrameHeader> {
+    for segment in Segments::new(data) {
+        let segment = segment?;
+
+        match segment.marker {
+            SOF0 | SOF1 => return frame_header(segment.marker, segment.payload),
+            marker if is_unsupported_frame(marker) => bail!(unsupported_frame(marker)),
+            SOS => break,
+            _ => {}
+        }
+    }
+
+    bail!("no frame header before the first scan")
+}
+
+/// Bytes `decode_jpeg` allocates for the MCU aligned component buffers of `frame`, to check
+/// before decoding it.
+pub fn frame_buffer_size(frame: &FrameHeader) -> u64 {
+    let (mcus_x, mcus_y) = mcu_count(frame);
+
+    frame
+        .components
+        .iter()
+        .map(|c| {
+            let samples = (mcus_x * c.horizontal_sampling as usize * 8) as u64
+                * (mcus_y * c.vertical_sampling as usize * 8) as u64;
+            samples * size_of::<u16>() as u64
+        })
+        .sum()
+}
+
+/// Decodes a baseline or extended sequential, Huffman coded JPEG (ITU-T T.81).
+///
+/// Components are returned as coded, without upsampling or colour conversion.
+pub fn decode_jpeg(data: &[u8]) -> Result<JpegImage> {
+    let mut decoder = Decoder::default();
+
+    for segment in Segments::new(data) {
+        let segment = segment?;
+
+        match segment.marker {
+            SOF0 | SOF1 => {
+                ensure!(decoder.frame.is_none(), "more than one frame");
+                decoder.start_frame(frame_header(segment.marker, segment.payload)?);
+            }
+            marker if is_unsupported_frame(marker) => bail!(unsupported_frame(marker)),
+            DHT => decoder.huffman_tables(segment.payload)?,
+            DQT => decoder.quantization_tables(segment.payload)?,
+            DRI => {
+                let [hi, lo] = *segment.payload else {
+                    bail!("DRI has {} bytes", segment.payload.len());
+                };
+                decoder.restart_interval = u16::from_be_bytes([hi, lo]);
+            }
+            SOS => decoder.scan(segment.payload, segment.entropy_coded)?,
+            DNL => bail!("DNL is not supported"),
+            _ => {}
+        }
+    }
+
+    decoder.finish()
+}
+
+fn unsupported_frame(marker: u8) -> anyhow::Error {
+    let process = match marker {
+        0xC2 | 0xC6 | 0xCA | 0xCE => "progressive",
+        0xC3 | 0xC7 | 0xCB | 0xCF => "lossless",
+        _ => "arithmetic coded",
+    };
+
+    anyhow!("{process} JPEG (SOF{}) is not supported", marker - SOF0)
+}
+
+fn frame_header(marker: u8, payload: &[u8]) -> Result<FrameHeader> {
+    let [precision, h0, h1, w0, w1, count, rest @ ..] = payload else {
+        bail!("frame header is too short");
+    };
+
+    let kind = if marker == SOF0 {
+        FrameKind::Baseline
+    } else {
+        FrameKind::ExtendedSequential
+    };
+
+    ensure!(
+        *precision == 8 || (*precision == 12 && kind == FrameKind::ExtendedSequential),
+        "unsupported {precision}-bit {kind:?} JPEG"
+    );
+    ensure!(
+        (1..=4).contains(count) && rest.len() == *count as usize * 3,
+        "frame header has {count} components in {} bytes",
+        rest.len()
+    );
+
+    let components = rest
+        .chunks_exact(3)
+        .map(|c| {
+            let component = FrameComponent {
+                id: c[0],
+                horizontal_sampling: c[1] >> 4,
+                vertical_sampling: c[1] & 0x0F,
+                quantization_table: c[2],
+            };
+            ensure!(
+                (1..=4).contains(&component.horizontal_sampling)
+                    && (1..=4).contains(&component.vertical_sampling)
+                    && component.quantization_table < 4,
+                "invalid frame component {component:?}"
+            );
+
+            Ok(component)
+        })
+        .collect::<Result<Box<[_]>>>()?;
+
+    let frame = FrameHeader {
+        kind,
+        precision: *precision,
+        height: u16::from_be_bytes([*h0, *h1]),
+        width: u16::from_be_bytes([*w0, *w1]),
+        components,
+    };
+    // a height of 0 is only known at the DNL after the first scan
+    ensure!(
+        frame.width > 0 && frame.height > 0,
+        "{}x{} frame is not supported",
+        frame.width,
+        frame.height
+    );
+
+    Ok(frame)
+}
+
+/// A marker segment, with the entropy coded data that follows it for `SOS`.
+struct Segment<'a> {
+    marker: u8,
+    payload: &'a [u8],
+    entropy_coded: &'a [u8],
+}
+
+/// Splits a JPEG into marker segments; a missing `EOI` is tolerated.
+struct Segments<'a> {
+    data: &'a [u8],
+    position: usize,
+    done: bool,
+}
+
+impl<'a> Segments<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            done: false,
+        }
+    }
+
+    fn read_segment(&mut self) -> Result<Segment<'a>> {
+        let data = self.data;
+
+        ensure!(
+            data[self.position] == 0xFF,
+            "expected a marker at offset {}",
+            self.position
+        );
+        // any number of fill bytes may precede a marker
+        while data.get(self.position) == Some(&0xFF) {
+            self.position += 1;
+        }
+        let marker = *data
+            .get(self.position)
+            .ok_or_else(|| anyhow!("data ends in a marker"))?;
+        self.position += 1;
+
+        let payload = match marker {
+            SOI | EOI | TEM => &[][..],
+            _ if is_restart(marker) => &[][..],
+            _ => {
+                let length = data
+                    .get(self.position..self.position + 2)
+                    .map(|l| u16::from_be_bytes([l[0], l[1]]) as usize)
+                    .ok_or_else(|| anyhow!("marker {marker:#04x} is truncated"))?;
+                let payload = data
+                    .get(self.position + 2..self.position + length.max(2))
+                    .ok_or_else(|| anyhow!("marker {marker:#04x} is truncated"))?;
+                self.position += length.max(2);
+
+                payload
+            }
+        };
+
+        // the entropy coded data runs up to the next marker that isn't RSTn; 0xFF in the data
+        // itself is followed by a stuffed 0x00
+        let entropy_coded = if marker == SOS {
+            let start = self.position;
+            let end = (start..data.len())
+                .find(|&i| {
+                    data[i] == 0xFF
+                        && data
+                            .get(i + 1)
+                            .is_some_and(|&next| next != 0x00 && !is_restart(next))
+                })
+                .unwrap_or(data.len());
+            self.position = end;
+
+            &data[start..end]
+        } else {
+            &[]
+        };
+
+        if marker == EOI {
+            self.done = true;
+        }
+
+        Ok(Segment {
+            marker,
+            payload,
+            entropy_coded,
+        })
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Result<Segment<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.position >= self.data.len() {
+            return None;
+        }
+
+        let segment = self.read_segment();
+        self.done |= segment.is_err();
+
+        Some(segment)
+    }
+}
+
+#[derive(Default)]
+struct Decoder {
+    frame: Option<FrameHeader>,
+    /// in zig-zag order
+    quantization_tables: [Option<[u16; 64]>; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    /// in MCUs, 0 for none
+    restart_interval: u16,
+    /// one per frame component, padded to whole MCUs
+    buffers: Vec<ComponentBuffer>,
+}
+
+struct ComponentBuffer {
+    stride: usize,
+    samples: Vec<u16>,
+    decoded: bool,
+}
+
+impl Decoder {
+    fn start_frame(&mut self, frame: FrameHeader) {
+        let (mcus_x, mcus_y) = mcu_count(&frame);
+
+        self.buffers = frame
+            .components
+            .iter()
+            .map(|c| {
+                let stride = mcus_x * c.horizontal_sampling as usize * 8;
+                let rows = mcus_y * c.vertical_sampling as usize * 8;
+
+                ComponentBuffer {
+                    stride,
+                    samples: vec![0; stride * rows],
+                    decoded: false,
+                }
+            })
+            .collect();
+        self.frame = Some(frame);
+    }
+
+    fn huffman_tables(&mut self, mut payload: &[u8]) -> Result<()> {
+        while let [class_id, rest @ ..] = payload {
+            let (class, id) = (class_id >> 4, (class_id & 0x0F) as usize);
+            ensure!(
+                class <= 1 && id < 4,
+                "invalid huffman table {class_id:#04x}"
+            );
+
+            let (counts, rest) = rest
+                .split_first_chunk::<16>()
+                .ok_or_else(|| anyhow!("huffman table is truncated"))?;
+            let total = counts.iter().map(|&n| n as usize).sum::<usize>();
+            ensure!(rest.len() >= total, "huffman table is truncated");
+
+            let table = HuffmanTable::new(counts, &rest[..total])?;
+            if class == 0 {
+                self.dc_tables[id] = Some(table);
+            } else {
+                self.ac_tables[id] = Some(table);
+            }
+
+            payload = &rest[total..];
+        }
+
+        Ok(())
+    }
+
+    fn quantization_tables(&mut self, mut payload: &[u8]) -> Result<()> {
+        while let [precision_id, rest @ ..] = payload {
+            let (precision, id) = (precision_id >> 4, (precision_id & 0x0F) as usize);
+            ensure!(
+                precision <= 1 && id < 4,
+                "invalid quantization table {precision_id:#04x}"
+            );
+
+            let size = 64 << precision;
+            ensure!(rest.len() >= size, "quantization table is truncated");
+
+            let mut table = [0; 64];
+            for (k, q) in table.iter_mut().enumerate() {
+                *q = match precision {
+                    0 => rest[k] as u16,
+                    _ => u16::from_be_bytes([rest[2 * k], rest[2 * k + 1]]),
+                };
+            }
+
+            self.quantization_tables[id] = Some(table);
+            payload = &rest[size..];
+        }
+
+        Ok(())
+    }
+
+    fn scan(&mut self, header: &[u8], entropy_coded: &[u8]) -> Result<()> {
+        let frame = self
+            .frame
+            .as_ref()
+            .ok_or_else(|| anyhow!("scan before the frame header"))?;
+
+        let [count, rest @ ..] = header else {
+            bail!("scan header is empty");
+        };
+        let count = *count as usize;
+        ensure!(
+            (1..=4).contains(&count) && rest.len() == count * 2 + 3,
+            "scan header has {count} components in {} bytes",
+            rest.len()
+        );
+
+        let (selectors, spectral) = rest.split_at(count * 2);
+        ensure!(
+            *spectral == [0, 63, 0],
+            "scan is not sequential: Ss, Se, AhAl = {spectral:?}"
+        );
+
+        let mut components = Vec::with_capacity(count);
+        for selector in selectors.chunks_exact(2) {
+            let index = frame
+                .components
+                .iter()
+                .position(|c| c.id == selector[0])
+                .ok_or_else(|| anyhow!("scan references unknown component {}", selector[0]))?;
+            ensure!(
+                !self.buffers[index].decoded,
+                "component {} is in more than one scan",
+                selector[0]
+            );
+
+            let component = &frame.components[index];
+            components.push(ScanComponent {
+                index,
+                component: *component,
+                dc: huffman_table(&self.dc_tables, selector[1] >> 4, "DC")?,
+                ac: huffman_table(&self.ac_tables, selector[1] & 0x0F, "AC")?,
+                quantization: self.quantization_tables[component.quantization_table as usize]
+                    .as_ref()
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "missing quantization table {}",
+                            component.quantization_table
+                        )
+                    })?,
+                predictor: 0,
+            });
+        }
+
+        // a single component scan is non-interleaved: one block per MCU, covering just the
+        // component's own samples (A.2.2)
+        let (mcus_x, mcus_y) = match components.as_slice() {
+            [single] => {
+                let (width, height) = frame.component_size(&single.component);
+                (width.div_ceil(8) as usize, height.div_ceil(8) as usize)
+            }
+            _ => {
+                let blocks_per_mcu = components
+                    .iter()
+                    .map(|c| c.component.horizontal_sampling * c.component.vertical_sampling)
+                    .sum::<u8>();
+                ensure!(
+                    blocks_per_mcu <= 10,
+                    "{blocks_per_mcu} blocks per MCU exceeds 10"
+                );
+
+                mcu_count(frame)
+            }
+        };
+
+        let interleaved = components.len() > 1;
+        let level_shift = 1 << (frame.precision - 1);
+        let max_sample = (1 << frame.precision) - 1;
+        let idct = IdctTable::new();
+        let restart_interval = self.restart_interval as usize;
+
+        let mut reader = EntropyReader::new(entropy_coded);
+        let mut coefficients = [0i32; 64];
+
+        for mcu in 0..mcus_x * mcus_y {
+            if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+                reader.restart()?;
+                for c in &mut components {
+                    c.predictor = 0;
+                }
+            }
+
+            let (mcu_x, mcu_y) = (mcu % mcus_x, mcu / mcus_x);
+
+            for c in &mut components {
+                let (h, v) = if interleaved {
+                    (
+                        c.component.horizontal_sampling as usize,
+                        c.component.vertical_sampling as usize,
+                    )
+                } else {
+                    (1, 1)
+                };
+                let buffer = &mut self.buffers[c.index];
+
+                for block in 0..h * v {
+                    c.decode_block(&mut reader, &mut coefficients)?;
+
+                    let x = (mcu_x * h + block % h) * 8;
+                    let y = (mcu_y * v + block / h) * 8;
+                    let out = &mut buffer.samples[y * buffer.stride + x..];
+                    idct.inverse(&coefficients, out, buffer.stride, level_shift, max_sample);
+                }
+            }
+        }
+
+        for c in &components {
+            self.buffers[c.index].decoded = true;
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<JpegImage> {
+        let frame = self.frame.ok_or_else(|| anyhow!("no frame header"))?;
+
+        let planes = frame
+            .components
+            .iter()
+            .zip(self.buffers)
+            .map(|(component, buffer)| {
+                ensure!(buffer.decoded, "component {} has no scan", component.id);
+
+                let (width, height) = frame.component_size(component);
+                let samples = buffer
+                    .samples
+                    .chunks_exact(buffer.stride)
+                    .take(height as usize)
+                    .flat_map(|row| &row[..width as usize])
+                    .copied()
+                    .collect();
+
+                Ok(ComponentPlane {
+                    id: component.id,
+                    width,
+                    height,
+                    samples,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(JpegImage { frame, planes })
+    }
+}
+
+fn huffman_table<'t>(
+    tables: &'t [Option<HuffmanTable>; 4],
+    id: u8,
+    kind: &str,
+) -> Result<&'t HuffmanTable> {
+    tables
+        .get(id as usize)
+        .and_then(Option::as_ref)
+        .ok_or_else(|| anyhow!("missing {kind} huffman table {id}"))
+}
+
+// MCUs per row and column of an interleaved scan
+fn mcu_count(frame: &FrameHeader) -> (usize, usize) {
+    let mcu_width = frame.max_horizontal_sampling() as usize * 8;
+    let mcu_height = frame.max_vertical_sampling() as usize * 8;
+
+    (
+        (frame.width as usize).div_ceil(mcu_width),
+        (frame.height as usize).div_ceil(mcu_height),
+    )
+}
+
+struct ScanComponent<'d> {
+    /// into the frame components
+    index: usize,
+    component: FrameComponent,
+    dc: &'d HuffmanTable,
+    ac: &'d HuffmanTable,
+    quantization: &'d [u16; 64],
+    predictor: i32,
+}
+
+impl ScanComponent<'_> {
+    // F.2.2: the dequantized coefficients of one block, in natural order
+    fn decode_block(
+        &mut self,
+        reader: &mut EntropyReader,
+        coefficients: &mut [i32; 64],
+    ) -> Result<()> {
+        coefficients.fill(0);
+
+        let size = self.dc.decode(reader)?;
+        ensure!(size <= 16, "DC difference of {size} bits");
+        self.predictor = self
+            .predictor
+            .wrapping_add(extend(reader.read_bits(size)?, size));
+        coefficients[0] = self.predictor.saturating_mul(self.quantization[0] as i32);
+
+        let mut k = 1;
+        while k < 64 {
+            let run_size = self.ac.decode(reader)?;
+            let (run, size) = ((run_size >> 4) as usize, run_size & 0x0F);
+
+            if size == 0 {
+                if run == 15 {
+                    // ZRL, sixteen zeros
+                    k += 16;
+                    continue;
+                }
+                // EOB
+                break;
+            }
+
+            k += run;
+            ensure!(k < 64, "coefficient run past the end of the block");
+
+            coefficients[ZIGZAG[k]] =
+                extend(reader.read_bits(size)?, size).saturating_mul(self.quantization[k] as i32);
+            k += 1;
+        }
+
+        Ok(())
+    }
+}
+
+// F.2.2.1: the `size` bit value read as a signed difference
+const fn extend(value: u32, size: u8) -> i32 {
+    if size == 0 {
+        0
+    } else if value < 1 << (size - 1) {
+        value as i32 - (1 << size) + 1
+    } else {
+        value as i32
+    }
+}
+
+/// Canonical Huffman codes, decoded as in T.81 F.2.2.3.
+struct HuffmanTable {
+    /// largest code of each length, -1 for none
+    max_code: [i32; 17],
+    /// index into `values` minus the first code of each length
+    offsets: [i32; 17],
+    values: Box<[u8]>,
+}
+
+impl HuffmanTable {
+    fn new(counts: &[u8; 16], values: &[u8]) -> Result<Self> {
+        let mut max_code = [-1; 17];
+        let mut offsets = [0; 17];
+        let (mut code, mut index) = (0i32, 0i32);
+
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+
+            offsets[length] = index - code;
+            code += count;
+            index += count;
+            if count > 0 {
+                max_code[length] = code - 1;
+            }
+
+            ensure!(code <= 1 << length, "huffman table has too many codes");
+            code <<= 1;
+        }
+
+        Ok(Self {
+            max_code,
+            offsets,
+            values: values.into(),
+        })
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> Result<u8> {
+        let mut code = 0;
+
+        for length in 1..=16 {
+            code = (code << 1) | reader.read_bits(1)? as i32;
+
+            if code <= self.max_code[length] {
+                return Ok(self.values[(code + self.offsets[length]) as usize]);
+            }
+        }
+
+        bail!("invalid huffman code")
+    }
+}
+
+/// Reads bits from entropy coded data, dropping stuffed zero bytes.
+///
+/// Past a marker or the end of the data it reads zeros, like libjpeg does for truncated files.
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// left aligned
+    bits: u64,
+    count: u32,
+}
+
+impl<'a> EntropyReader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            let byte = match self.data.get(self.position) {
+                Some(0xFF) => match self.data.get(self.position + 1) {
+                    Some(0x00) => {
+                        self.position += 2;
+                        0xFF
+                    }
+                    // a marker
+                    _ => 0,
+                },
+                Some(&byte) => {
+                    self.position += 1;
+                    byte
+                }
+                None => 0,
+            };
+
+            self.bits |= (byte as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn read_bits(&mut self, n: u8) -> Result<u32> {
+        if n == 0 {
+            return Ok(0);
+        }
+        ensure!(n <= 16, "can't read {n} bits at once");
+
+        if self.count < n as u32 {
+            self.fill();
+        }
+
+        let value = (self.bits >> (64 - n)) as u32;
+        self.bits <<= n;
+        self.count -= n as u32;
+
+        Ok(value)
+    }
+
+    // drops what is left of the current interval and skips the RSTn marker ending it
+    fn restart(&mut self) -> Result<()> {
+        let marker = (self.position..self.data.len().saturating_sub(1))
+            .find(|&i| self.data[i] == 0xFF && is_restart(self.data[i + 1]))
+            .ok_or_else(|| anyhow!("missing restart marker"))?;
+
+        self.position = marker + 2;
+        self.bits = 0;
+        self.count = 0;
+
+        Ok(())
+    }
+}
+
+/// cos((2x + 1)uπ/16) scaled by C(u)/2, A.3.3
+struct IdctTable([[f32; 8]; 8]);
+
+impl IdctTable {
+    fn new() -> Self {
+        let mut table = [[0.0; 8]; 8];
+
+        for (x, row) in table.iter_mut().enumerate() {
+            for (u, value) in row.iter_mut().enumerate() {
+                let c = if u == 0 { FRAC_1_SQRT_2 } else { 1.0 };
+                *value = c / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+            }
+        }
+
+        Self(table)
+    }
+
+    // writes the 8x8 block of samples to the top left of `out`
+    fn inverse(
+        &self,
+        coefficients: &[i32; 64],
+        out: &mut [u16],
+        stride: usize,
+        level_shift: i32,
+        max_sample: i32,
+    ) {
+        let table = &self.0;
+        let mut rows = [0f32; 64];
+
+        for v in 0..8 {
+            for x in 0..8 {
+                rows[v * 8 + x] = (0..8)
+                    .map(|u| table[x][u] * coefficients[v * 8 + u] as f32)
+                    .sum();
+            }
+        }
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let sample = (0..8).map(|v| table[y][v] * rows[v * 8 + x]).sum::<f32>();
+                out[y * stride + x] =
+                    (sample.round() as i32 + level_shift).clamp(0, max_sample) as u16;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MSB first
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: u32,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, n: u32) {
+            for i in (0..n).rev() {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if value >> i & 1 != 0 {
+                    *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+                }
+                self.bits += 1;
+            }
+        }
+
+        // pads with 1s and stuffs 0xFF bytes
+        fn finish(mut self) -> Vec<u8> {
+            let padding = (8 - self.bits % 8) % 8;
+            self.write(u32::MAX, padding);
+
+            self.bytes
+                .into_iter()
+                .flat_map(|b| if b == 0xFF { vec![0xFF, 0x00] } else { vec![b] })
+                .collect()
+        }
+    }
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        [
+            &[0xFF, marker][..],
+            &(payload.len() as u16 + 2).to_be_bytes(),
+            payload,
+        ]
+        .concat()
+    }
+
+    // DC table 0: categories 0..=11 as 4-bit codes; AC table 0: just EOB, as a single 0 bit
+    fn tables() -> Vec<u8> {
+        let mut dc = vec![0x00];
+        dc.extend([0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        dc.extend(0..12);
+        let mut ac = vec![0x10];
+        ac.extend([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        ac.push(0x00);
+
+        [
+            segment(DQT, &[&[0][..], &[1; 64]].concat()),
+            segment(DHT, &[dc, ac].concat()),
+        ]
+        .concat()
+    }
+
+    // a block of `value` everywhere: only its DC coefficient, 8 * (value - 128)
+    fn flat_block(writer: &mut BitWriter, predictor: &mut i32, value: i32) {
+        let dc = 8 * (value - 128);
+        let diff = dc - *predictor;
+        *predictor = dc;
+
+        let size = 32 - diff.unsigned_abs().leading_zeros();
+        let bits = if diff < 0 { diff - 1 } else { diff } as u32 & ((1 << size) - 1);
+
+        writer.write(size, 4);
+        writer.write(bits, size);
+        writer.write(0, 1); // EOB
+    }
+
+    fn sof(width: u16, height: u16, components: &[(u8, u8)]) -> Vec<u8> {
+        let mut payload = vec![8];
+        payload.extend(height.to_be_bytes());
+        payload.extend(width.to_be_bytes());
+        payload.push(components.len() as u8);
+        for &(id, sampling) in components {
+            payload.extend([id, sampling, 0]);
+        }
+        segment(SOF0, &payload)
+    }
+
+    #[test]
+    fn test_decode_420_interleaved() {
+        // one 16x16 MCU: four luma blocks, one Cb and one Cr
+        let mut writer = BitWriter::default();
+        let mut predictors = [0; 3];
+        for value in [16, 32, 64, 255] {
+            flat_block(&mut writer, &mut predictors[0], value);
+        }
+        flat_block(&mut writer, &mut predictors[1], 100);
+        flat_block(&mut writer, &mut predictors[2], 200);
+
+        let data = [
+            vec![0xFF, SOI],
+            tables(),
+            sof(16, 16, &[(1, 0x22), (2, 0x11), (3, 0x11)]),
+            segment(SOS, &[3, 1, 0x00, 2, 0x00, 3, 0x00, 0, 63, 0]),
+            writer.finish(),
+            vec![0xFF, EOI],
+        ]
+        .concat();
+
+        let image = decode_jpeg(&data).unwrap();
+        assert_eq!(image.frame.kind, FrameKind::Baseline);
+
+        let [y, cb, cr] = &*image.planes else {
+            panic!("expected 3 planes");
+        };
+        assert_eq!((y.width, y.height, cb.width, cb.height), (16, 16, 8, 8));
+        assert_eq!(
+            [
+                y.samples[0],
+                y.samples[8],
+                y.samples[8 * 16],
+                y.samples[255]
+            ],
+            [16, 32, 64, 255]
+        );
+        assert!(cb.samples.iter().all(|&s| s == 100));
+        assert!(cr.samples.iter().all(|&s| s == 200));
+    }
+
+    #[test]
+    fn test_restart_intervals_and_cropping() {
+        // 12x4 grayscale: two blocks, each in its own restart interval
+        let mut data = [
+            vec![0xFF, SOI],
+            tables(),
+            sof(12, 4, &[(1, 0x11)]),
+            segment(DRI, &[0, 1]),
+            segment(SOS, &[1, 1, 0x00, 0, 63, 0]),
+        ]
+        .concat();
+
+        for (i, value) in [50, 150].into_iter().enumerate() {
+            let mut writer = BitWriter::default();
+            flat_block(&mut writer, &mut 0, value);
+            data.extend(writer.finish());
+            if i == 0 {
+                data.extend([0xFF, 0xD0]);
+            }
+        }
+
+        let header = read_frame_header(&data).unwrap();
+        assert_eq!((header.width, header.height), (12, 4));
+
+        let image = decode_jpeg(&data).unwrap();
+        let plane = &image.planes[0];
+        assert_eq!(plane.samples.len(), 12 * 4);
+        assert_eq!(
+            plane.samples[..12],
+            [50, 50, 50, 50, 50, 50, 50, 50, 150, 150, 150, 150]
+        );
+    }
+
+    #[test]
+    fn test_progressive_is_unsupported() {
+        let data = [
+            vec![0xFF, SOI],
+            segment(0xC2, &[8, 0, 8, 0, 8, 1, 1, 0x11, 0]),
+        ]
+        .concat();
+
+        let err = decode_jpeg(&data).unwrap_err();
+        assert!(err.to_string().contains("progressive"), "{err}");
+    }
+}
@@ -0,0 +1,80 @@
+/// `SOF0`/`SOF1`, ITU-T T.81 B.2.2
+#[derive(Debug, Clone)]
+pub struct FrameHeader {
+    pub kind: FrameKind,
+    /// sample precision in bits, 8 or 12
+    pub precision: u8,
+    pub height: u16,
+    pub width: u16,
+    pub components: Box<[FrameComponent]>,
+}
+
+impl FrameHeader {
+    pub fn max_horizontal_sampling(&self) -> u8 {
+        self.components
+            .iter()
+            .map(|c| c.horizontal_sampling)
+            .max()
+            .unwrap_or(1)
+    }
+
+    pub fn max_vertical_sampling(&self) -> u8 {
+        self.components
+            .iter()
+            .map(|c| c.vertical_sampling)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// The size of `component` in samples, A.1.1.
+    pub fn component_size(&self, component: &FrameComponent) -> (u32, u32) {
+        let scale =
+            |size: u16, sampling: u8, max: u8| (size as u32 * sampling as u32).div_ceil(max as u32);
+
+        (
+            scale(
+                self.width,
+                component.horizontal_sampling,
+                self.max_horizontal_sampling(),
+            ),
+            scale(
+                self.height,
+                component.vertical_sampling,
+                self.max_vertical_sampling(),
+            ),
+        )
+    }
+}
+
+/// The coding processes we decode: Huffman coded and sequential.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// `SOF0`, 8-bit samples and at most two tables of each kind
+    Baseline,
+    /// `SOF1`, 8 or 12-bit samples and up to four tables of each kind
+    ExtendedSequential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameComponent {
+    pub id: u8,
+    pub horizontal_sampling: u8,
+    pub vertical_sampling: u8,
+    pub quantization_table: u8,
+}
+
+/// A decoded JPEG, one plane per frame component at its own (possibly subsampled) size.
+#[derive(Debug)]
+pub struct JpegImage {
+    pub frame: FrameHeader,
+    pub planes: Box<[ComponentPlane]>,
+}
+
+#[derive(Debug)]
+pub struct ComponentPlane {
+    pub id: u8,
+    pub width: u32,
+    pub height: u32,
+    /// row-major, `width` samples per row
+    pub samples: Box<[u16]>,
+}
@@ -0,0 +1,5 @@
+mod decoder;
+pub mod grammar;
+
+pub use decoder::*;
+pub use grammar::*;
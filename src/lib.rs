@@ -1,11 +1,16 @@
 #![warn(clippy::nursery)]
 
+mod error;
 mod impl_read;
 
+pub mod av1;
 pub mod cabac;
 pub mod heic;
 pub mod heif;
 pub mod hevc;
+pub mod jpeg;
+pub mod vvc;
 
-pub use heic::HeicDecoder;
-pub use heif::HeifReader;
+pub use error::{ErrorLocation, HeifError};
+pub use heic::{HeicDecoder, ItemDecoder, PlanarImage};
+pub use heif::{DecodeBudget, DecodeLimits, HeifReader, ParseOptions};
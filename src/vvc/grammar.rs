@@ -0,0 +1,309 @@
+use crate::hevc::{ChromaFormat, RawNalUnit};
+
+/// `vvcC`, ISO/IEC 14496-15 11.2.4.2
+#[derive(Debug)]
+pub struct VvcDecoderConfigurationRecord {
+    pub length_size_minus_one: u8,
+    pub ptl: Option<VvcPtlInfo>,
+    pub arrays: Box<[VvcNalArray]>,
+    /// the first SPS in `arrays`, when the writer included one
+    pub sequence_parameter_set: Option<SequenceParameterSet>,
+}
+
+impl VvcDecoderConfigurationRecord {
+    /// The first NAL unit of `kind`, e.g. the SPS.
+    pub fn nal_unit(&self, kind: NalUnitKind) -> Option<&RawNalUnit> {
+        self.arrays
+            .iter()
+            .find(|a| a.nal_unit_type == kind)
+            .and_then(|a| a.nal_units.first())
+    }
+
+    /// From the SPS, else the record's own `chroma_format_idc`.
+    pub fn chroma_format(&self) -> Option<ChromaFormat> {
+        match (&self.sequence_parameter_set, &self.ptl) {
+            (Some(sps), _) => Some(sps.chroma_format),
+            (None, Some(ptl)) => ChromaFormat::try_from(ptl.chroma_format_idc as u32).ok(),
+            (None, None) => None,
+        }
+    }
+
+    /// From the SPS, else the record's own `bit_depth_minus8`.
+    pub const fn bit_depth(&self) -> Option<u8> {
+        match (&self.sequence_parameter_set, &self.ptl) {
+            (Some(sps), _) => Some(sps.bit_depth()),
+            (None, Some(ptl)) => Some(ptl.bit_depth_minus8 + 8),
+            (None, None) => None,
+        }
+    }
+
+    /// From the SPS profile_tier_level(), else the record's native PTL.
+    pub fn profile(&self) -> Option<Profile> {
+        self.sequence_parameter_set
+            .as_ref()
+            .and_then(|sps| sps.profile_tier_level.as_ref())
+            .map(ProfileTierLevel::profile)
+            .or_else(|| {
+                self.ptl
+                    .as_ref()
+                    .map(|ptl| Profile::from(ptl.native_ptl.general_profile_idc))
+            })
+    }
+}
+
+/// The part of `vvcC` only present when `ptl_present_flag` is set.
+#[derive(Debug)]
+pub struct VvcPtlInfo {
+    pub ols_idx: u16,
+    pub num_sublayers: u8,
+    pub constant_frame_rate: u8,
+    pub chroma_format_idc: u8,
+    pub bit_depth_minus8: u8,
+    pub native_ptl: VvcPtlRecord,
+    pub max_picture_width: u16,
+    pub max_picture_height: u16,
+    pub avg_frame_rate: u16,
+}
+
+/// `VvcPTLRecord`, the profile_tier_level() of the output layer set.
+#[derive(Debug)]
+pub struct VvcPtlRecord {
+    pub general_profile_idc: u8,
+    pub general_tier_flag: bool,
+    pub general_level_idc: u8,
+    pub ptl_frame_only_constraint_flag: bool,
+    pub ptl_multi_layer_enabled_flag: bool,
+    /// the remaining general_constraint_info bits, as stored
+    pub general_constraint_info: Box<[u8]>,
+    /// indexed by sublayer, `None` where not present
+    pub sublayer_level_idc: Box<[Option<u8>]>,
+    pub general_sub_profile_idc: Box<[u32]>,
+}
+
+#[derive(Debug)]
+pub struct VvcNalArray {
+    pub array_completeness: bool,
+    pub nal_unit_type: NalUnitKind,
+    pub nal_units: Box<[RawNalUnit]>,
+}
+
+/// nal_unit_type, H.266 table 5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NalUnitKind {
+    Trail,
+    Stsa,
+    Radl,
+    Rasl,
+    IdrWRadl,
+    IdrNLp,
+    Cra,
+    Gdr,
+    /// operating point information
+    Opi,
+    /// decoding capability information
+    Dci,
+    Vps,
+    Sps,
+    Pps,
+    PrefixAps,
+    SuffixAps,
+    /// picture header
+    Ph,
+    Aud,
+    Eos,
+    Eob,
+    PrefixSei,
+    SuffixSei,
+    Fd,
+    Reserved(u8),
+    Unspecified(u8),
+}
+
+impl NalUnitKind {
+    pub const fn is_irap(&self) -> bool {
+        matches!(self, Self::IdrWRadl | Self::IdrNLp | Self::Cra)
+    }
+}
+
+impl From<u8> for NalUnitKind {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Trail,
+            1 => Self::Stsa,
+            2 => Self::Radl,
+            3 => Self::Rasl,
+            7 => Self::IdrWRadl,
+            8 => Self::IdrNLp,
+            9 => Self::Cra,
+            10 => Self::Gdr,
+            12 => Self::Opi,
+            13 => Self::Dci,
+            14 => Self::Vps,
+            15 => Self::Sps,
+            16 => Self::Pps,
+            17 => Self::PrefixAps,
+            18 => Self::SuffixAps,
+            19 => Self::Ph,
+            20 => Self::Aud,
+            21 => Self::Eos,
+            22 => Self::Eob,
+            23 => Self::PrefixSei,
+            24 => Self::SuffixSei,
+            25 => Self::Fd,
+            28..=31 => Self::Unspecified(value),
+            other => Self::Reserved(other),
+        }
+    }
+}
+
+/// The two byte nal_unit_header(), H.266 7.3.1.2
+#[derive(Debug, Clone, Copy)]
+pub struct NalUnitHeader(pub u16);
+
+impl NalUnitHeader {
+    pub const fn forbidden_zero_bit(&self) -> bool {
+        (self.0 & 0x8000) != 0
+    }
+
+    pub const fn nuh_layer_id(&self) -> u8 {
+        ((self.0 >> 8) & 0x3F) as u8
+    }
+
+    pub fn nal_unit_type(&self) -> NalUnitKind {
+        NalUnitKind::from(((self.0 >> 3) & 0x1F) as u8)
+    }
+
+    pub const fn nuh_temporal_id_plus1(&self) -> u8 {
+        (self.0 & 0x07) as u8
+    }
+}
+
+/// general_profile_idc, H.266 A.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Main10,
+    Main10StillPicture,
+    Main10_444,
+    Main10_444StillPicture,
+    MultilayerMain10,
+    MultilayerMain10_444,
+    Main12,
+    Main12Intra,
+    Main12StillPicture,
+    Main12_444,
+    Main12_444Intra,
+    Main12_444StillPicture,
+    Main16_444,
+    Main16_444Intra,
+    Main16_444StillPicture,
+    Other(u8),
+}
+
+impl From<u8> for Profile {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Main10,
+            65 => Self::Main10StillPicture,
+            33 => Self::Main10_444,
+            97 => Self::Main10_444StillPicture,
+            17 => Self::MultilayerMain10,
+            49 => Self::MultilayerMain10_444,
+            2 => Self::Main12,
+            10 => Self::Main12Intra,
+            66 => Self::Main12StillPicture,
+            34 => Self::Main12_444,
+            42 => Self::Main12_444Intra,
+            98 => Self::Main12_444StillPicture,
+            36 => Self::Main16_444,
+            44 => Self::Main16_444Intra,
+            100 => Self::Main16_444StillPicture,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// profile_tier_level(), H.266 7.3.3.1, without the constraint flags
+#[derive(Debug, Clone)]
+pub struct ProfileTierLevel {
+    pub general_profile_idc: u8,
+    pub general_tier_flag: bool,
+    pub general_level_idc: u8,
+    pub ptl_frame_only_constraint_flag: bool,
+    pub ptl_multilayer_enabled_flag: bool,
+    pub general_sub_profile_idc: Box<[u32]>,
+}
+
+impl ProfileTierLevel {
+    pub fn profile(&self) -> Profile {
+        Profile::from(self.general_profile_idc)
+    }
+}
+
+/// The start of video_parameter_set_rbsp(), H.266 7.3.2.3
+#[derive(Debug)]
+pub struct VideoParameterSet {
+    pub vps_video_parameter_set_id: u8,
+    pub vps_max_layers_minus1: u8,
+    pub vps_max_sublayers_minus1: u8,
+    pub vps_all_independent_layers_flag: bool,
+    pub vps_layer_id: Box<[u8]>,
+}
+
+/// The start of seq_parameter_set_rbsp(), up to the bit depth, H.266 7.3.2.4
+#[derive(Debug)]
+pub struct SequenceParameterSet {
+    pub sps_seq_parameter_set_id: u8,
+    pub sps_video_parameter_set_id: u8,
+    pub sps_max_sublayers_minus1: u8,
+    pub chroma_format: ChromaFormat,
+    pub sps_log2_ctu_size_minus5: u8,
+    /// only in SPSs with sps_ptl_dpb_hrd_params_present_flag
+    pub profile_tier_level: Option<ProfileTierLevel>,
+    pub sps_pic_width_max_in_luma_samples: u32,
+    pub sps_pic_height_max_in_luma_samples: u32,
+    /// left, right, top, bottom, in chroma samples
+    pub conformance_window: [u32; 4],
+    pub sps_num_subpics_minus1: u32,
+    pub sps_bitdepth_minus8: u8,
+}
+
+impl SequenceParameterSet {
+    pub const fn bit_depth(&self) -> u8 {
+        self.sps_bitdepth_minus8 + 8
+    }
+
+    pub const fn ctb_size(&self) -> u32 {
+        1 << (self.sps_log2_ctu_size_minus5 + 5)
+    }
+
+    /// The size of the output picture: the maximum picture size less the conformance window.
+    pub const fn cropped_size(&self) -> (u32, u32) {
+        let (sub_width, sub_height) = match self.chroma_format {
+            ChromaFormat::YUV420 => (2, 2),
+            ChromaFormat::YUV422 => (2, 1),
+            ChromaFormat::Monochrome | ChromaFormat::YUV444 => (1, 1),
+        };
+        let [left, right, top, bottom] = self.conformance_window;
+
+        (
+            self.sps_pic_width_max_in_luma_samples
+                .saturating_sub(sub_width * (left + right)),
+            self.sps_pic_height_max_in_luma_samples
+                .saturating_sub(sub_height * (top + bottom)),
+        )
+    }
+}
+
+/// The start of pic_parameter_set_rbsp(), H.266 7.3.2.5
+#[derive(Debug)]
+pub struct PictureParameterSet {
+    pub pps_pic_parameter_set_id: u8,
+    pub pps_seq_parameter_set_id: u8,
+    pub pps_mixed_nalu_types_in_pic_flag: bool,
+    pub pps_pic_width_in_luma_samples: u32,
+    pub pps_pic_height_in_luma_samples: u32,
+    /// left, right, top, bottom, in chroma samples
+    pub conformance_window: [u32; 4],
+    pub pps_output_flag_present_flag: bool,
+    pub pps_no_pic_partition_flag: bool,
+}
@@ -0,0 +1,5 @@
+pub mod grammar;
+mod parameter_set_reader;
+
+pub use grammar::*;
+pub use parameter_set_reader::*;
@@ -0,0 +1,352 @@
+use crate::hevc::{ChromaFormat, RbspReader};
+use crate::vvc::{
+    NalUnitHeader, PictureParameterSet, ProfileTierLevel, SequenceParameterSet, VideoParameterSet,
+};
+use anyhow::{Result, bail, ensure};
+
+/// Splits a NAL unit as stored in `vvcC` (no length prefix) into its header and RBSP.
+pub fn read_nal_unit(raw_nal_unit: &[u8]) -> Result<(NalUnitHeader, Vec<u8>)> {
+    match raw_nal_unit {
+        [header_1, header_2, rbsp @ ..] => {
+            let header = NalUnitHeader(u16::from_be_bytes([*header_1, *header_2]));
+            ensure!(!header.forbidden_zero_bit(), "forbidden_zero_bit is set");
+            Ok((header, RbspReader::remove_emulation_prevention(rbsp)))
+        }
+        _ => bail!("nal unit is too short"),
+    }
+}
+
+pub fn video_parameter_set_rbsp(data: &[u8]) -> Result<VideoParameterSet> {
+    let mut reader = RbspReader::new(data);
+
+    let vps_video_parameter_set_id = reader.read_u8(4)?;
+    let vps_max_layers_minus1 = reader.read_u8(6)?;
+    let vps_max_sublayers_minus1 = reader.read_u8(3)?;
+    ensure!(
+        vps_max_sublayers_minus1 <= 6,
+        "invalid vps_max_sublayers_minus1 {vps_max_sublayers_minus1}"
+    );
+
+    if vps_max_layers_minus1 > 0 && vps_max_sublayers_minus1 > 0 {
+        let _vps_default_ptl_dpb_hrd_max_tid_flag = reader.read_flag()?;
+    }
+
+    let vps_all_independent_layers_flag = if vps_max_layers_minus1 > 0 {
+        reader.read_flag()?
+    } else {
+        true
+    };
+
+    let mut vps_layer_id = Vec::with_capacity(vps_max_layers_minus1 as usize + 1);
+    for i in 0..=vps_max_layers_minus1 as usize {
+        vps_layer_id.push(reader.read_u8(6)?);
+
+        if i > 0 && !vps_all_independent_layers_flag {
+            let vps_independent_layer_flag = reader.read_flag()?;
+            if !vps_independent_layer_flag {
+                let vps_max_tid_ref_present_flag = reader.read_flag()?;
+                for _ in 0..i {
+                    let vps_direct_ref_layer_flag = reader.read_flag()?;
+                    if vps_direct_ref_layer_flag && vps_max_tid_ref_present_flag {
+                        let _vps_max_tid_il_ref_pics_plus1 = reader.read_u8(3)?;
+                    }
+                }
+            }
+        }
+    }
+
+    // the output layer sets, PTLs, DPB and HRD parameters follow; heif only needs the layers
+
+    Ok(VideoParameterSet {
+        vps_video_parameter_set_id,
+        vps_max_layers_minus1,
+        vps_max_sublayers_minus1,
+        vps_all_independent_layers_flag,
+        vps_layer_id: vps_layer_id.into_boxed_slice(),
+    })
+}
+
+pub fn sequence_parameter_set_rbsp(data: &[u8]) -> Result<SequenceParameterSet> {
+    let mut reader = RbspReader::new(data);
+
+    let sps_seq_parameter_set_id = reader.read_u8(4)?;
+    let sps_video_parameter_set_id = reader.read_u8(4)?;
+    let sps_max_sublayers_minus1 = reader.read_u8(3)?;
+    ensure!(
+        sps_max_sublayers_minus1 <= 6,
+        "invalid sps_max_sublayers_minus1 {sps_max_sublayers_minus1}"
+    );
+
+    let chroma_format = ChromaFormat::try_from(reader.read_u32(2)?)?;
+    let sps_log2_ctu_size_minus5 = reader.read_u8(2)?;
+    ensure!(
+        sps_log2_ctu_size_minus5 <= 2,
+        "invalid sps_log2_ctu_size_minus5 {sps_log2_ctu_size_minus5}"
+    );
+
+    let sps_ptl_dpb_hrd_params_present_flag = reader.read_flag()?;
+    let profile_tier_level = sps_ptl_dpb_hrd_params_present_flag
+        .then(|| profile_tier_level(&mut reader, true, sps_max_sublayers_minus1))
+        .transpose()?;
+
+    let _sps_gdr_enabled_flag = reader.read_flag()?;
+    let sps_ref_pic_resampling_enabled_flag = reader.read_flag()?;
+    if sps_ref_pic_resampling_enabled_flag {
+        let _sps_res_change_in_clvs_allowed_flag = reader.read_flag()?;
+    }
+
+    let sps_pic_width_max_in_luma_samples = reader.read_ue()?;
+    let sps_pic_height_max_in_luma_samples = reader.read_ue()?;
+
+    // same bound as the hevc SPS, so derived sizes can't overflow
+    ensure!(
+        (1..=u16::MAX as u32).contains(&sps_pic_width_max_in_luma_samples)
+            && (1..=u16::MAX as u32).contains(&sps_pic_height_max_in_luma_samples),
+        "invalid picture size {sps_pic_width_max_in_luma_samples}x{sps_pic_height_max_in_luma_samples}"
+    );
+
+    let conformance_window = conformance_window(&mut reader)?;
+
+    let ctb_size = 1 << (sps_log2_ctu_size_minus5 + 5);
+    let sps_num_subpics_minus1 = skip_subpic_info(
+        &mut reader,
+        sps_pic_width_max_in_luma_samples,
+        sps_pic_height_max_in_luma_samples,
+        ctb_size,
+    )?;
+
+    let sps_bitdepth_minus8 = reader.read_ue()?;
+    ensure!(
+        sps_bitdepth_minus8 <= 8,
+        "invalid sps_bitdepth_minus8 {sps_bitdepth_minus8}"
+    );
+
+    // everything after the bit depth is coding tools, which nothing here looks at yet
+
+    Ok(SequenceParameterSet {
+        sps_seq_parameter_set_id,
+        sps_video_parameter_set_id,
+        sps_max_sublayers_minus1,
+        chroma_format,
+        sps_log2_ctu_size_minus5,
+        profile_tier_level,
+        sps_pic_width_max_in_luma_samples,
+        sps_pic_height_max_in_luma_samples,
+        conformance_window,
+        sps_num_subpics_minus1,
+        sps_bitdepth_minus8: sps_bitdepth_minus8 as u8,
+    })
+}
+
+pub fn picture_parameter_set_rbsp(data: &[u8]) -> Result<PictureParameterSet> {
+    let mut reader = RbspReader::new(data);
+
+    let pps_pic_parameter_set_id = reader.read_u8(6)?;
+    let pps_seq_parameter_set_id = reader.read_u8(4)?;
+    let pps_mixed_nalu_types_in_pic_flag = reader.read_flag()?;
+    let pps_pic_width_in_luma_samples = reader.read_ue()?;
+    let pps_pic_height_in_luma_samples = reader.read_ue()?;
+
+    let conformance_window = conformance_window(&mut reader)?;
+
+    let pps_scaling_window_explicit_signalling_flag = reader.read_flag()?;
+    if pps_scaling_window_explicit_signalling_flag {
+        for _ in 0..4 {
+            let _pps_scaling_win_offset = reader.read_se()?;
+        }
+    }
+
+    let pps_output_flag_present_flag = reader.read_flag()?;
+    let pps_no_pic_partition_flag = reader.read_flag()?;
+
+    Ok(PictureParameterSet {
+        pps_pic_parameter_set_id,
+        pps_seq_parameter_set_id,
+        pps_mixed_nalu_types_in_pic_flag,
+        pps_pic_width_in_luma_samples,
+        pps_pic_height_in_luma_samples,
+        conformance_window,
+        pps_output_flag_present_flag,
+        pps_no_pic_partition_flag,
+    })
+}
+
+// 7.3.3.1
+pub fn profile_tier_level(
+    reader: &mut RbspReader,
+    profile_tier_present_flag: bool,
+    max_num_sublayers_minus1: u8,
+) -> Result<ProfileTierLevel> {
+    let (general_profile_idc, general_tier_flag) = if profile_tier_present_flag {
+        (reader.read_u8(7)?, reader.read_flag()?)
+    } else {
+        (0, false)
+    };
+
+    let general_level_idc = reader.read_u8(8)?;
+    let ptl_frame_only_constraint_flag = reader.read_flag()?;
+    let ptl_multilayer_enabled_flag = reader.read_flag()?;
+
+    if profile_tier_present_flag {
+        skip_general_constraints_info(reader)?;
+    }
+
+    ensure!(
+        max_num_sublayers_minus1 <= 6,
+        "invalid MaxNumSubLayersMinus1 {max_num_sublayers_minus1}"
+    );
+    let mut ptl_sublayer_level_present_flag = [false; 6];
+    for flag in ptl_sublayer_level_present_flag[..max_num_sublayers_minus1 as usize]
+        .iter_mut()
+        .rev()
+    {
+        *flag = reader.read_flag()?;
+    }
+
+    while !reader.is_byte_aligned() {
+        let _ptl_reserved_zero_bit = reader.read_flag()?;
+    }
+
+    for present in ptl_sublayer_level_present_flag[..max_num_sublayers_minus1 as usize]
+        .iter()
+        .rev()
+    {
+        if *present {
+            let _sublayer_level_idc = reader.read_u8(8)?;
+        }
+    }
+
+    let general_sub_profile_idc = if profile_tier_present_flag {
+        let ptl_num_sub_profiles = reader.read_u8(8)?;
+        (0..ptl_num_sub_profiles)
+            .map(|_| reader.read_u32(32))
+            .collect::<Result<_>>()?
+    } else {
+        Box::default()
+    };
+
+    Ok(ProfileTierLevel {
+        general_profile_idc,
+        general_tier_flag,
+        general_level_idc,
+        ptl_frame_only_constraint_flag,
+        ptl_multilayer_enabled_flag,
+        general_sub_profile_idc,
+    })
+}
+
+// 7.3.3.2, the individual constraint flags aren't of interest
+fn skip_general_constraints_info(reader: &mut RbspReader) -> Result<()> {
+    let gci_present_flag = reader.read_flag()?;
+    if gci_present_flag {
+        // gci_intra_only_constraint_flag through gci_no_virtual_boundaries_constraint_flag
+        reader.read_bits(32)?;
+        reader.read_bits(32)?;
+        reader.read_bits(7)?;
+
+        let gci_num_additional_bits = reader.read_u8(8)?;
+        for _ in 0..gci_num_additional_bits {
+            let _gci_additional_bit = reader.read_flag()?;
+        }
+    }
+
+    while !reader.is_byte_aligned() {
+        let _gci_alignment_zero_bit = reader.read_flag()?;
+    }
+
+    Ok(())
+}
+
+// the subpicture layout in 7.3.2.4, returning sps_num_subpics_minus1
+fn skip_subpic_info(
+    reader: &mut RbspReader,
+    pic_width_max: u32,
+    pic_height_max: u32,
+    ctb_size: u32,
+) -> Result<u32> {
+    let sps_subpic_info_present_flag = reader.read_flag()?;
+    if !sps_subpic_info_present_flag {
+        return Ok(0);
+    }
+
+    let sps_num_subpics_minus1 = reader.read_ue()?;
+    let width_in_ctbs = pic_width_max.div_ceil(ctb_size);
+    let height_in_ctbs = pic_height_max.div_ceil(ctb_size);
+    ensure!(
+        sps_num_subpics_minus1 < width_in_ctbs * height_in_ctbs,
+        "invalid sps_num_subpics_minus1 {sps_num_subpics_minus1}"
+    );
+
+    let (sps_independent_subpics_flag, sps_subpic_same_size_flag) = if sps_num_subpics_minus1 > 0 {
+        (reader.read_flag()?, reader.read_flag()?)
+    } else {
+        (true, false)
+    };
+
+    // Ceil(Log2(n)) bits
+    let bits = |n: u32| (32 - (n - 1).leading_zeros()) as usize;
+    let x_bits = bits(width_in_ctbs);
+    let y_bits = bits(height_in_ctbs);
+
+    for i in 0..=sps_num_subpics_minus1 {
+        if sps_num_subpics_minus1 == 0 {
+            break;
+        }
+
+        if !sps_subpic_same_size_flag || i == 0 {
+            let last = i == sps_num_subpics_minus1;
+
+            if i > 0 && pic_width_max > ctb_size {
+                let _sps_subpic_ctu_top_left_x = reader.read_bits(x_bits)?;
+            }
+            if i > 0 && pic_height_max > ctb_size {
+                let _sps_subpic_ctu_top_left_y = reader.read_bits(y_bits)?;
+            }
+            if !last && pic_width_max > ctb_size {
+                let _sps_subpic_width_minus1 = reader.read_bits(x_bits)?;
+            }
+            if !last && pic_height_max > ctb_size {
+                let _sps_subpic_height_minus1 = reader.read_bits(y_bits)?;
+            }
+        }
+
+        if !sps_independent_subpics_flag {
+            let _sps_subpic_treated_as_pic_flag = reader.read_flag()?;
+            let _sps_loop_filter_across_subpic_enabled_flag = reader.read_flag()?;
+        }
+    }
+
+    let sps_subpic_id_len_minus1 = reader.read_ue()?;
+    ensure!(
+        sps_subpic_id_len_minus1 <= 15,
+        "invalid sps_subpic_id_len_minus1 {sps_subpic_id_len_minus1}"
+    );
+
+    let sps_subpic_id_mapping_explicitly_signalled_flag = reader.read_flag()?;
+    if sps_subpic_id_mapping_explicitly_signalled_flag {
+        let sps_subpic_id_mapping_present_flag = reader.read_flag()?;
+        if sps_subpic_id_mapping_present_flag {
+            for _ in 0..=sps_num_subpics_minus1 {
+                let _sps_subpic_id = reader.read_bits(sps_subpic_id_len_minus1 as usize + 1)?;
+            }
+        }
+    }
+
+    Ok(sps_num_subpics_minus1)
+}
+
+// the four conf_win offsets behind a conformance_window_flag
+fn conformance_window(reader: &mut RbspReader) -> Result<[u32; 4]> {
+    let conformance_window_flag = reader.read_flag()?;
+
+    if conformance_window_flag {
+        Ok([
+            reader.read_ue()?,
+            reader.read_ue()?,
+            reader.read_ue()?,
+            reader.read_ue()?,
+        ])
+    } else {
+        Ok([0; 4])
+    }
+}
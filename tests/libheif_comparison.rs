@@ -33,10 +33,12 @@ fn cross_check_with_libheif() {
 
     let iprp = heif
         .meta_box
+        .as_ref()
+        .expect("No meta")
         .item_properties
         .as_ref()
         .expect("No properties");
-    let primary_id = heif.primary_item_id();
+    let primary_id = heif.primary_item_id().expect("No primary item");
 
     let primary_assoc = iprp
         .association
@@ -75,6 +77,8 @@ fn cross_check_with_libheif() {
 
     let our_num_thumbnails = heif
         .meta_box
+        .as_ref()
+        .expect("No meta")
         .item_references
         .as_ref()
         .map(|iref| {